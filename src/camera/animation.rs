@@ -0,0 +1,151 @@
+use crate::camera::camera::CameraBuilder;
+use crate::math::point3::Point3;
+use std::ops::{Add, Mul, Sub};
+
+/// The camera's placement and lens at one moment of an animation.
+#[derive(Copy, Clone)]
+pub struct CameraKeyframe {
+    pub time: f64,
+    pub look_from: Point3,
+    pub look_at: Point3,
+    pub fov: f64,
+    pub focus_dist: f64,
+    pub defocus_angle: f64,
+}
+
+impl CameraKeyframe {
+    pub fn new(
+        time: f64,
+        look_from: Point3,
+        look_at: Point3,
+        fov: f64,
+        focus_dist: f64,
+        defocus_angle: f64,
+    ) -> Self {
+        Self {
+            time,
+            look_from,
+            look_at,
+            fov,
+            focus_dist,
+            defocus_angle,
+        }
+    }
+}
+
+/// How values between keyframes are filled in.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Interpolation {
+    /// Straight lines, which turn sharply at each keyframe.
+    #[default]
+    Linear,
+    /// A Catmull-Rom spline through the keyframes, which passes through each
+    /// one smoothly, heading from the keyframe before it towards the one
+    /// after, its slopes taking account of unevenly spaced keyframes.
+    CatmullRom,
+}
+
+/// Camera parameters that change over time, held before the first keyframe
+/// and after the last.
+pub struct CameraAnimation {
+    keyframes: Vec<CameraKeyframe>,
+    interpolation: Interpolation,
+}
+
+impl CameraAnimation {
+    pub fn new(mut keyframes: Vec<CameraKeyframe>, interpolation: Interpolation) -> Self {
+        assert!(
+            !keyframes.is_empty(),
+            "an animation needs at least one keyframe"
+        );
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        Self {
+            keyframes,
+            interpolation,
+        }
+    }
+
+    /// The camera's parameters at `time`.
+    pub fn at(&self, time: f64) -> CameraKeyframe {
+        let first = &self.keyframes[0];
+        let last = &self.keyframes[self.keyframes.len() - 1];
+        if time <= first.time {
+            return CameraKeyframe { time, ..*first };
+        }
+        if time >= last.time {
+            return CameraKeyframe { time, ..*last };
+        }
+
+        // Ties can't be picked: a time at a repeated keyframe's time ends
+        // the segment before it.
+        let i = self
+            .keyframes
+            .windows(2)
+            .position(|pair| time <= pair[1].time)
+            .unwrap();
+
+        CameraKeyframe {
+            time,
+            look_from: self.interpolate(i, time, |keyframe| keyframe.look_from),
+            look_at: self.interpolate(i, time, |keyframe| keyframe.look_at),
+            fov: self.interpolate(i, time, |keyframe| keyframe.fov),
+            focus_dist: self.interpolate(i, time, |keyframe| keyframe.focus_dist),
+            defocus_angle: self.interpolate(i, time, |keyframe| keyframe.defocus_angle),
+        }
+    }
+
+    /// `builder` with the camera's placement and lens at `time`.
+    pub fn builder_at(&self, builder: &CameraBuilder, time: f64) -> CameraBuilder {
+        let keyframe = self.at(time);
+
+        builder
+            .clone()
+            .look_from(keyframe.look_from)
+            .look_at(keyframe.look_at)
+            .fov(keyframe.fov)
+            .focus_dist(keyframe.focus_dist)
+            .defocus_angle(keyframe.defocus_angle)
+    }
+
+    /// One parameter at `time`, between keyframes `i` and `i + 1`.
+    fn interpolate<T, F>(&self, i: usize, time: f64, value: F) -> T
+    where
+        T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f64, Output = T>,
+        F: Fn(&CameraKeyframe) -> T,
+    {
+        let (a, b) = (&self.keyframes[i], &self.keyframes[i + 1]);
+        let span = b.time - a.time;
+        let t = (time - a.time) / span;
+        let (p0, p1) = (value(a), value(b));
+
+        match self.interpolation {
+            Interpolation::Linear => p0 + (p1 - p0) * t,
+            Interpolation::CatmullRom => {
+                // A cubic Hermite segment, with the slopes per unit of time
+                // scaled to the segment's length.
+                let m0 = self.slope(i, &value) * span;
+                let m1 = self.slope(i + 1, &value) * span;
+                let (t2, t3) = (t * t, t * t * t);
+
+                p0 * (2.0 * t3 - 3.0 * t2 + 1.0)
+                    + m0 * (t3 - 2.0 * t2 + t)
+                    + p1 * (3.0 * t2 - 2.0 * t3)
+                    + m1 * (t3 - t2)
+            }
+        }
+    }
+
+    /// The rate of change at keyframe `i`, from its neighbours, or from the
+    /// next or previous one at the ends.
+    fn slope<T, F>(&self, i: usize, value: &F) -> T
+    where
+        T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f64, Output = T>,
+        F: Fn(&CameraKeyframe) -> T,
+    {
+        let before = &self.keyframes[i.saturating_sub(1)];
+        let after = &self.keyframes[(i + 1).min(self.keyframes.len() - 1)];
+
+        (value(after) - value(before)) * (1.0 / (after.time - before.time))
+    }
+}
@@ -0,0 +1,92 @@
+use crate::images::error::ImageError;
+use crate::images::image::{ColorSpace, Image};
+use crate::images::loader::load_image;
+use crate::math::distribution::Distribution2D;
+use crate::math::random;
+use crate::math::vector3::{random_in_unit_disk, Vector3};
+use rand::Rng;
+use std::f64::consts::PI;
+use std::path::Path;
+use std::sync::Arc;
+
+/// The shape of the thin lens's opening, which out-of-focus highlights take
+/// on, sized to the circle `defocus_angle` sets.
+#[derive(Clone, Default)]
+pub enum Aperture {
+    #[default]
+    Circle,
+    /// A regular polygon of `blades` sides, as an iris of straight blades
+    /// makes, turned by `rotation` degrees. Fewer than three blades give a
+    /// circle.
+    Polygon { blades: u32, rotation: f64 },
+    /// Any shape, from a grayscale image.
+    Image(Arc<ApertureMask>),
+}
+
+impl Aperture {
+    /// A random point on the aperture, within the unit disk in x and y.
+    pub(crate) fn sample(&self) -> Vector3 {
+        match self {
+            Aperture::Circle => random_in_unit_disk(),
+            Aperture::Polygon { blades, .. } if *blades < 3 => random_in_unit_disk(),
+            Aperture::Polygon { blades, rotation } => {
+                let mut rng = random::rng();
+                let (u, v, w) = (
+                    rng.random_range(0.0..1.0),
+                    rng.random_range(0.0..1.0),
+                    rng.random_range(0.0..1.0),
+                );
+
+                // Every blade's triangle from the centre has the same area.
+                let blade = ((u * *blades as f64) as u32).min(blades - 1);
+                let step = 2.0 * PI / *blades as f64;
+                let start = rotation.to_radians() + blade as f64 * step;
+                let (a, b) = (start.sin_cos(), (start + step).sin_cos());
+
+                let (mut s, mut t) = (v, w);
+                if s + t > 1.0 {
+                    (s, t) = (1.0 - s, 1.0 - t);
+                }
+                Vector3::new(s * a.1 + t * b.1, s * a.0 + t * b.0, 0.0)
+            }
+            Aperture::Image(mask) => mask.sample(),
+        }
+    }
+}
+
+/// An aperture shape from an image: brighter pixels let more light
+/// through. The image is stretched over the square around the aperture's
+/// circle, top up.
+pub struct ApertureMask {
+    distribution: Distribution2D,
+}
+
+impl ApertureMask {
+    pub fn new(image: &Image) -> Self {
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        assert!(width > 0 && height > 0, "aperture image is empty");
+
+        let mut weights = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                weights.push(image.pixel(x as u32, y as u32).luminance());
+            }
+        }
+
+        Self {
+            distribution: Distribution2D::new(&weights, width, height),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ImageError> {
+        Ok(Self::new(&load_image(path.as_ref(), ColorSpace::Srgb)?))
+    }
+
+    fn sample(&self) -> Vector3 {
+        let mut rng = random::rng();
+        let (u, v) = (rng.random_range(0.0..1.0), rng.random_range(0.0..1.0));
+        let ((x, y), _) = self.distribution.sample(u, v);
+
+        Vector3::new(2.0 * x - 1.0, 1.0 - 2.0 * y, 0.0)
+    }
+}
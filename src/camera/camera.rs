@@ -1,28 +1,45 @@
+use crate::camera::aperture::Aperture;
+use crate::camera::crop::Crop;
+use crate::camera::lens::LensSystem;
+use crate::camera::projection::Projection;
+use crate::camera::stereo::Stereo;
+use crate::lights::background::Background;
+use crate::lights::light::Light;
+use crate::lights::light_sampler::{LightSampling, Lighting};
 use crate::math::color::{write_color, Color};
 use crate::math::point3::Point3;
+use crate::math::random;
 use crate::math::ray::{ray_color, Ray};
-use crate::math::vector3::{cross, random_in_unit_disk, unit_vector, Vector3};
+use crate::math::spectrum::Wavelengths;
+use crate::math::vector3::{cross, dot, unit_vector, Vector3};
 use crate::objects::hittable::Hittable;
 use rand::Rng;
 use std::io;
 use std::io::Write;
+use std::sync::Arc;
 
 pub struct Camera {
-    aspect_ratio: f64,
     width: u32,
     samples_per_pixel: u32,
     max_depth: u32,
 
-    fov: f64,
-    look_from: Point3,
-    look_at: Point3,
-    up: Vector3,
+    projection: Projection,
 
     defocus_angle: f64,
     focus_dist: f64,
+    aperture: Aperture,
+
+    shutter_open: f64,
+    shutter_close: f64,
+
+    stereo: Option<Stereo>,
+    crop: Option<Crop>,
+    spectral: bool,
+    background: Background,
+    lights: Vec<Arc<dyn Light>>,
+    light_sampling: LightSampling,
 
     height: u32,
-    pixel_samples_scale: f64,
     center: Point3,
     pixel00_loc: Point3,
 
@@ -35,6 +52,11 @@ pub struct Camera {
 
     defocus_disk_u: Vector3,
     defocus_disk_v: Vector3,
+    /// Normal of the plane of focus when tilt or swing turn it away from
+    /// facing the camera.
+    focus_normal: Option<Vector3>,
+
+    lens: Option<LensSystem>,
 }
 
 impl Camera {
@@ -47,11 +69,12 @@ impl Camera {
         // let focal_length = (builder.look_from - builder.look_at).length();
         let theta = builder.fov.to_radians();
         let h = (theta / 2.0).tan();
-        let viewport_height = 2.0 * h * builder.focus_dist;
+        let viewport_height = match builder.projection {
+            Projection::Orthographic { size } => size,
+            _ => 2.0 * h * builder.focus_dist,
+        };
         let viewport_width = viewport_height * (builder.width as f64 / height as f64);
 
-        let pixel_samples_scale = 1.0 / builder.samples_per_pixel as f64;
-
         let w = unit_vector(&(builder.look_from - builder.look_at));
         let u = unit_vector(&cross(&builder.up, &w));
         let v = cross(&w, &u);
@@ -62,25 +85,47 @@ impl Camera {
         let pixel_delta_u = viewport_u / builder.width as f64;
         let pixel_delta_v = viewport_v / height as f64;
 
-        let viewport_upper_left =
+        let mut viewport_upper_left =
             center - (builder.focus_dist * w) - viewport_u / 2.0 - viewport_v / 2.0;
+        if builder.shift != (0.0, 0.0) {
+            let (x, y) = builder.shift;
+            viewport_upper_left =
+                viewport_upper_left + x * viewport_width * u + y * viewport_height * v;
+        }
         let pixel00_loc = viewport_upper_left + 0.5 * (pixel_delta_u + pixel_delta_v);
 
         let defocus_radius = builder.focus_dist * (builder.defocus_angle / 2.0).to_radians().tan();
 
+        let focus_normal = (builder.tilt != 0.0 || builder.swing != 0.0).then(|| {
+            let tilt = builder.tilt.clamp(-89.0, 89.0).to_radians();
+            let swing = builder.swing.clamp(-89.0, 89.0).to_radians();
+            unit_vector(&(w + tilt.tan() * v + swing.tan() * u))
+        });
+
+        let lens = match &builder.projection {
+            Projection::Realistic(lens) => {
+                Some(lens.build(builder.focus_dist, builder.width as f64 / height as f64))
+            }
+            _ => None,
+        };
+
         Self {
-            aspect_ratio: builder.aspect_ratio,
             width: builder.width,
             samples_per_pixel: builder.samples_per_pixel,
             max_depth: builder.max_depth,
-            fov: builder.fov,
-            look_from: builder.look_from,
-            look_at: builder.look_at,
-            up: builder.up,
+            projection: builder.projection.clone(),
             defocus_angle: builder.defocus_angle,
             focus_dist: builder.focus_dist,
+            aperture: builder.aperture.clone(),
+            shutter_open: builder.shutter_open,
+            shutter_close: builder.shutter_close,
+            stereo: builder.stereo,
+            crop: builder.crop,
+            spectral: builder.spectral,
+            background: builder.background.clone(),
+            lights: builder.lights.clone(),
+            light_sampling: builder.light_sampling,
             height,
-            pixel_samples_scale,
             center,
             pixel00_loc,
             pixel_delta_u,
@@ -90,54 +135,225 @@ impl Camera {
             w,
             defocus_disk_u: u * defocus_radius,
             defocus_disk_v: v * defocus_radius,
+            focus_normal,
+            lens,
         }
     }
 
+    /// Renders the image, or the builder's crop of it, to standard output.
     pub(crate) fn render(&self, world: &dyn Hittable) {
-        println!("P3\n{} {}\n255", self.width, self.height);
+        self.render_to(world, &mut io::stdout().lock()).unwrap();
+    }
 
-        for j in 0..self.height {
-            eprint!("\rScanlines remaining: {}\x1b[K", self.height - j);
+    /// Renders the image, or the builder's crop of it, to `out`.
+    pub(crate) fn render_to(&self, world: &dyn Hittable, out: &mut dyn Write) -> io::Result<()> {
+        self.render_crop(world, self.crop.as_ref(), out)
+    }
+
+    /// Renders only the pixels in `crop`, or the whole image without one.
+    pub(crate) fn render_crop(
+        &self,
+        world: &dyn Hittable,
+        crop: Option<&Crop>,
+        out: &mut dyn Write,
+    ) -> io::Result<()> {
+        // Stereo renders tile one image per eye, each of the set size.
+        let (eyes_across, eyes_down) = self.stereo.map_or((1, 1), |stereo| stereo.layout.grid());
+        let (width, height) = (self.width * eyes_across, self.height * eyes_down);
+
+        let (x0, y0, x1, y1) = crop.map_or((0, 0, width, height), |crop| {
+            crop.window.bounds(width, height)
+        });
+        let (columns, rows) = if crop.is_none_or(|crop| crop.full_frame) {
+            (0..width, 0..height)
+        } else {
+            (x0..x1, y0..y1)
+        };
+        writeln!(out, "P3\n{} {}\n255", columns.len(), rows.len())?;
+        let lighting = Lighting::new(
+            self.background.clone(),
+            self.lights.clone(),
+            self.light_sampling,
+            &world.bounding_box(),
+        );
+        let radiance = |ray: &Ray| ray_color(ray, world, &lighting, self.max_depth);
+
+        for j in rows.clone() {
+            eprint!("\rScanlines remaining: {}\x1b[K", rows.end - j);
             io::stderr().flush().unwrap();
 
-            for i in 0..self.width {
+            for i in columns.clone() {
+                if !(x0..x1).contains(&i) || !(y0..y1).contains(&j) {
+                    write_color(out, &Color::black(), self.samples_per_pixel)?;
+                    continue;
+                }
+
+                // Every pixel draws from its own sequence, so it comes out
+                // the same whichever other pixels are rendered.
+                random::seed(u64::from(j) * u64::from(width) + u64::from(i));
+                let eye_offset = self.stereo.map_or(0.0, |stereo| {
+                    stereo.eye_offset(i / self.width + j / self.height)
+                });
+                let (i, j) = (i % self.width, j % self.height);
+
                 let mut pixel_color = Color::black();
                 for _sample in 0..self.samples_per_pixel {
-                    let ray = self.get_ray(i, j);
-                    pixel_color += ray_color(&ray, world, self.max_depth);
+                    let Some((ray, weight)) = self.get_ray(i, j, eye_offset) else {
+                        continue;
+                    };
+                    pixel_color += weight
+                        * if self.spectral {
+                            let u = random::rng().random_range(0.0..1.0);
+                            let wavelengths = Wavelengths::sample(u);
+                            let ray = ray.with_wavelengths(Some(wavelengths));
+                            wavelengths.radiance_to_rgb(&radiance(&ray))
+                        } else {
+                            radiance(&ray)
+                        };
                 }
 
-                write_color(&pixel_color, self.samples_per_pixel);
+                write_color(out, &pixel_color, self.samples_per_pixel)?;
             }
         }
         eprintln!("\rDone.");
+
+        Ok(())
     }
 
-    pub fn get_ray(&self, i: u32, j: u32) -> Ray {
+    /// A ray through a random point in pixel (i, j) and the weight of the
+    /// light it brings back, or none where the projection sees nothing, as
+    /// outside a fisheye's image circle. `eye_offset` moves the eye along
+    /// the camera's right axis for stereo.
+    pub fn get_ray(&self, i: u32, j: u32, eye_offset: f64) -> Option<(Ray, f64)> {
         let offset = sample_square();
         let pixel_sample = self.pixel00_loc
             + ((i as f64 + offset.x()) * self.pixel_delta_u)
             + ((j as f64 + offset.y()) * self.pixel_delta_v);
 
+        let mut weight = 1.0;
+        let (ray_origin, ray_direction) = match &self.projection {
+            Projection::Perspective => self.stereo_ray(&self.center, &pixel_sample, eye_offset),
+            Projection::Orthographic { .. } => {
+                // Each pixel has its own centre, straight back from its
+                // point on the focus plane.
+                let center = pixel_sample + self.focus_dist * self.w;
+                self.stereo_ray(&center, &pixel_sample, eye_offset)
+            }
+            Projection::Realistic(_) => {
+                let lens = self.lens.as_ref()?;
+                let x = (i as f64 + 0.5 + offset.x()) / self.width as f64;
+                let y = (j as f64 + 0.5 + offset.y()) / self.height as f64;
+                let mut rng = random::rng();
+                let (u, v) = (rng.random_range(0.0..1.0), rng.random_range(0.0..1.0));
+                let (origin, direction, lens_weight) = lens.sample_ray(x, y, u, v)?;
+                weight = lens_weight;
+                let origin = self.center + eye_offset * self.u + self.to_world(&origin);
+                (origin, self.to_world(&direction))
+            }
+            _ => {
+                let x = (i as f64 + 0.5 + offset.x()) / self.width as f64;
+                let y = (j as f64 + 0.5 + offset.y()) / self.height as f64;
+                let local =
+                    self.projection
+                        .direction(x, y, self.width as f64, self.height as f64)?;
+                if eye_offset == 0.0 {
+                    (self.center, self.to_world(&local))
+                } else {
+                    // Seen from the eye beside this direction, level with
+                    // it, as a head turned towards it would.
+                    let right = Vector3::new(-local.z(), 0.0, local.x());
+                    let length = right.length();
+                    let right = if length > 0.0 { right / length } else { right };
+                    let direction = local - (eye_offset / self.convergence()) * right;
+                    let origin = self.center + self.to_world(&(eye_offset * right));
+                    (origin, self.to_world(&direction))
+                }
+            }
+        };
+
+        Some((
+            Ray::new(ray_origin, ray_direction, self.sample_time()),
+            weight,
+        ))
+    }
+
+    /// Converts from the camera frame, x right, y up and z backwards.
+    fn to_world(&self, local: &Vector3) -> Vector3 {
+        local.x() * self.u + local.y() * self.v + local.z() * self.w
+    }
+
+    fn sample_time(&self) -> f64 {
+        if self.shutter_close <= self.shutter_open {
+            return self.shutter_open;
+        }
+
+        let mut rng = random::rng();
+        rng.random_range(self.shutter_open..self.shutter_close)
+    }
+
+    /// `thin_lens_ray` from the eye `eye_offset` to the right of `center`,
+    /// its view shifted to converge with the other's at the convergence
+    /// distance.
+    fn stereo_ray(
+        &self,
+        center: &Point3,
+        pixel_sample: &Point3,
+        eye_offset: f64,
+    ) -> (Point3, Vector3) {
+        if eye_offset == 0.0 {
+            return self.thin_lens_ray(center, pixel_sample);
+        }
+
+        let eye = *center + eye_offset * self.u;
+        let shift = eye_offset * (1.0 - self.focus_dist / self.convergence());
+        self.thin_lens_ray(&eye, &(*pixel_sample + shift * self.u))
+    }
+
+    fn convergence(&self) -> f64 {
+        self.stereo
+            .map_or(f64::INFINITY, |stereo| stereo.convergence)
+    }
+
+    /// Origin and direction of a ray through the thin lens centred on
+    /// `center`, aimed at where the chief ray through `pixel_sample` meets
+    /// the plane of focus.
+    fn thin_lens_ray(&self, center: &Point3, pixel_sample: &Point3) -> (Point3, Vector3) {
         let ray_origin = if self.defocus_angle <= 0.0 {
-            self.center
+            *center
         } else {
-            self.defocus_disk_sample()
+            self.aperture_sample(center)
         };
-        let ray_direction = pixel_sample - ray_origin;
 
-        Ray::new(ray_origin, ray_direction)
+        match self.focus_point(center, pixel_sample) {
+            Some(focus) => (ray_origin, focus - ray_origin),
+            // Beyond infinity: every ray leaves parallel to the chief ray.
+            None => (ray_origin, *pixel_sample - *center),
+        }
+    }
+
+    /// Where the ray from `center` through `pixel_sample` meets the plane of
+    /// focus, which is `pixel_sample` itself unless the plane is tilted, or
+    /// none if the plane isn't ahead of it.
+    fn focus_point(&self, center: &Point3, pixel_sample: &Point3) -> Option<Point3> {
+        let Some(normal) = self.focus_normal else {
+            return Some(*pixel_sample);
+        };
+
+        let direction = *pixel_sample - *center;
+        let focus = self.center - self.focus_dist * self.w;
+        let t = dot(&normal, &(focus - *center)) / dot(&normal, &direction);
+        (t > 0.0 && t.is_finite()).then(|| *center + t * direction)
     }
 
-    pub fn defocus_disk_sample(&self) -> Point3 {
-        let p = random_in_unit_disk();
+    pub fn aperture_sample(&self, center: &Point3) -> Point3 {
+        let p = self.aperture.sample();
 
-        self.center + (p.x() * self.defocus_disk_u) + (p.y() * self.defocus_disk_v)
+        *center + (p.x() * self.defocus_disk_u) + (p.y() * self.defocus_disk_v)
     }
 }
 
 fn sample_square() -> Vector3 {
-    let mut rng = rand::rng();
+    let mut rng = random::rng();
 
     Vector3::new(
         rng.random_range(-0.5..0.5),
@@ -146,6 +362,7 @@ fn sample_square() -> Vector3 {
     )
 }
 
+#[derive(Clone)]
 pub struct CameraBuilder {
     aspect_ratio: f64,
     width: u32,
@@ -153,12 +370,27 @@ pub struct CameraBuilder {
     max_depth: u32,
 
     fov: f64,
+    projection: Projection,
     look_from: Point3,
     look_at: Point3,
     up: Vector3,
 
     defocus_angle: f64,
     focus_dist: f64,
+    aperture: Aperture,
+    tilt: f64,
+    swing: f64,
+    shift: (f64, f64),
+
+    shutter_open: f64,
+    shutter_close: f64,
+
+    stereo: Option<Stereo>,
+    crop: Option<Crop>,
+    spectral: bool,
+    background: Background,
+    lights: Vec<Arc<dyn Light>>,
+    light_sampling: LightSampling,
 }
 
 impl CameraBuilder {
@@ -169,11 +401,24 @@ impl CameraBuilder {
             samples_per_pixel: 10,
             max_depth: 10,
             fov: 90.0,
+            projection: Projection::default(),
             look_from: Point3::zero(),
             look_at: Point3::new(0.0, 0.0, -1.0),
             up: Vector3::new(0.0, 1.0, 0.0),
             defocus_angle: 0.0,
             focus_dist: 10.0,
+            aperture: Aperture::default(),
+            tilt: 0.0,
+            swing: 0.0,
+            shift: (0.0, 0.0),
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+            stereo: None,
+            crop: None,
+            spectral: false,
+            background: Background::Gradient,
+            lights: vec![],
+            light_sampling: LightSampling::default(),
         }
     }
 
@@ -202,6 +447,13 @@ impl CameraBuilder {
         self
     }
 
+    /// How image positions map to rays, perspective by default. `fov`
+    /// only applies to the perspective projection.
+    pub fn projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
     pub fn look_from(mut self, look_from: Point3) -> Self {
         self.look_from = look_from;
         self
@@ -226,4 +478,176 @@ impl CameraBuilder {
         self.focus_dist = dist;
         self
     }
+
+    /// Shape of the thin lens's aperture, a circle by default.
+    pub fn aperture(mut self, aperture: Aperture) -> Self {
+        self.aperture = aperture;
+        self
+    }
+
+    /// Tilts the plane of focus by `degrees` about the camera's horizontal
+    /// axis through the focus point, its top away from the camera for
+    /// positive angles, as tilting a view camera's lens does. Lets a
+    /// receding floor be sharp from front to back, or, tilted against it,
+    /// makes a scene look like a miniature.
+    pub fn tilt(mut self, degrees: f64) -> Self {
+        self.tilt = degrees;
+        self
+    }
+
+    /// Turns the plane of focus by `degrees` about the camera's vertical
+    /// axis, its right side away from the camera for positive angles.
+    pub fn swing(mut self, degrees: f64) -> Self {
+        self.swing = degrees;
+        self
+    }
+
+    /// Slides the sensor across the image circle by fractions of the frame's
+    /// width and height, right and up, without turning the camera; pointing
+    /// level and shifting up keeps a building's verticals parallel.
+    pub fn shift(mut self, x: f64, y: f64) -> Self {
+        self.shift = (x, y);
+        self
+    }
+
+    pub fn shutter_open(mut self, time: f64) -> Self {
+        self.shutter_open = time;
+        self
+    }
+
+    pub fn shutter_close(mut self, time: f64) -> Self {
+        self.shutter_close = time;
+        self
+    }
+
+    /// Renders a view for each eye, stacked as the layout says, each
+    /// `width` wide at the set aspect ratio.
+    pub fn stereo(mut self, stereo: Stereo) -> Self {
+        self.stereo = Some(stereo);
+        self
+    }
+
+    /// Renders only part of the image.
+    pub fn crop(mut self, crop: Crop) -> Self {
+        self.crop = Some(crop);
+        self
+    }
+
+    /// Traces three wavelengths per sample instead of RGB, so that
+    /// wavelength-dependent effects such as dispersion show up.
+    pub fn spectral(mut self, spectral: bool) -> Self {
+        self.spectral = spectral;
+        self
+    }
+
+    /// What rays that leave the scene see, the sky gradient by default.
+    pub fn background(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Lights that direct lighting samples at every diffuse or glossy
+    /// bounce. Area lights also belong in the world, to be seen and hit.
+    pub fn lights(mut self, lights: Vec<Arc<dyn Light>>) -> Self {
+        self.lights = lights;
+        self
+    }
+
+    /// How direct lighting chooses among the lights, by a light BVH unless
+    /// set otherwise.
+    pub fn light_sampling(mut self, light_sampling: LightSampling) -> Self {
+        self.light_sampling = light_sampling;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::crop::CropWindow;
+    use crate::camera::stereo::StereoLayout;
+    use crate::lights::point::PointLight;
+    use crate::materials::material::{Lambertian, Metal};
+    use crate::objects::hittable_list::HittableList;
+    use crate::objects::sphere::Sphere;
+
+    /// Renders `crop` of a small lit scene, returning the image's width,
+    /// height and pixel values in order.
+    fn render(builder: &CameraBuilder, crop: Option<&Crop>) -> (usize, usize, Vec<u8>) {
+        let mut world = HittableList::new();
+        world.add(Box::new(Sphere::new(
+            Point3::new(0.0, -100.5, -1.0),
+            100.0,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )));
+        world.add(Box::new(Sphere::new(
+            Point3::new(0.0, 0.0, -1.0),
+            0.5,
+            Arc::new(Metal::new(Color::new(0.8, 0.6, 0.2), 0.3)),
+        )));
+
+        let mut out = Vec::new();
+        Camera::new_from_builder(builder)
+            .render_crop(&world, crop, &mut out)
+            .unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        let mut values = text
+            .split_whitespace()
+            .skip(1)
+            .map(|value| value.parse().unwrap());
+        let width = values.next().unwrap() as usize;
+        let height = values.next().unwrap() as usize;
+        let pixels = values.skip(1).map(|value: u32| value as u8).collect();
+
+        (width, height, pixels)
+    }
+
+    #[test]
+    fn crops_match_the_full_frame() {
+        let window = CropWindow::Pixels {
+            x: 5,
+            y: 3,
+            width: 10,
+            height: 7,
+        };
+        let (x0, y0, x1, y1) = (5, 3, 15, 10);
+
+        for stereo in [None, Some(Stereo::new(StereoLayout::SideBySide))] {
+            let mut builder = CameraBuilder::new()
+                .aspect_ratio(3.0 / 2.0)
+                .width(24)
+                .samples_per_pixel(4)
+                .max_depth(5)
+                .look_from(Point3::new(0.0, 0.5, 1.0))
+                .look_at(Point3::new(0.0, 0.0, -1.0))
+                .defocus_angle(1.0)
+                .background(Background::Gradient)
+                .lights(vec![Arc::new(PointLight::new(
+                    Point3::new(1.0, 2.0, 0.0),
+                    Color::new(4.0, 4.0, 4.0),
+                ))]);
+            if let Some(stereo) = stereo {
+                builder = builder.stereo(stereo);
+            }
+
+            let (width, _, full) = render(&builder, None);
+            let (crop_width, crop_height, cropped) = render(&builder, Some(&Crop::new(window)));
+            assert_eq!((crop_width, crop_height), (x1 - x0, y1 - y0));
+            let framed_crop = Crop::new(window).full_frame(true);
+            let (_, _, framed) = render(&builder, Some(&framed_crop));
+            assert_eq!(framed.len(), full.len());
+
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let pixel = |image: &[u8], width: usize, x: usize, y: usize| {
+                        image[3 * (y * width + x)..3 * (y * width + x + 1)].to_vec()
+                    };
+                    let expected = pixel(&full, width, x, y);
+                    assert_eq!(pixel(&cropped, crop_width, x - x0, y - y0), expected);
+                    assert_eq!(pixel(&framed, width, x, y), expected);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,69 @@
+/// A rectangle of the image, counted from the top left.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CropWindow {
+    /// `width` by `height` pixels from column `x` and row `y`.
+    Pixels {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+    /// From (`x0`, `y0`) to (`x1`, `y1`) in fractions of the image's width
+    /// and height, taking in every pixel the rectangle touches.
+    Normalized { x0: f64, y0: f64, x1: f64, y1: f64 },
+}
+
+impl CropWindow {
+    /// The window's first and one-past-last columns and rows in a `width`
+    /// by `height` image, clipped to it.
+    pub(crate) fn bounds(&self, width: u32, height: u32) -> (u32, u32, u32, u32) {
+        let (x0, y0, x1, y1) = match *self {
+            CropWindow::Pixels {
+                x,
+                y,
+                width,
+                height,
+            } => (x, y, x.saturating_add(width), y.saturating_add(height)),
+            CropWindow::Normalized { x0, y0, x1, y1 } => {
+                let column = |x: f64| (x.clamp(0.0, 1.0) * width as f64) as u32;
+                let row = |y: f64| (y.clamp(0.0, 1.0) * height as f64) as u32;
+                let (x0, x1) = (x0.min(x1), x0.max(x1));
+                let (y0, y1) = (y0.min(y1), y0.max(y1));
+                (
+                    column(x0),
+                    row(y0),
+                    (x1.clamp(0.0, 1.0) * width as f64).ceil() as u32,
+                    (y1.clamp(0.0, 1.0) * height as f64).ceil() as u32,
+                )
+            }
+        };
+
+        let (x0, y0) = (x0.min(width), y0.min(height));
+        (x0, y0, x1.clamp(x0, width), y1.clamp(y0, height))
+    }
+}
+
+/// Renders only part of the image. Each pixel comes out exactly as it would
+/// in a render of the whole frame, so a noisy region can be redone with
+/// more samples and pasted back.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Crop {
+    pub(crate) window: CropWindow,
+    pub(crate) full_frame: bool,
+}
+
+impl Crop {
+    /// Writes an image of just the window.
+    pub fn new(window: CropWindow) -> Self {
+        Self {
+            window,
+            full_frame: false,
+        }
+    }
+
+    /// Writes the whole frame instead, black outside the window.
+    pub fn full_frame(mut self, full_frame: bool) -> Self {
+        self.full_frame = full_frame;
+        self
+    }
+}
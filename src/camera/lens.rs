@@ -0,0 +1,554 @@
+//! Realistic camera lenses after Kolb, Mitchell and Hanrahan, "A Realistic
+//! Camera Model for Computer Graphics" (1995), as pbrt implements them: rays
+//! leave the film and are refracted through each spherical element of a
+//! lens prescription in turn, so that defocus, vignetting, cat's-eye bokeh
+//! and the change of framing with focus all come from the glass itself.
+
+use crate::math::vector3::{dot, unit_vector, Vector3};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Segments the film's radius is split into for exit pupil bounds.
+const PUPIL_SEGMENTS: usize = 64;
+
+/// Rays traced from each segment to find its exit pupil.
+const PUPIL_SAMPLES: usize = 1 << 16;
+
+/// Diagonal of the film in millimetres, which with the lens's focal length
+/// sets the field of view.
+const FILM_DIAGONAL: f64 = 35.0;
+
+/// One surface of a lens prescription, in millimetres.
+#[derive(Copy, Clone, Debug)]
+pub struct LensElement {
+    /// Radius of curvature, positive when the centre lies towards the film,
+    /// or zero for the aperture stop.
+    pub radius: f64,
+    /// Distance along the axis to the next surface, or to the film after
+    /// the last.
+    pub thickness: f64,
+    /// Index of refraction of the glass between this surface and the next,
+    /// with zero read as air.
+    pub ior: f64,
+    /// Diameter of the surface's clear aperture.
+    pub aperture: f64,
+}
+
+#[derive(Debug)]
+pub enum LensError {
+    Io(io::Error),
+    /// A line didn't hold four numbers.
+    Parse {
+        line: usize,
+        message: String,
+    },
+    /// The table parsed but describes no usable lens.
+    Invalid(String),
+}
+
+impl fmt::Display for LensError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LensError::Io(error) => write!(f, "could not read lens: {}", error),
+            LensError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            LensError::Invalid(what) => write!(f, "invalid lens: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for LensError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LensError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LensError {
+    fn from(error: io::Error) -> Self {
+        LensError::Io(error)
+    }
+}
+
+/// A lens prescription with the film behind it. Elements run from the one
+/// facing the scene to the one facing the film. The scene is taken to be
+/// in metres.
+#[derive(Clone, Debug)]
+pub struct Lens {
+    elements: Vec<LensElement>,
+    aperture: Option<f64>,
+}
+
+impl Lens {
+    pub fn new(elements: Vec<LensElement>) -> Self {
+        Self {
+            elements,
+            aperture: None,
+        }
+    }
+
+    /// Reads a lens table in pbrt's format: one element per line giving
+    /// radius, thickness, index of refraction and aperture diameter in
+    /// millimetres, with `#` starting a comment.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LensError> {
+        let source = fs::read_to_string(path)?;
+
+        let mut elements = vec![];
+        for (index, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let numbers: Vec<f64> = line
+                .split_whitespace()
+                .map(|text| text.parse())
+                .collect::<Result<_, _>>()
+                .map_err(|_| LensError::Parse {
+                    line: index + 1,
+                    message: format!("{:?} is not a number", line),
+                })?;
+            let [radius, thickness, ior, aperture] = numbers[..] else {
+                return Err(LensError::Parse {
+                    line: index + 1,
+                    message: "expected radius, thickness, ior and aperture".into(),
+                });
+            };
+            elements.push(LensElement {
+                radius,
+                thickness,
+                ior,
+                aperture,
+            });
+        }
+
+        if elements.is_empty() {
+            return Err(LensError::Invalid("no elements".into()));
+        }
+        Ok(Self::new(elements))
+    }
+
+    /// A 50 mm f/2 double Gauss, the layout of most standard lenses, from
+    /// US patent 2,673,491 as scaled in pbrt's example scenes.
+    pub fn double_gauss() -> Self {
+        let table = [
+            (29.475, 3.76, 1.67, 25.2),
+            (84.83, 0.12, 1.0, 25.2),
+            (19.275, 4.025, 1.67, 23.0),
+            (40.77, 3.275, 1.699, 23.0),
+            (12.75, 5.705, 1.0, 18.0),
+            (0.0, 4.5, 0.0, 17.1),
+            (-14.495, 1.18, 1.603, 17.0),
+            (40.77, 6.065, 1.658, 20.0),
+            (-20.385, 0.19, 1.0, 20.0),
+            (437.065, 3.22, 1.717, 20.0),
+            (-39.73, 5.0, 1.0, 20.0),
+        ];
+
+        Self::new(
+            table
+                .iter()
+                .map(|&(radius, thickness, ior, aperture)| LensElement {
+                    radius,
+                    thickness,
+                    ior,
+                    aperture,
+                })
+                .collect(),
+        )
+    }
+
+    /// Stops the aperture down to `millimetres` across. It can't open wider
+    /// than the prescription's stop.
+    pub fn aperture(mut self, millimetres: f64) -> Self {
+        self.aperture = Some(millimetres);
+        self
+    }
+
+    /// Converts to metres, moves the lens to focus at `focus_distance` from
+    /// the film and finds the exit pupils, for a film of `aspect_ratio`.
+    pub(crate) fn build(&self, focus_distance: f64, aspect_ratio: f64) -> LensSystem {
+        let interfaces = self
+            .elements
+            .iter()
+            .map(|element| {
+                let mut aperture = element.aperture;
+                if let Some(stop) = self.aperture.filter(|_| element.radius == 0.0) {
+                    aperture = stop.clamp(0.0, aperture);
+                }
+
+                Interface {
+                    radius: 0.001 * element.radius,
+                    thickness: 0.001 * element.thickness,
+                    ior: if element.ior == 0.0 { 1.0 } else { element.ior },
+                    aperture_radius: 0.0005 * aperture,
+                }
+            })
+            .collect();
+
+        let diagonal = 0.001 * FILM_DIAGONAL;
+        let film_height = diagonal / (1.0 + aspect_ratio * aspect_ratio).sqrt();
+        let mut system = LensSystem {
+            interfaces,
+            film_width: aspect_ratio * film_height,
+            film_height,
+            pupils: vec![],
+        };
+        system.focus(focus_distance);
+        system.pupils = (0..PUPIL_SEGMENTS)
+            .map(|segment| system.bound_exit_pupil(segment, diagonal / 2.0))
+            .collect();
+
+        system
+    }
+}
+
+struct Interface {
+    radius: f64,
+    thickness: f64,
+    ior: f64,
+    aperture_radius: f64,
+}
+
+/// Bounds on the rear element's plane as min x, min y, max x, max y.
+type Bounds = [f64; 4];
+
+/// A `Lens` ready to trace, in metres and focused. Lens space has the film
+/// at z = 0 and the lens in front of it along -z, matching the camera
+/// frame.
+pub(crate) struct LensSystem {
+    interfaces: Vec<Interface>,
+    film_width: f64,
+    film_height: f64,
+    /// For each ring of the film, from the centre out, bounds on where rays
+    /// through the rear element can make it out of the lens, taken along +x.
+    pupils: Vec<Bounds>,
+}
+
+impl LensSystem {
+    /// A ray leaving the lens for the film point at fractions `x` across and
+    /// `y` down the image, aimed at the rear element by `u` and `v`. Gives
+    /// the origin and direction in lens space and the weight for its
+    /// radiance, or none if the lens blocks it.
+    ///
+    /// Weights fall off with the fourth power of the cosine and with the
+    /// exit pupil's area, normalised to one at the centre of the film
+    /// whatever the aperture, so that images stay as bright as a pinhole's.
+    pub(crate) fn sample_ray(
+        &self,
+        x: f64,
+        y: f64,
+        u: f64,
+        v: f64,
+    ) -> Option<(Vector3, Vector3, f64)> {
+        // The lens turns the image upside down and left to right.
+        let film = Vector3::new(
+            -(x - 0.5) * self.film_width,
+            (y - 0.5) * self.film_height,
+            0.0,
+        );
+
+        let film_radius = (film.x() * film.x() + film.y() * film.y()).sqrt();
+        let max_radius =
+            0.5 * (self.film_width * self.film_width + self.film_height * self.film_height).sqrt();
+        let segment =
+            ((film_radius / max_radius * PUPIL_SEGMENTS as f64) as usize).min(PUPIL_SEGMENTS - 1);
+        let pupil = self.pupils[segment];
+        if area(&pupil) == 0.0 {
+            return None;
+        }
+
+        // The bounds were found along +x; turn them to face the film point.
+        let px = pupil[0] + u * (pupil[2] - pupil[0]);
+        let py = pupil[1] + v * (pupil[3] - pupil[1]);
+        let (sin, cos) = if film_radius > 0.0 {
+            (film.y() / film_radius, film.x() / film_radius)
+        } else {
+            (0.0, 1.0)
+        };
+        let rear = Vector3::new(cos * px - sin * py, sin * px + cos * py, self.rear_z());
+
+        let direction = rear - film;
+        let (origin, out) = self.trace_from_film(film, direction)?;
+
+        let cos_theta = -unit_vector(&direction).z();
+        let centre = area(&self.pupils[0]);
+        let scale = if centre > 0.0 {
+            area(&pupil) / centre
+        } else {
+            1.0
+        };
+        Some((origin, out, cos_theta.powi(4) * scale))
+    }
+
+    fn rear_z(&self) -> f64 {
+        -self
+            .interfaces
+            .last()
+            .map_or(0.0, |interface| interface.thickness)
+    }
+
+    fn front_z(&self) -> f64 {
+        -self
+            .interfaces
+            .iter()
+            .map(|interface| interface.thickness)
+            .sum::<f64>()
+    }
+
+    /// Follows a ray from the film out through the lens, returning where it
+    /// leaves the front element and its new direction.
+    fn trace_from_film(&self, origin: Vector3, direction: Vector3) -> Option<(Vector3, Vector3)> {
+        let (mut origin, mut direction) = (origin, unit_vector(&direction));
+        let mut z = 0.0;
+        for i in (0..self.interfaces.len()).rev() {
+            let interface = &self.interfaces[i];
+            z -= interface.thickness;
+
+            let outside = if i > 0 {
+                self.interfaces[i - 1].ior
+            } else {
+                1.0
+            };
+            (origin, direction) =
+                self.cross(interface, z, origin, direction, interface.ior / outside)?;
+        }
+
+        Some((origin, direction))
+    }
+
+    /// Follows a ray from the scene in through the lens, returning where it
+    /// leaves the rear element and its new direction.
+    fn trace_from_scene(&self, origin: Vector3, direction: Vector3) -> Option<(Vector3, Vector3)> {
+        let (mut origin, mut direction) = (origin, unit_vector(&direction));
+        let mut z = self.front_z();
+        for (i, interface) in self.interfaces.iter().enumerate() {
+            let outside = if i > 0 {
+                self.interfaces[i - 1].ior
+            } else {
+                1.0
+            };
+            (origin, direction) =
+                self.cross(interface, z, origin, direction, outside / interface.ior)?;
+            z += interface.thickness;
+        }
+
+        Some((origin, direction))
+    }
+
+    /// Takes a ray across the interface whose vertex is at `z`, refracting
+    /// with the ratio of indices `eta`, or stops it if it misses the surface
+    /// or its aperture.
+    fn cross(
+        &self,
+        interface: &Interface,
+        z: f64,
+        origin: Vector3,
+        direction: Vector3,
+        eta: f64,
+    ) -> Option<(Vector3, Vector3)> {
+        let (t, normal) = if interface.radius == 0.0 {
+            if direction.z() == 0.0 {
+                return None;
+            }
+            ((z - origin.z()) / direction.z(), None)
+        } else {
+            let (t, normal) = intersect_spherical(interface.radius, z, &origin, &direction)?;
+            (t, Some(normal))
+        };
+        if t < 0.0 {
+            return None;
+        }
+
+        let point = origin + t * direction;
+        let r2 = point.x() * point.x() + point.y() * point.y();
+        if r2 > interface.aperture_radius * interface.aperture_radius {
+            return None;
+        }
+
+        match normal {
+            Some(normal) => Some((point, refract(&-direction, &normal, eta)?)),
+            None => Some((point, direction)),
+        }
+    }
+
+    /// Sets the gap between the rear element and the film so that points
+    /// `distance` from the film are in focus, from a thick lens fitted to
+    /// the prescription. Lenses asked to focus closer than they can keep
+    /// the prescription's gap.
+    fn focus(&mut self, distance: f64) {
+        let Some((principal, focal)) = self.thick_lens() else {
+            return;
+        };
+
+        let f = focal[0] - principal[0];
+        let z = -distance;
+        let c = (principal[1] - z - principal[0]) * (principal[1] - z - 4.0 * f - principal[0]);
+        if c.is_nan() || c < 0.0 {
+            return;
+        }
+
+        let delta = 0.5 * (principal[1] - z + principal[0] - c.sqrt());
+        if let Some(last) = self.interfaces.last_mut() {
+            last.thickness += delta;
+        }
+    }
+
+    /// Positions along the axis of the principal planes and focal points,
+    /// the film side's first, found by tracing rays parallel to the axis
+    /// through each way.
+    fn thick_lens(&self) -> Option<([f64; 2], [f64; 2])> {
+        let height = 0.001
+            * (self.film_width * self.film_width + self.film_height * self.film_height).sqrt();
+
+        let scene_in = Vector3::new(height, 0.0, self.front_z() - 1.0);
+        let axis = Vector3::new(0.0, 0.0, 1.0);
+        let film_side = self.trace_from_scene(scene_in, axis)?;
+        let film_in = Vector3::new(height, 0.0, self.rear_z() + 1.0);
+        let scene_side = self.trace_from_film(film_in, -axis)?;
+
+        let (p0, f0) = cardinal_points(height, film_side)?;
+        let (p1, f1) = cardinal_points(height, scene_side)?;
+        Some(([p0, p1], [f0, f1]))
+    }
+
+    /// Bounds on the rear element's plane of the rays from the film ring
+    /// `segment` of `PUPIL_SEGMENTS` out to `max_radius` that pass through
+    /// the lens.
+    fn bound_exit_pupil(&self, segment: usize, max_radius: f64) -> Bounds {
+        let r0 = max_radius * segment as f64 / PUPIL_SEGMENTS as f64;
+        let r1 = max_radius * (segment + 1) as f64 / PUPIL_SEGMENTS as f64;
+        let rear_radius = self
+            .interfaces
+            .last()
+            .map_or(0.0, |last| last.aperture_radius);
+        let extent = 1.5 * rear_radius;
+        let rear_z = self.rear_z();
+
+        let mut bounds = [
+            f64::INFINITY,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NEG_INFINITY,
+        ];
+        for i in 0..PUPIL_SAMPLES {
+            let film_x = r0 + (i as f64 + 0.5) / PUPIL_SAMPLES as f64 * (r1 - r0);
+            let film = Vector3::new(film_x, 0.0, 0.0);
+            let x = -extent + 2.0 * extent * radical_inverse(2, i);
+            let y = -extent + 2.0 * extent * radical_inverse(3, i);
+            let rear = Vector3::new(x, y, rear_z);
+
+            if self.trace_from_film(film, rear - film).is_some() {
+                bounds = [
+                    bounds[0].min(x),
+                    bounds[1].min(y),
+                    bounds[2].max(x),
+                    bounds[3].max(y),
+                ];
+            }
+        }
+        if bounds[0] > bounds[2] {
+            return [0.0; 4];
+        }
+
+        // Grow by a sample spacing so the edges of the pupil aren't lost.
+        let margin = 2.0 * extent / (PUPIL_SAMPLES as f64).sqrt();
+        [
+            bounds[0] - margin,
+            bounds[1] - margin,
+            bounds[2] + margin,
+            bounds[3] + margin,
+        ]
+    }
+}
+
+fn area(bounds: &Bounds) -> f64 {
+    (bounds[2] - bounds[0]).max(0.0) * (bounds[3] - bounds[1]).max(0.0)
+}
+
+/// Where a ray that entered parallel to the axis at `height` crosses the
+/// axis, and where its extension reaches that height again, as positions
+/// along the axis.
+fn cardinal_points(height: f64, (origin, direction): (Vector3, Vector3)) -> Option<(f64, f64)> {
+    if direction.x() == 0.0 {
+        return None;
+    }
+
+    let focal = origin.z() + (-origin.x() / direction.x()) * direction.z();
+    let principal = origin.z() + ((height - origin.x()) / direction.x()) * direction.z();
+    Some((principal, focal))
+}
+
+/// Hits the sphere of `radius` whose vertex on the axis is at `z`, taking
+/// the intersection on the side the lens surface lies on. Returns the
+/// distance and the normal facing back along the ray.
+fn intersect_spherical(
+    radius: f64,
+    z: f64,
+    origin: &Vector3,
+    direction: &Vector3,
+) -> Option<(f64, Vector3)> {
+    let offset = *origin - Vector3::new(0.0, 0.0, z + radius);
+    let a = direction.length_squared();
+    let b = 2.0 * dot(direction, &offset);
+    let c = offset.length_squared() - radius * radius;
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let root = discriminant.sqrt();
+    let q = if b < 0.0 {
+        -0.5 * (b - root)
+    } else {
+        -0.5 * (b + root)
+    };
+    let (t0, t1) = (q / a, c / q);
+    let (near, far) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+    let t = if (direction.z() > 0.0) ^ (radius < 0.0) {
+        near
+    } else {
+        far
+    };
+    if t.is_nan() || t <= 0.0 {
+        return None;
+    }
+
+    let mut normal = unit_vector(&(offset + t * *direction));
+    if dot(&normal, direction) > 0.0 {
+        normal = -normal;
+    }
+    Some((t, normal))
+}
+
+/// Refracts `incoming`, pointing away from the surface on the side of
+/// `normal`, by the ratio of indices `eta`, or none on total internal
+/// reflection.
+fn refract(incoming: &Vector3, normal: &Vector3, eta: f64) -> Option<Vector3> {
+    let cos_i = dot(normal, incoming);
+    let sin2_i = (1.0 - cos_i * cos_i).max(0.0);
+    let sin2_t = eta * eta * sin2_i;
+    if sin2_t >= 1.0 {
+        return None;
+    }
+
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(eta * -*incoming + (eta * cos_i - cos_t) * *normal)
+}
+
+/// The `index`th point of the van der Corput sequence in `base`, spreading
+/// the pupil search more evenly than random samples.
+fn radical_inverse(base: usize, mut index: usize) -> f64 {
+    let mut result = 0.0;
+    let mut scale = 1.0 / base as f64;
+    while index > 0 {
+        result += (index % base) as f64 * scale;
+        index /= base;
+        scale /= base as f64;
+    }
+
+    result
+}
@@ -0,0 +1,133 @@
+use crate::camera::lens::Lens;
+use crate::math::vector3::Vector3;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// How the camera maps image positions to rays. All projections look along
+/// the frame set by `look_from`, `look_at` and `up`.
+#[derive(Clone, Debug, Default)]
+pub enum Projection {
+    /// A pinhole or thin lens with the builder's `fov`, `defocus_angle` and
+    /// `focus_dist`.
+    #[default]
+    Perspective,
+    /// Parallel rays through a viewport `size` world units high. Defocus
+    /// still blurs away from the focus distance.
+    Orthographic { size: f64 },
+    /// A circular fisheye whose image circle spans `fov` degrees, up to 360,
+    /// across the shorter side of the image, with distance from the centre
+    /// proportional to the angle off axis. Outside the circle is black.
+    Equidistant { fov: f64 },
+    /// A circular fisheye like `Equidistant`, but with equal areas of the
+    /// image covering equal solid angles.
+    Equisolid { fov: f64 },
+    /// The whole sphere of directions, longitude across and latitude down,
+    /// with the view direction in the middle. Wants a 2:1 aspect ratio.
+    Equirectangular,
+    /// Six 90° perspective faces in a 3:2 grid: right, left and up on top,
+    /// then down, back and front. Faces are named for the camera frame's
+    /// +x, -x, +y, -y, +z and -z, with z pointing backwards.
+    CubeMap,
+    /// Through the elements of a real lens onto a film behind it, which
+    /// sits at `look_from`. The lens focuses itself at `focus_dist`.
+    Realistic(Arc<Lens>),
+}
+
+impl Projection {
+    /// Direction in the camera frame, x right, y up and z backwards, for
+    /// the point at fractions `x` across and `y` down a `width` by `height`
+    /// image, or none where the projection covers no directions. Only for
+    /// the projections that shoot every ray from the camera's centre.
+    pub(crate) fn direction(&self, x: f64, y: f64, width: f64, height: f64) -> Option<Vector3> {
+        match *self {
+            Projection::Perspective
+            | Projection::Orthographic { .. }
+            | Projection::Realistic(_) => None,
+            Projection::Equidistant { fov } => {
+                fisheye(x, y, width, height, |r| r * half_angle(fov))
+            }
+            Projection::Equisolid { fov } => fisheye(x, y, width, height, |r| {
+                2.0 * (r * (0.5 * half_angle(fov)).sin()).clamp(-1.0, 1.0).asin()
+            }),
+            Projection::Equirectangular => {
+                let longitude = (x - 0.5) * 2.0 * PI;
+                let latitude = (0.5 - y) * PI;
+                Some(Vector3::new(
+                    latitude.cos() * longitude.sin(),
+                    latitude.sin(),
+                    -latitude.cos() * longitude.cos(),
+                ))
+            }
+            Projection::CubeMap => {
+                let column = ((3.0 * x) as usize).min(2);
+                let row = ((2.0 * y) as usize).min(1);
+                // Position within the face, right and down, in [-1, 1].
+                let s = 2.0 * (3.0 * x - column as f64) - 1.0;
+                let t = 2.0 * (2.0 * y - row as f64) - 1.0;
+
+                let (forward, right, up) = CUBE_FACES[3 * row + column];
+                Some(forward + s * right - t * up)
+            }
+        }
+    }
+}
+
+/// Forward, right and up for each cube face, in the order they are laid out.
+const CUBE_FACES: [(Vector3, Vector3, Vector3); 6] = [
+    (
+        Vector3::new(1.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, 1.0),
+        Vector3::new(0.0, 1.0, 0.0),
+    ),
+    (
+        Vector3::new(-1.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, -1.0),
+        Vector3::new(0.0, 1.0, 0.0),
+    ),
+    (
+        Vector3::new(0.0, 1.0, 0.0),
+        Vector3::new(1.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, 1.0),
+    ),
+    (
+        Vector3::new(0.0, -1.0, 0.0),
+        Vector3::new(1.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, -1.0),
+    ),
+    (
+        Vector3::new(0.0, 0.0, 1.0),
+        Vector3::new(-1.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+    ),
+    (
+        Vector3::new(0.0, 0.0, -1.0),
+        Vector3::new(1.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+    ),
+];
+
+/// Half a fisheye's field of view, in radians.
+fn half_angle(fov: f64) -> f64 {
+    fov.clamp(0.0, 360.0).to_radians() / 2.0
+}
+
+/// Direction through a circular fisheye image, where `angle` gives the
+/// angle off axis for a distance from the centre, with one at the edge of
+/// the image circle.
+fn fisheye(x: f64, y: f64, width: f64, height: f64, angle: impl Fn(f64) -> f64) -> Option<Vector3> {
+    let radius = 0.5 * width.min(height);
+    let dx = (x - 0.5) * width / radius;
+    let dy = (0.5 - y) * height / radius;
+    let r = (dx * dx + dy * dy).sqrt();
+    if r > 1.0 {
+        return None;
+    }
+
+    let theta = angle(r);
+    let phi = dy.atan2(dx);
+    Some(Vector3::new(
+        theta.sin() * phi.cos(),
+        theta.sin() * phi.sin(),
+        -theta.cos(),
+    ))
+}
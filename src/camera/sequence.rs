@@ -0,0 +1,103 @@
+use crate::camera::animation::CameraAnimation;
+use crate::camera::camera::{Camera, CameraBuilder};
+use crate::objects::hittable::Hittable;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// Renders an animation to one numbered image file per frame. Frames whose
+/// file already exists are skipped, so a sequence that was stopped picks up
+/// where it left off, and a frame is only given its name once it's whole.
+pub struct ImageSequence {
+    path: String,
+    frames: Range<u32>,
+    frame_rate: f64,
+    shutter: f64,
+}
+
+impl ImageSequence {
+    /// Frames `frames`, not including its end, written to `path` with its
+    /// run of `#`s replaced by the frame number padded with zeros to as
+    /// many digits, e.g. `turntable_###.ppm`. 24 frames a second with the
+    /// shutter open for an instant.
+    pub fn new(path: impl Into<String>, frames: Range<u32>) -> Self {
+        Self {
+            path: path.into(),
+            frames,
+            frame_rate: 24.0,
+            shutter: 0.0,
+        }
+    }
+
+    /// Frames per unit of animation time; frame `n` is at `n / frame_rate`.
+    pub fn frame_rate(mut self, frame_rate: f64) -> Self {
+        self.frame_rate = frame_rate;
+        self
+    }
+
+    /// How much of each frame's time the shutter is open for, 0.5 for the
+    /// usual 180° shutter. Replaces the builder's shutter times, so objects
+    /// keyframed in the animation's time blur across their motion.
+    pub fn shutter(mut self, fraction: f64) -> Self {
+        self.shutter = fraction;
+        self
+    }
+
+    /// The file frame `frame` is written to.
+    pub fn frame_path(&self, frame: u32) -> PathBuf {
+        let start = self.path.find('#').unwrap_or(self.path.len());
+        let digits = self.path[start..].chars().take_while(|&c| c == '#').count();
+
+        let mut path = String::from(&self.path[..start]);
+        path.push_str(&format!("{:01$}", frame, digits));
+        path.push_str(&self.path[start + digits..]);
+        PathBuf::from(path)
+    }
+
+    /// Renders every frame of `animation` not yet written, the rest of the
+    /// camera set by `builder`.
+    pub fn render(
+        &self,
+        builder: &CameraBuilder,
+        animation: &CameraAnimation,
+        world: &dyn Hittable,
+    ) -> io::Result<()> {
+        if !self.path.contains('#') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: no # for the frame number", self.path),
+            ));
+        }
+
+        for frame in self.frames.clone() {
+            let path = self.frame_path(frame);
+            if path.exists() {
+                eprintln!("Skipping {}, which exists", path.display());
+                continue;
+            }
+            eprintln!("Rendering {}", path.display());
+
+            let time = frame as f64 / self.frame_rate;
+            let builder = animation
+                .builder_at(builder, time)
+                .shutter_open(time)
+                .shutter_close(time + self.shutter / self.frame_rate);
+            let camera = Camera::new_from_builder(&builder);
+
+            let partial = path.with_extension("partial");
+            let with_path = |error: io::Error| {
+                io::Error::new(error.kind(), format!("{}: {}", partial.display(), error))
+            };
+            let mut out = BufWriter::new(File::create(&partial).map_err(with_path)?);
+            camera.render_to(world, &mut out).map_err(with_path)?;
+            out.flush().map_err(with_path)?;
+            drop(out);
+            fs::rename(&partial, &path).map_err(with_path)?;
+        }
+
+        Ok(())
+    }
+}
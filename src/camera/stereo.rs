@@ -0,0 +1,65 @@
+/// How the two eyes' images are put together into one.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StereoLayout {
+    /// Left eye on the left, right eye on the right, each the full width.
+    SideBySide,
+    /// Left eye above the right, each the full height.
+    TopBottom,
+}
+
+impl StereoLayout {
+    /// Columns and rows of eye images in the output.
+    pub(crate) fn grid(&self) -> (u32, u32) {
+        match self {
+            StereoLayout::SideBySide => (2, 1),
+            StereoLayout::TopBottom => (1, 2),
+        }
+    }
+}
+
+/// Renders a view for each eye into one stacked image. With perspective
+/// cameras the eyes look parallel and their images are shifted so that
+/// objects at the convergence distance land in the same place in both.
+/// With the equirectangular projection, and the other panoramas, each
+/// direction is seen from the eye offset across it, which gives an
+/// omni-directional stereo (ODS) panorama.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Stereo {
+    pub(crate) layout: StereoLayout,
+    pub(crate) interocular: f64,
+    pub(crate) convergence: f64,
+}
+
+impl Stereo {
+    /// Eyes 64 mm apart, taking scene units as metres, converging at
+    /// infinity.
+    pub fn new(layout: StereoLayout) -> Self {
+        Self {
+            layout,
+            interocular: 0.064,
+            convergence: f64::INFINITY,
+        }
+    }
+
+    /// Distance between the eyes.
+    pub fn interocular(mut self, distance: f64) -> Self {
+        self.interocular = distance;
+        self
+    }
+
+    /// Distance at which the eyes' views meet, which appears at the depth
+    /// of the screen; nearer objects come out of it.
+    pub fn convergence(mut self, distance: f64) -> Self {
+        self.convergence = distance;
+        self
+    }
+
+    /// Offset of eye `index`, left first, along the camera's right axis.
+    pub(crate) fn eye_offset(&self, index: u32) -> f64 {
+        if index == 0 {
+            -0.5 * self.interocular
+        } else {
+            0.5 * self.interocular
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum ImageError {
+    Io(io::Error),
+    /// The data ended before the decoder had everything it needed.
+    Truncated,
+    /// The file is well formed but uses a feature the loader doesn't handle.
+    Unsupported(String),
+    /// The file is not a valid image of its format.
+    Malformed(String),
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageError::Io(error) => write!(f, "could not read image: {}", error),
+            ImageError::Truncated => write!(f, "image data is truncated"),
+            ImageError::Unsupported(what) => write!(f, "unsupported image: {}", what),
+            ImageError::Malformed(what) => write!(f, "malformed image: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImageError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ImageError {
+    fn from(error: io::Error) -> Self {
+        ImageError::Io(error)
+    }
+}
@@ -0,0 +1,234 @@
+use crate::images::error::ImageError;
+use crate::images::image::Image;
+use crate::math::color::Color;
+
+/// Decodes Radiance RGBE (.hdr) files, both flat and run-length encoded.
+/// Values are already linear and are returned as stored.
+pub fn decode(data: &[u8]) -> Result<Image, ImageError> {
+    let mut position = 0;
+    let line = |position: &mut usize| -> Result<String, ImageError> {
+        let start = *position;
+        let end = data[start..]
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .ok_or(ImageError::Truncated)?;
+        *position = start + end + 1;
+        Ok(String::from_utf8_lossy(&data[start..start + end]).into_owned())
+    };
+
+    let magic = line(&mut position)?;
+    if magic != "#?RADIANCE" && magic != "#?RGBE" {
+        return Err(ImageError::Malformed("missing Radiance header".into()));
+    }
+
+    let mut exposure = 1.0;
+    loop {
+        let header = line(&mut position)?;
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some(format) = header.strip_prefix("FORMAT=") {
+            if format != "32-bit_rle_rgbe" {
+                return Err(ImageError::Unsupported(format!(
+                    "Radiance format {}",
+                    format
+                )));
+            }
+        } else if let Some(value) = header.strip_prefix("EXPOSURE=") {
+            let value: f64 = value
+                .trim()
+                .parse()
+                .map_err(|_| ImageError::Malformed("Radiance exposure".into()))?;
+            exposure *= value;
+        }
+    }
+
+    let resolution = line(&mut position)?;
+    let fields: Vec<&str> = resolution.split_whitespace().collect();
+    let (width, height) = match fields.as_slice() {
+        ["-Y", height, "+X", width] => (parse_size(width)?, parse_size(height)?),
+        [_, _, _, _] => {
+            return Err(ImageError::Unsupported(format!(
+                "Radiance orientation {}",
+                resolution
+            )));
+        }
+        _ => return Err(ImageError::Malformed("Radiance resolution line".into())),
+    };
+
+    // Every scanline takes at least this many bytes, so a header promising
+    // more than the file holds is caught before anything is allocated.
+    let scanline_bytes = if (8..0x8000).contains(&width) {
+        Some(4 + 8 * width.div_ceil(128))
+    } else {
+        width.checked_mul(4)
+    };
+    let raster_bytes = scanline_bytes.and_then(|bytes| bytes.checked_mul(height));
+    if raster_bytes.is_none_or(|bytes| bytes > data.len() - position) {
+        return Err(ImageError::Truncated);
+    }
+
+    let mut pixels = Vec::with_capacity(width * height);
+    let mut scanline = vec![[0u8; 4]; width];
+    for _ in 0..height {
+        position = read_scanline(data, position, &mut scanline)?;
+        pixels.extend(scanline.iter().map(|rgbe| rgbe_to_color(rgbe) / exposure));
+    }
+
+    Ok(Image::new(width as u32, height as u32, pixels))
+}
+
+fn parse_size(text: &str) -> Result<usize, ImageError> {
+    match text.parse() {
+        Ok(0) | Err(_) => Err(ImageError::Malformed(format!("Radiance size {:?}", text))),
+        Ok(size) => Ok(size),
+    }
+}
+
+fn read_scanline(
+    data: &[u8],
+    mut position: usize,
+    scanline: &mut [[u8; 4]],
+) -> Result<usize, ImageError> {
+    let width = scanline.len();
+    let start = data
+        .get(position..position + 4)
+        .ok_or(ImageError::Truncated)?;
+
+    // New-style RLE stores each channel separately, flagged by 2, 2 and the
+    // scanline width. Anything else is a flat scanline.
+    let is_rle =
+        (8..0x8000).contains(&width) && start[0] == 2 && start[1] == 2 && start[2] & 0x80 == 0;
+    if !is_rle {
+        for pixel in scanline.iter_mut() {
+            let bytes = data
+                .get(position..position + 4)
+                .ok_or(ImageError::Truncated)?;
+            pixel.copy_from_slice(bytes);
+            position += 4;
+        }
+        return Ok(position);
+    }
+
+    if ((start[2] as usize) << 8 | start[3] as usize) != width {
+        return Err(ImageError::Malformed(
+            "Radiance scanline width mismatch".into(),
+        ));
+    }
+    position += 4;
+
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let count = *data.get(position).ok_or(ImageError::Truncated)? as usize;
+            position += 1;
+
+            if count > 128 {
+                let run = count - 128;
+                let value = *data.get(position).ok_or(ImageError::Truncated)?;
+                position += 1;
+                if x + run > width {
+                    return Err(ImageError::Malformed(
+                        "Radiance run overflows scanline".into(),
+                    ));
+                }
+                for pixel in &mut scanline[x..x + run] {
+                    pixel[channel] = value;
+                }
+                x += run;
+            } else {
+                if count == 0 || x + count > width {
+                    return Err(ImageError::Malformed("Radiance bad literal run".into()));
+                }
+                let values = data
+                    .get(position..position + count)
+                    .ok_or(ImageError::Truncated)?;
+                position += count;
+                for (pixel, &value) in scanline[x..x + count].iter_mut().zip(values) {
+                    pixel[channel] = value;
+                }
+                x += count;
+            }
+        }
+    }
+
+    Ok(position)
+}
+
+fn rgbe_to_color(rgbe: &[u8; 4]) -> Color {
+    if rgbe[3] == 0 {
+        return Color::black();
+    }
+
+    let scale = 2.0f64.powi(rgbe[3] as i32 - (128 + 8));
+    Color::new(
+        (rgbe[0] as f64 + 0.5) * scale,
+        (rgbe[1] as f64 + 0.5) * scale,
+        (rgbe[2] as f64 + 0.5) * scale,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &[u8] = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n";
+
+    fn file(resolution: &str, raster: &[u8]) -> Vec<u8> {
+        let mut file = HEADER.to_vec();
+        file.extend_from_slice(resolution.as_bytes());
+        file.extend_from_slice(raster);
+        file
+    }
+
+    #[test]
+    fn decodes_flat_scanlines() {
+        let image = decode(&file("-Y 1 +X 2\n", &[128, 64, 0, 129, 0, 0, 0, 0])).unwrap();
+
+        assert_eq!((image.width(), image.height()), (2, 1));
+        // Mantissas are centred in their step, scaled by 2^(exponent - 136).
+        assert_eq!(image.pixel(0, 0).r(), 128.5 / 128.0);
+        assert_eq!(image.pixel(0, 0).g(), 64.5 / 128.0);
+        assert_eq!(image.pixel(1, 0).r(), 0.0);
+    }
+
+    #[test]
+    fn decodes_run_length_scanlines() {
+        // Red as a literal run, the other channels as repeated runs.
+        let mut raster = vec![2, 2, 0, 8, 8, 0, 1, 2, 3, 4, 5, 6, 7];
+        raster.extend_from_slice(&[136, 10, 136, 20, 136, 128]);
+        let image = decode(&file("-Y 1 +X 8\n", &raster)).unwrap();
+
+        for x in 0..8 {
+            let pixel = image.pixel(x, 0);
+            assert_eq!(pixel.r(), (x as f64 + 0.5) / 256.0);
+            assert_eq!(pixel.g(), 10.5 / 256.0);
+            assert_eq!(pixel.b(), 20.5 / 256.0);
+        }
+    }
+
+    #[test]
+    fn run_past_scanline_end_is_malformed() {
+        let raster = [2, 2, 0, 8, 137, 1, 0, 0, 0, 0, 0, 0];
+        assert!(matches!(
+            decode(&file("-Y 1 +X 8\n", &raster)),
+            Err(ImageError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn truncated_files_are_errors() {
+        let file = file("-Y 1 +X 2\n", &[128, 64, 0, 129, 1, 2, 3, 129]);
+        assert!(decode(&file).is_ok());
+        for length in 0..file.len() {
+            assert!(decode(&file[..length]).is_err(), "length {}", length);
+        }
+    }
+
+    #[test]
+    fn oversized_header_is_truncated_before_allocating() {
+        let file = file("-Y 200000 +X 200000\n", &[2, 2, 0, 0]);
+        assert!(matches!(decode(&file), Err(ImageError::Truncated)));
+    }
+}
@@ -0,0 +1,88 @@
+use crate::math::color::Color;
+
+/// A decoded image with linear color values, stored row by row from the top.
+/// Color is not premultiplied by alpha.
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+    alpha: Option<Vec<f64>>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, pixels: Vec<Color>) -> Self {
+        assert_eq!(pixels.len(), width as usize * height as usize);
+
+        Self {
+            width,
+            height,
+            pixels,
+            alpha: None,
+        }
+    }
+
+    pub fn with_alpha(mut self, alpha: Vec<f64>) -> Self {
+        assert_eq!(alpha.len(), self.pixels.len());
+        self.alpha = Some(alpha);
+        self
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[cfg(test)]
+    pub fn has_alpha(&self) -> bool {
+        self.alpha.is_some()
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        self.pixels[self.index(x, y)]
+    }
+
+    pub fn alpha(&self, x: u32, y: u32) -> f64 {
+        match &self.alpha {
+            Some(alpha) => alpha[self.index(x, y)],
+            None => 1.0,
+        }
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+}
+
+/// How the integer samples of PNG and PNM files are read. Float formats
+/// (HDR and PFM) always hold linear values and ignore it.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    /// sRGB-encoded color, decoded to linear intensity, as for albedo maps.
+    #[default]
+    Srgb,
+    /// Data such as normal, height or roughness maps, read as stored so a
+    /// mid-grey texel is 0.5.
+    Linear,
+}
+
+impl ColorSpace {
+    /// Converts one channel value in [0, 1] as stored to linear.
+    pub fn decode(&self, value: f64) -> f64 {
+        match self {
+            ColorSpace::Srgb => srgb_to_linear(value),
+            ColorSpace::Linear => value,
+        }
+    }
+}
+
+/// Decodes one sRGB-encoded channel value in [0, 1] to linear intensity.
+pub fn srgb_to_linear(value: f64) -> f64 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
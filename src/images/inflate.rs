@@ -0,0 +1,431 @@
+//! A small DEFLATE (RFC 1951) decoder with the zlib (RFC 1950) wrapper, just
+//! enough to unpack PNG image data.
+
+use crate::images::error::ImageError;
+
+const MAX_BITS: usize = 15;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    bit_buffer: u32,
+    bit_count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            bit_buffer: 0,
+            bit_count: 0,
+        }
+    }
+
+    fn bits(&mut self, count: u32) -> Result<u32, ImageError> {
+        while self.bit_count < count {
+            let byte = *self.data.get(self.position).ok_or(ImageError::Truncated)?;
+            self.position += 1;
+            self.bit_buffer |= (byte as u32) << self.bit_count;
+            self.bit_count += 8;
+        }
+
+        let value = self.bit_buffer & ((1u32 << count) - 1);
+        self.bit_buffer >>= count;
+        self.bit_count -= count;
+
+        Ok(value)
+    }
+
+    fn align_to_byte(&mut self) {
+        self.bit_buffer = 0;
+        self.bit_count = 0;
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], ImageError> {
+        let end = self.position + count;
+        let slice = self
+            .data
+            .get(self.position..end)
+            .ok_or(ImageError::Truncated)?;
+        self.position = end;
+
+        Ok(slice)
+    }
+}
+
+/// Canonical Huffman code stored as code counts per length plus symbols
+/// ordered by code, decoded one bit at a time.
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self, ImageError> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = (left << 1) - count as i32;
+            if left < 0 {
+                return Err(ImageError::Malformed("over-subscribed Huffman code".into()));
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for length in 1..=MAX_BITS {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+
+        Ok(Self { counts, symbols })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, ImageError> {
+        let mut code = 0i32;
+        let mut first = 0i32;
+        let mut index = 0i32;
+
+        for length in 1..=MAX_BITS {
+            code |= reader.bits(1)? as i32;
+            let count = self.counts[length] as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + (code - first)) as usize]);
+            }
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+
+        Err(ImageError::Malformed("invalid Huffman code".into()))
+    }
+}
+
+fn fixed_tables() -> Result<(Huffman, Huffman), ImageError> {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5u8; 30])?))
+}
+
+fn dynamic_tables(reader: &mut BitReader) -> Result<(Huffman, Huffman), ImageError> {
+    let literal_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_length_count = reader.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for &index in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[index] = reader.bits(3)? as u8;
+    }
+    let code_length_code = Huffman::new(&code_lengths)?;
+
+    let mut lengths = vec![0u8; literal_count + distance_count];
+    let mut i = 0;
+    while i < lengths.len() {
+        let symbol = code_length_code.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                if i == 0 {
+                    return Err(ImageError::Malformed(
+                        "repeat with no previous length".into(),
+                    ));
+                }
+                (lengths[i - 1], 3 + reader.bits(2)? as usize)
+            }
+            17 => (0, 3 + reader.bits(3)? as usize),
+            _ => (0, 11 + reader.bits(7)? as usize),
+        };
+
+        if i + repeat > lengths.len() {
+            return Err(ImageError::Malformed("too many code lengths".into()));
+        }
+        lengths[i..i + repeat].fill(value);
+        i += repeat;
+    }
+
+    Ok((
+        Huffman::new(&lengths[..literal_count])?,
+        Huffman::new(&lengths[literal_count..])?,
+    ))
+}
+
+fn too_long() -> ImageError {
+    ImageError::Malformed("more compressed data than expected".into())
+}
+
+fn inflate_block(
+    reader: &mut BitReader,
+    output: &mut Vec<u8>,
+    limit: usize,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> Result<(), ImageError> {
+    loop {
+        let symbol = literals.decode(reader)? as usize;
+        match symbol {
+            0..=255 => {
+                if output.len() >= limit {
+                    return Err(too_long());
+                }
+                output.push(symbol as u8);
+            }
+            256 => return Ok(()),
+            257..=285 => {
+                let index = symbol - 257;
+                let length =
+                    LENGTH_BASE[index] as usize + reader.bits(LENGTH_EXTRA[index] as u32)? as usize;
+
+                let index = distances.decode(reader)? as usize;
+                if index >= DIST_BASE.len() {
+                    return Err(ImageError::Malformed("invalid distance code".into()));
+                }
+                let distance =
+                    DIST_BASE[index] as usize + reader.bits(DIST_EXTRA[index] as u32)? as usize;
+                if distance > output.len() {
+                    return Err(ImageError::Malformed(
+                        "distance before start of data".into(),
+                    ));
+                }
+                if length > limit - output.len() {
+                    return Err(too_long());
+                }
+
+                // Copies may overlap their own output, so go byte by byte.
+                let start = output.len() - distance;
+                for k in 0..length {
+                    output.push(output[start + k]);
+                }
+            }
+            _ => return Err(ImageError::Malformed("invalid literal/length code".into())),
+        }
+    }
+}
+
+/// Decompresses `data`, failing rather than producing more than `limit`
+/// bytes, so that a small stream can't expand without bound.
+pub fn inflate(data: &[u8], limit: usize) -> Result<Vec<u8>, ImageError> {
+    let mut reader = BitReader::new(data);
+    let mut output = Vec::new();
+
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align_to_byte();
+                let header = reader.bytes(4)?;
+                let length = u16::from_le_bytes([header[0], header[1]]);
+                let complement = u16::from_le_bytes([header[2], header[3]]);
+                if length != !complement {
+                    return Err(ImageError::Malformed("stored block length mismatch".into()));
+                }
+                if length as usize > limit - output.len() {
+                    return Err(too_long());
+                }
+                output.extend_from_slice(reader.bytes(length as usize)?);
+            }
+            1 => {
+                let (literals, distances) = fixed_tables()?;
+                inflate_block(&mut reader, &mut output, limit, &literals, &distances)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_tables(&mut reader)?;
+                inflate_block(&mut reader, &mut output, limit, &literals, &distances)?;
+            }
+            _ => return Err(ImageError::Malformed("invalid deflate block type".into())),
+        }
+
+        if last {
+            return Ok(output);
+        }
+    }
+}
+
+/// Decompresses a zlib stream of at most `limit` bytes and checks it.
+pub fn zlib_decompress(data: &[u8], limit: usize) -> Result<Vec<u8>, ImageError> {
+    if data.len() < 6 {
+        return Err(ImageError::Truncated);
+    }
+
+    let cmf = data[0];
+    let flg = data[1];
+    if cmf & 0x0f != 8 || !((cmf as u16) << 8 | flg as u16).is_multiple_of(31) {
+        return Err(ImageError::Malformed("invalid zlib header".into()));
+    }
+    if flg & 0x20 != 0 {
+        return Err(ImageError::Unsupported("zlib preset dictionary".into()));
+    }
+
+    let output = inflate(&data[2..], limit)?;
+
+    let trailer = &data[data.len() - 4..];
+    let expected = u32::from_be_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    if adler32(&output) != expected {
+        return Err(ImageError::Malformed("zlib checksum mismatch".into()));
+    }
+
+    Ok(output)
+}
+
+pub(crate) fn adler32(data: &[u8]) -> u32 {
+    let mut a = 1u32;
+    let mut b = 0u32;
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Streams made by zlib at the noted settings.
+
+    /// "stored" at level 0.
+    const STORED: [u8; 17] = [
+        0x78, 0x01, 0x01, 0x06, 0x00, 0xf9, 0xff, 0x73, 0x74, 0x6f, 0x72, 0x65, 0x64, 0x09, 0x3c,
+        0x02, 0x92,
+    ];
+
+    /// "abcabc..." with fixed codes, one copy at distance 3.
+    const FIXED: [u8; 13] = [
+        0x78, 0x01, 0x4b, 0x4c, 0x4a, 0x4e, 0xc4, 0x82, 0x00, 0x69, 0xaf, 0x08, 0xce,
+    ];
+
+    /// Twenty `a`s with fixed codes, a copy at distance 1 from one literal.
+    const RUN: [u8; 11] = [
+        0x78, 0x01, 0x4b, 0x4c, 0xc4, 0x04, 0x00, 0x4f, 0xa6, 0x07, 0x95,
+    ];
+
+    /// `DYNAMIC_TEXT` with dynamic codes.
+    const DYNAMIC: [u8; 45] = [
+        0x78, 0xda, 0x25, 0x8a, 0xc1, 0x0d, 0x00, 0x40, 0x0c, 0x82, 0x66, 0x05, 0xeb, 0xfe, 0x2b,
+        0x5c, 0x9b, 0xf3, 0x61, 0x0c, 0xc8, 0xa0, 0x22, 0xa4, 0x09, 0x50, 0xe6, 0x6a, 0xb3, 0x3c,
+        0xab, 0xce, 0x61, 0xbe, 0xb9, 0xeb, 0x6e, 0x99, 0x6a, 0x5b, 0x1e, 0x1c, 0xd5, 0x18, 0x8b,
+    ];
+    const DYNAMIC_TEXT: &[u8] = b"adabbbabaaceccaaaeadaaeaaaabbbcbbaabaaabcaaeadabbabbcabadebbeeea";
+
+    /// The deflate block type of a zlib stream's first block.
+    fn block_type(stream: &[u8]) -> u8 {
+        (stream[2] >> 1) & 3
+    }
+
+    #[test]
+    fn stored_block() {
+        assert_eq!(block_type(&STORED), 0);
+        assert_eq!(zlib_decompress(&STORED, 100).unwrap(), b"stored");
+    }
+
+    #[test]
+    fn fixed_block_with_overlapping_copy() {
+        assert_eq!(block_type(&FIXED), 1);
+        assert_eq!(
+            zlib_decompress(&FIXED, 100).unwrap(),
+            b"abcabcabcabcabcabcabcab"
+        );
+    }
+
+    #[test]
+    fn copy_of_one_byte_repeats_it() {
+        assert_eq!(zlib_decompress(&RUN, 100).unwrap(), [b'a'; 20]);
+    }
+
+    #[test]
+    fn dynamic_block() {
+        assert_eq!(block_type(&DYNAMIC), 2);
+        assert_eq!(zlib_decompress(&DYNAMIC, 100).unwrap(), DYNAMIC_TEXT);
+    }
+
+    #[test]
+    fn adler_mismatch_is_an_error() {
+        let mut stream = DYNAMIC;
+        stream[44] ^= 1;
+        assert!(matches!(
+            zlib_decompress(&stream, 100),
+            Err(ImageError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn truncated_streams_are_errors() {
+        for stream in [&STORED[..], &FIXED[..], &DYNAMIC[..]] {
+            for length in 0..stream.len() - 6 {
+                assert!(
+                    inflate(&stream[2..2 + length], 100).is_err(),
+                    "length {}",
+                    length
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn output_is_limited() {
+        assert_eq!(inflate(&RUN[2..], 20).unwrap().len(), 20);
+        for stream in [&STORED[..], &FIXED[..], &RUN[..], &DYNAMIC[..]] {
+            let length = inflate(&stream[2..], 100).unwrap().len();
+            assert!(matches!(
+                inflate(&stream[2..], length - 1),
+                Err(ImageError::Malformed(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn reserved_block_type_is_an_error() {
+        assert!(matches!(
+            inflate(&[0x07], 100),
+            Err(ImageError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn stored_length_must_match_its_complement() {
+        assert!(matches!(
+            inflate(&[0x01, 0x06, 0x00, 0xf8, 0xff, 0, 0, 0, 0, 0, 0], 100),
+            Err(ImageError::Malformed(_))
+        ));
+    }
+}
@@ -0,0 +1,30 @@
+use crate::images::error::ImageError;
+use crate::images::image::{ColorSpace, Image};
+use crate::images::{hdr, pfm, png, pnm};
+use std::fs;
+use std::path::Path;
+
+pub fn load_image(path: &Path, color_space: ColorSpace) -> Result<Image, ImageError> {
+    let data = fs::read(path)?;
+
+    decode_image(&data, color_space)
+}
+
+/// Picks the decoder from the file's magic bytes rather than its extension.
+/// `color_space` says how integer samples are read; float formats are
+/// linear already.
+pub fn decode_image(data: &[u8], color_space: ColorSpace) -> Result<Image, ImageError> {
+    if data.starts_with(&png::SIGNATURE) {
+        png::decode(data, color_space)
+    } else if data.starts_with(b"#?") {
+        hdr::decode(data)
+    } else if data.starts_with(b"PF") || data.starts_with(b"Pf") {
+        pfm::decode(data)
+    } else if data.len() >= 2 && data[0] == b'P' && data[1].is_ascii_digit() {
+        pnm::decode(data, color_space)
+    } else if data.is_empty() {
+        Err(ImageError::Truncated)
+    } else {
+        Err(ImageError::Unsupported("unrecognized image format".into()))
+    }
+}
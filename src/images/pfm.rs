@@ -0,0 +1,132 @@
+use crate::images::error::ImageError;
+use crate::images::image::Image;
+use crate::math::color::Color;
+
+/// Decodes Portable Float Map (.pfm) files, color (PF) or greyscale (Pf).
+/// Values are linear and are returned scaled by the header's scale factor.
+pub fn decode(data: &[u8]) -> Result<Image, ImageError> {
+    let mut position = 0;
+    let mut token = || -> Result<String, ImageError> {
+        while data
+            .get(position)
+            .is_some_and(|byte| byte.is_ascii_whitespace())
+        {
+            position += 1;
+        }
+        let start = position;
+        while data
+            .get(position)
+            .is_some_and(|byte| !byte.is_ascii_whitespace())
+        {
+            position += 1;
+        }
+        if start == position {
+            return Err(ImageError::Truncated);
+        }
+        Ok(String::from_utf8_lossy(&data[start..position]).into_owned())
+    };
+
+    let channels = match token()?.as_str() {
+        "PF" => 3,
+        "Pf" => 1,
+        magic => return Err(ImageError::Malformed(format!("PFM magic {:?}", magic))),
+    };
+    let size = |text: String| match text.parse::<usize>() {
+        Ok(0) | Err(_) => Err(ImageError::Malformed(format!("PFM size {:?}", text))),
+        Ok(size) => Ok(size),
+    };
+    let width = size(token()?)?;
+    let height = size(token()?)?;
+    let scale_text = token()?;
+    let scale: f32 = scale_text
+        .parse()
+        .map_err(|_| ImageError::Malformed(format!("PFM scale {:?}", scale_text)))?;
+    if scale == 0.0 || !scale.is_finite() {
+        return Err(ImageError::Malformed(format!("PFM scale {:?}", scale_text)));
+    }
+
+    // Exactly one whitespace byte separates the header from the raster. A
+    // negative scale means little-endian samples.
+    position += 1;
+    let little_endian = scale < 0.0;
+    let length = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(4 * channels))
+        .ok_or_else(|| ImageError::Malformed(format!("PFM size {}x{}", width, height)))?;
+    let bytes = data
+        .get(position..)
+        .and_then(|rest| rest.get(..length))
+        .ok_or(ImageError::Truncated)?;
+    let samples: Vec<f64> = bytes
+        .chunks(4)
+        .map(|chunk| {
+            let chunk = [chunk[0], chunk[1], chunk[2], chunk[3]];
+            let value = if little_endian {
+                f32::from_le_bytes(chunk)
+            } else {
+                f32::from_be_bytes(chunk)
+            };
+            (value * scale.abs()) as f64
+        })
+        .collect();
+
+    // Rows are stored bottom to top.
+    let mut pixels = Vec::with_capacity(width * height);
+    for row in samples.chunks(width * channels).rev() {
+        pixels.extend(row.chunks(channels).map(|pixel| match pixel {
+            [g] => Color::new(*g, *g, *g),
+            _ => Color::new(pixel[0], pixel[1], pixel[2]),
+        }));
+    }
+
+    Ok(Image::new(width as u32, height as u32, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn greyscale(header: &str, values: &[f32]) -> Vec<u8> {
+        let mut file = header.as_bytes().to_vec();
+        for value in values {
+            file.extend_from_slice(&value.to_le_bytes());
+        }
+        file
+    }
+
+    #[test]
+    fn rows_run_bottom_to_top() {
+        let image = decode(&greyscale("Pf\n1 2\n-2.0\n", &[0.25, 1.5])).unwrap();
+
+        assert_eq!(image.pixel(0, 0).r(), 3.0);
+        assert_eq!(image.pixel(0, 1).r(), 0.5);
+    }
+
+    #[test]
+    fn positive_scale_is_big_endian() {
+        let mut file = b"PF\n1 1\n1.0\n".to_vec();
+        for value in [1.0f32, 2.0, 4.0] {
+            file.extend_from_slice(&value.to_be_bytes());
+        }
+        let pixel = decode(&file).unwrap().pixel(0, 0);
+
+        assert_eq!((pixel.r(), pixel.g(), pixel.b()), (1.0, 2.0, 4.0));
+    }
+
+    #[test]
+    fn truncated_files_are_errors() {
+        let file = greyscale("Pf\n1 2\n-1.0\n", &[0.25, 1.5]);
+        assert!(decode(&file).is_ok());
+        for length in 0..file.len() {
+            assert!(decode(&file[..length]).is_err(), "length {}", length);
+        }
+    }
+
+    #[test]
+    fn oversized_header_is_an_error() {
+        let file = greyscale("PF\n4000000000 4000000000\n-1.0\n", &[0.0]);
+        assert!(matches!(decode(&file), Err(ImageError::Malformed(_))));
+        let file = greyscale("PF\n400000 400000\n-1.0\n", &[0.0]);
+        assert!(matches!(decode(&file), Err(ImageError::Truncated)));
+    }
+}
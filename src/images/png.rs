@@ -0,0 +1,591 @@
+use crate::images::error::ImageError;
+use crate::images::image::{ColorSpace, Image};
+use crate::images::inflate::zlib_decompress;
+use crate::math::color::Color;
+
+pub const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+/// Starting column/row and column/row step of the seven Adam7 passes.
+const ADAM7: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+#[derive(Copy, Clone, PartialEq)]
+enum ColorType {
+    Gray,
+    Rgb,
+    Palette,
+    GrayAlpha,
+    Rgba,
+}
+
+impl ColorType {
+    fn from_byte(byte: u8) -> Result<Self, ImageError> {
+        match byte {
+            0 => Ok(ColorType::Gray),
+            2 => Ok(ColorType::Rgb),
+            3 => Ok(ColorType::Palette),
+            4 => Ok(ColorType::GrayAlpha),
+            6 => Ok(ColorType::Rgba),
+            _ => Err(ImageError::Malformed(format!("PNG color type {}", byte))),
+        }
+    }
+
+    fn channels(&self) -> usize {
+        match self {
+            ColorType::Gray | ColorType::Palette => 1,
+            ColorType::GrayAlpha => 2,
+            ColorType::Rgb => 3,
+            ColorType::Rgba => 4,
+        }
+    }
+
+    fn allows_depth(&self, depth: u8) -> bool {
+        match self {
+            ColorType::Gray => matches!(depth, 1 | 2 | 4 | 8 | 16),
+            ColorType::Palette => matches!(depth, 1 | 2 | 4 | 8),
+            _ => matches!(depth, 8 | 16),
+        }
+    }
+}
+
+struct Header {
+    width: usize,
+    height: usize,
+    depth: u8,
+    color_type: ColorType,
+    interlaced: bool,
+}
+
+/// Transparency from a tRNS chunk: per-entry alpha for palettes, or the one
+/// color (in raw sample values) that is fully transparent otherwise.
+enum Transparency {
+    None,
+    Palette(Vec<u8>),
+    Key([u16; 3]),
+}
+
+pub fn decode(data: &[u8], color_space: ColorSpace) -> Result<Image, ImageError> {
+    if data.len() < SIGNATURE.len() {
+        return Err(ImageError::Truncated);
+    }
+    if data[..SIGNATURE.len()] != SIGNATURE {
+        return Err(ImageError::Malformed("missing PNG signature".into()));
+    }
+
+    let mut header = None;
+    let mut palette: Vec<[u8; 3]> = vec![];
+    let mut transparency = Transparency::None;
+    let mut compressed = vec![];
+    let mut ended = false;
+
+    let mut position = SIGNATURE.len();
+    while !ended {
+        let length_bytes = data
+            .get(position..position + 4)
+            .ok_or(ImageError::Truncated)?;
+        let length = u32::from_be_bytes(length_bytes.try_into().unwrap()) as usize;
+        let chunk = data
+            .get(position + 4..position + 8 + length)
+            .ok_or(ImageError::Truncated)?;
+        let crc_bytes = data
+            .get(position + 8 + length..position + 12 + length)
+            .ok_or(ImageError::Truncated)?;
+        if crc32(chunk) != u32::from_be_bytes(crc_bytes.try_into().unwrap()) {
+            return Err(ImageError::Malformed("PNG chunk CRC mismatch".into()));
+        }
+        position += 12 + length;
+
+        let (kind, body) = chunk.split_at(4);
+        match kind {
+            b"IHDR" => header = Some(parse_header(body)?),
+            b"PLTE" => {
+                if body.len() % 3 != 0 || body.len() > 256 * 3 {
+                    return Err(ImageError::Malformed("PNG palette size".into()));
+                }
+                palette = body.chunks(3).map(|c| [c[0], c[1], c[2]]).collect();
+            }
+            b"tRNS" => {
+                let header = header
+                    .as_ref()
+                    .ok_or_else(|| ImageError::Malformed("tRNS chunk before IHDR".into()))?;
+                transparency = parse_transparency(header, body)?;
+            }
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => ended = true,
+            _ => {
+                // Ancillary chunks have a lowercase first letter and may be skipped.
+                if kind[0].is_ascii_uppercase() {
+                    return Err(ImageError::Unsupported(format!(
+                        "PNG critical chunk {}",
+                        String::from_utf8_lossy(kind)
+                    )));
+                }
+            }
+        }
+    }
+
+    let header = header.ok_or_else(|| ImageError::Malformed("missing IHDR chunk".into()))?;
+    if header.color_type == ColorType::Palette && palette.is_empty() {
+        return Err(ImageError::Malformed(
+            "palette image without PLTE chunk".into(),
+        ));
+    }
+
+    let raw_size =
+        raw_size(&header).ok_or_else(|| ImageError::Malformed("PNG image size".into()))?;
+    let raw = zlib_decompress(&compressed, raw_size)?;
+    let samples = unfilter_image(&header, &raw)?;
+
+    Ok(to_image(
+        &header,
+        &samples,
+        &palette,
+        &transparency,
+        color_space,
+    ))
+}
+
+fn parse_header(body: &[u8]) -> Result<Header, ImageError> {
+    if body.len() != 13 {
+        return Err(ImageError::Malformed("IHDR length".into()));
+    }
+
+    let width = u32::from_be_bytes(body[0..4].try_into().unwrap()) as usize;
+    let height = u32::from_be_bytes(body[4..8].try_into().unwrap()) as usize;
+    let depth = body[8];
+    let color_type = ColorType::from_byte(body[9])?;
+
+    if width == 0 || height == 0 {
+        return Err(ImageError::Malformed("zero image size".into()));
+    }
+    if !color_type.allows_depth(depth) {
+        return Err(ImageError::Malformed(format!("PNG bit depth {}", depth)));
+    }
+    if body[10] != 0 || body[11] != 0 {
+        return Err(ImageError::Unsupported(
+            "PNG compression or filter method".into(),
+        ));
+    }
+
+    let interlaced = match body[12] {
+        0 => false,
+        1 => true,
+        other => return Err(ImageError::Malformed(format!("PNG interlace {}", other))),
+    };
+
+    Ok(Header {
+        width,
+        height,
+        depth,
+        color_type,
+        interlaced,
+    })
+}
+
+fn parse_transparency(header: &Header, body: &[u8]) -> Result<Transparency, ImageError> {
+    let read = |i: usize| -> Result<u16, ImageError> {
+        let bytes = body.get(2 * i..2 * i + 2).ok_or(ImageError::Truncated)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    };
+
+    match header.color_type {
+        ColorType::Palette => Ok(Transparency::Palette(body.to_vec())),
+        ColorType::Gray => {
+            let gray = read(0)?;
+            Ok(Transparency::Key([gray, gray, gray]))
+        }
+        ColorType::Rgb => Ok(Transparency::Key([read(0)?, read(1)?, read(2)?])),
+        _ => Err(ImageError::Malformed("tRNS on an image with alpha".into())),
+    }
+}
+
+/// Starting column/row and column/row step of each pass over the image.
+fn passes(header: &Header) -> &'static [(usize, usize, usize, usize)] {
+    if header.interlaced {
+        &ADAM7
+    } else {
+        &[(0, 0, 1, 1)]
+    }
+}
+
+/// Length of the filtered data, every pass's rows with their filter bytes,
+/// or none if it's too large to address.
+fn raw_size(header: &Header) -> Option<usize> {
+    let bits_per_pixel = header.color_type.channels() * header.depth as usize;
+
+    passes(header)
+        .iter()
+        .try_fold(0usize, |total, &(x0, y0, dx, dy)| {
+            if x0 >= header.width || y0 >= header.height {
+                return Some(total);
+            }
+
+            let pass_width = (header.width - x0).div_ceil(dx);
+            let pass_height = (header.height - y0).div_ceil(dy);
+            let row_bytes = pass_width.checked_mul(bits_per_pixel)?.div_ceil(8);
+            total.checked_add(pass_height.checked_mul(row_bytes + 1)?)
+        })
+}
+
+/// Reverses the per-scanline filters and returns one sample per channel per
+/// pixel in raster order, de-interlacing if needed.
+fn unfilter_image(header: &Header, raw: &[u8]) -> Result<Vec<u16>, ImageError> {
+    // All the filtered data has to be there before the samples, which take
+    // up to 16 times as much room, are allocated.
+    if raw_size(header).is_none_or(|size| raw.len() < size) {
+        return Err(ImageError::Truncated);
+    }
+
+    let channels = header.color_type.channels();
+    let mut samples = vec![0u16; header.width * header.height * channels];
+
+    let mut offset = 0;
+    for &(x0, y0, dx, dy) in passes(header) {
+        if x0 >= header.width || y0 >= header.height {
+            continue;
+        }
+
+        let pass_width = (header.width - x0).div_ceil(dx);
+        let pass_height = (header.height - y0).div_ceil(dy);
+        let bits_per_pixel = channels * header.depth as usize;
+        let row_bytes = (pass_width * bits_per_pixel).div_ceil(8);
+        let pixel_bytes = bits_per_pixel.div_ceil(8);
+
+        let mut previous = vec![0u8; row_bytes];
+        let mut current = vec![0u8; row_bytes];
+        for row in 0..pass_height {
+            let filter = *raw.get(offset).ok_or(ImageError::Truncated)?;
+            let line = raw
+                .get(offset + 1..offset + 1 + row_bytes)
+                .ok_or(ImageError::Truncated)?;
+            offset += 1 + row_bytes;
+
+            unfilter_row(filter, line, &previous, &mut current, pixel_bytes)?;
+
+            let y = y0 + row * dy;
+            for column in 0..pass_width {
+                let x = x0 + column * dx;
+                for channel in 0..channels {
+                    let index = column * channels + channel;
+                    samples[(y * header.width + x) * channels + channel] =
+                        read_sample(&current, index, header.depth);
+                }
+            }
+
+            std::mem::swap(&mut previous, &mut current);
+        }
+    }
+
+    Ok(samples)
+}
+
+fn unfilter_row(
+    filter: u8,
+    line: &[u8],
+    previous: &[u8],
+    current: &mut [u8],
+    pixel_bytes: usize,
+) -> Result<(), ImageError> {
+    for i in 0..line.len() {
+        let left = if i >= pixel_bytes {
+            current[i - pixel_bytes]
+        } else {
+            0
+        };
+        let up = previous[i];
+        let up_left = if i >= pixel_bytes {
+            previous[i - pixel_bytes]
+        } else {
+            0
+        };
+
+        let predictor = match filter {
+            0 => 0,
+            1 => left,
+            2 => up,
+            3 => ((left as u16 + up as u16) / 2) as u8,
+            4 => paeth(left, up, up_left),
+            _ => return Err(ImageError::Malformed(format!("PNG filter type {}", filter))),
+        };
+        current[i] = line[i].wrapping_add(predictor);
+    }
+
+    Ok(())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+fn read_sample(row: &[u8], index: usize, depth: u8) -> u16 {
+    match depth {
+        16 => u16::from_be_bytes([row[2 * index], row[2 * index + 1]]),
+        8 => row[index] as u16,
+        _ => {
+            // Sub-byte samples are packed from the most significant bit.
+            let depth = depth as usize;
+            let bit = index * depth;
+            let shift = 8 - depth - bit % 8;
+            ((row[bit / 8] >> shift) & ((1u8 << depth) - 1)) as u16
+        }
+    }
+}
+
+fn to_image(
+    header: &Header,
+    samples: &[u16],
+    palette: &[[u8; 3]],
+    transparency: &Transparency,
+    color_space: ColorSpace,
+) -> Image {
+    let channels = header.color_type.channels();
+    let max = ((1u32 << header.depth) - 1) as f64;
+    let color = |sample: u16| color_space.decode(sample as f64 / max);
+
+    let pixel_count = header.width * header.height;
+    let mut pixels = Vec::with_capacity(pixel_count);
+    let mut alpha = Vec::with_capacity(pixel_count);
+
+    for pixel in samples.chunks(channels) {
+        let (c, a) = match header.color_type {
+            ColorType::Gray => {
+                let g = color(pixel[0]);
+                let a = match transparency {
+                    Transparency::Key(key) if key[0] == pixel[0] => 0.0,
+                    _ => 1.0,
+                };
+                (Color::new(g, g, g), a)
+            }
+            ColorType::GrayAlpha => {
+                let g = color(pixel[0]);
+                (Color::new(g, g, g), pixel[1] as f64 / max)
+            }
+            ColorType::Rgb => {
+                let a = match transparency {
+                    Transparency::Key(key) if key[..] == pixel[..3] => 0.0,
+                    _ => 1.0,
+                };
+                (
+                    Color::new(color(pixel[0]), color(pixel[1]), color(pixel[2])),
+                    a,
+                )
+            }
+            ColorType::Rgba => (
+                Color::new(color(pixel[0]), color(pixel[1]), color(pixel[2])),
+                pixel[3] as f64 / max,
+            ),
+            ColorType::Palette => {
+                let index = pixel[0] as usize;
+                // Out-of-range indices are an error in the spec; render them black.
+                let entry = palette.get(index).copied().unwrap_or([0, 0, 0]);
+                let a = match transparency {
+                    Transparency::Palette(alphas) => {
+                        alphas.get(index).map_or(1.0, |&a| a as f64 / 255.0)
+                    }
+                    _ => 1.0,
+                };
+                let channel = |v: u8| color_space.decode(v as f64 / 255.0);
+                (
+                    Color::new(channel(entry[0]), channel(entry[1]), channel(entry[2])),
+                    a,
+                )
+            }
+        };
+
+        pixels.push(c);
+        alpha.push(a);
+    }
+
+    let image = Image::new(header.width as u32, header.height as u32, pixels);
+    let has_alpha = matches!(header.color_type, ColorType::GrayAlpha | ColorType::Rgba)
+        || !matches!(transparency, Transparency::None);
+
+    if has_alpha {
+        image.with_alpha(alpha)
+    } else {
+        image
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::images::image::srgb_to_linear;
+    use crate::images::inflate::adler32;
+
+    fn srgb(data: &[u8]) -> Result<Image, ImageError> {
+        decode(data, ColorSpace::Srgb)
+    }
+
+    fn chunk(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut typed = kind.to_vec();
+        typed.extend_from_slice(body);
+
+        let mut chunk = (body.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(&typed);
+        chunk.extend_from_slice(&crc32(&typed).to_be_bytes());
+        chunk
+    }
+
+    /// An 8-bit greyscale PNG holding `raw` in one stored deflate block.
+    fn gray_png(width: u32, height: u32, interlaced: bool, raw: &[u8]) -> Vec<u8> {
+        let mut header = width.to_be_bytes().to_vec();
+        header.extend_from_slice(&height.to_be_bytes());
+        header.extend_from_slice(&[8, 0, 0, 0, interlaced as u8]);
+
+        let length = raw.len() as u16;
+        let mut stream = vec![0x78, 0x01, 0x01];
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(raw);
+        stream.extend_from_slice(&adler32(raw).to_be_bytes());
+
+        let mut png = SIGNATURE.to_vec();
+        png.extend(chunk(b"IHDR", &header));
+        png.extend(chunk(b"IDAT", &stream));
+        png.extend(chunk(b"IEND", &[]));
+        png
+    }
+
+    fn gray(image: &Image, x: u32, y: u32) -> f64 {
+        image.pixel(x, y).r()
+    }
+
+    fn level(value: u8) -> f64 {
+        srgb_to_linear(value as f64 / 255.0)
+    }
+
+    #[test]
+    fn unfilters_each_filter_type() {
+        // Two bytes a pixel, with a previous row that makes the Paeth
+        // predictor pick the left, upper and upper-left byte in turn.
+        let previous = [167, 53, 108, 136, 145, 63];
+        let line = [32, 246, 247, 45, 176, 34];
+        let expected = [
+            [32, 246, 247, 45, 176, 34],
+            [32, 246, 23, 35, 199, 69],
+            [199, 43, 99, 181, 65, 97],
+            [115, 16, 102, 121, 43, 126],
+            [199, 43, 158, 181, 78, 170],
+        ];
+
+        for (filter, expected) in expected.iter().enumerate() {
+            let mut current = [0; 6];
+            unfilter_row(filter as u8, &line, &previous, &mut current, 2).unwrap();
+            assert_eq!(&current, expected, "filter {}", filter);
+        }
+    }
+
+    #[test]
+    fn unknown_filter_type_is_an_error() {
+        let mut current = [0; 2];
+        assert!(matches!(
+            unfilter_row(5, &[0, 0], &[0, 0], &mut current, 1),
+            Err(ImageError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn decodes_filtered_rows() {
+        // Rows of 10, 20, 30 and 40, 60, 80, the second stored as the
+        // difference from the one above.
+        let raw = [1, 10, 10, 10, 2, 30, 40, 50];
+        let image = srgb(&gray_png(3, 2, false, &raw)).unwrap();
+
+        assert_eq!((image.width(), image.height()), (3, 2));
+        assert!(!image.has_alpha());
+        for (x, value) in [10, 20, 30].into_iter().enumerate() {
+            assert_eq!(gray(&image, x as u32, 0), level(value));
+        }
+        for (x, value) in [40, 60, 80].into_iter().enumerate() {
+            assert_eq!(gray(&image, x as u32, 1), level(value));
+        }
+    }
+
+    #[test]
+    fn linear_samples_are_read_as_stored() {
+        let raw = [0, 0, 128, 255];
+        let image = decode(&gray_png(3, 1, false, &raw), ColorSpace::Linear).unwrap();
+
+        assert_eq!(gray(&image, 1, 0), 128.0 / 255.0);
+        assert_eq!(gray(&image, 2, 0), 1.0);
+    }
+
+    #[test]
+    fn deinterlaces_adam7() {
+        // A 3x3 image has five non-empty passes: (0, 0); (2, 0); (0, 2) and
+        // (2, 2); (1, 0) then (1, 2); and the middle row.
+        let raw = [0, 0, 0, 2, 0, 6, 8, 0, 1, 0, 7, 0, 3, 4, 5];
+        let image = srgb(&gray_png(3, 3, true, &raw)).unwrap();
+
+        for y in 0..3 {
+            for x in 0..3 {
+                assert_eq!(
+                    gray(&image, x, y),
+                    level((3 * y + x) as u8),
+                    "({}, {})",
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn truncated_files_are_errors() {
+        let png = gray_png(3, 2, false, &[0, 1, 2, 3, 0, 4, 5, 6]);
+        assert!(srgb(&png).is_ok());
+        for length in 0..png.len() {
+            assert!(srgb(&png[..length]).is_err(), "length {}", length);
+        }
+    }
+
+    #[test]
+    fn too_little_image_data_is_truncated() {
+        let png = gray_png(3, 2, false, &[0, 1, 2, 3]);
+        assert!(matches!(srgb(&png), Err(ImageError::Truncated)));
+
+        // Far too little for the size the header gives, which mustn't be
+        // allocated.
+        let png = gray_png(200_000, 200_000, false, &[0; 16]);
+        assert!(matches!(srgb(&png), Err(ImageError::Truncated)));
+    }
+
+    #[test]
+    fn chunk_crc_mismatch_is_an_error() {
+        let mut png = gray_png(1, 1, false, &[0, 0]);
+        png[SIGNATURE.len() + 8] ^= 1;
+        assert!(matches!(srgb(&png), Err(ImageError::Malformed(_))));
+    }
+}
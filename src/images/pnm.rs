@@ -0,0 +1,201 @@
+use crate::images::error::ImageError;
+use crate::images::image::{ColorSpace, Image};
+use crate::math::color::Color;
+
+/// Decodes binary (P5/P6) and ASCII (P2/P3) PGM and PPM files, with 8- or
+/// 16-bit samples.
+pub fn decode(data: &[u8], color_space: ColorSpace) -> Result<Image, ImageError> {
+    let mut reader = Reader { data, position: 0 };
+
+    let magic = reader.token()?;
+    let (channels, binary) = match magic.as_str() {
+        "P2" => (1, false),
+        "P3" => (3, false),
+        "P5" => (1, true),
+        "P6" => (3, true),
+        "P1" | "P4" => return Err(ImageError::Unsupported("PBM bitmaps".into())),
+        _ => return Err(ImageError::Malformed(format!("PNM magic {:?}", magic))),
+    };
+
+    let width = reader.number()?;
+    let height = reader.number()?;
+    let max_value = reader.number()?;
+    if width == 0 || height == 0 {
+        return Err(ImageError::Malformed("zero image size".into()));
+    }
+    if max_value == 0 || max_value > 65535 {
+        return Err(ImageError::Malformed(format!(
+            "PNM max value {}",
+            max_value
+        )));
+    }
+
+    let count = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(channels))
+        .ok_or_else(|| ImageError::Malformed(format!("PNM size {}x{}", width, height)))?;
+    let samples = if binary {
+        // Exactly one whitespace byte separates the header from the raster.
+        reader.position += 1;
+        reader.binary_samples(count, max_value > 255)?
+    } else {
+        (0..count)
+            .map(|_| reader.number())
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    let max = max_value as f64;
+    let channel = |sample: u32| color_space.decode((sample as f64 / max).min(1.0));
+    let pixels = samples
+        .chunks(channels)
+        .map(|pixel| match pixel {
+            [g] => {
+                let g = channel(*g);
+                Color::new(g, g, g)
+            }
+            _ => Color::new(channel(pixel[0]), channel(pixel[1]), channel(pixel[2])),
+        })
+        .collect();
+
+    Ok(Image::new(width, height, pixels))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(&byte) = self.data.get(self.position) {
+            if byte == b'#' {
+                while let Some(&byte) = self.data.get(self.position) {
+                    self.position += 1;
+                    if byte == b'\n' {
+                        break;
+                    }
+                }
+            } else if byte.is_ascii_whitespace() {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn token(&mut self) -> Result<String, ImageError> {
+        self.skip_whitespace_and_comments();
+
+        let start = self.position;
+        while let Some(&byte) = self.data.get(self.position) {
+            if byte.is_ascii_whitespace() || byte == b'#' {
+                break;
+            }
+            self.position += 1;
+        }
+
+        if start == self.position {
+            return Err(ImageError::Truncated);
+        }
+
+        Ok(String::from_utf8_lossy(&self.data[start..self.position]).into_owned())
+    }
+
+    fn number(&mut self) -> Result<u32, ImageError> {
+        let token = self.token()?;
+        token
+            .parse()
+            .map_err(|_| ImageError::Malformed(format!("expected a number, found {:?}", token)))
+    }
+
+    fn binary_samples(&mut self, count: usize, wide: bool) -> Result<Vec<u32>, ImageError> {
+        let size = if wide { 2 } else { 1 };
+        let length = count
+            .checked_mul(size)
+            .ok_or_else(|| ImageError::Malformed("PNM raster size".into()))?;
+        let bytes = self
+            .data
+            .get(self.position..)
+            .and_then(|rest| rest.get(..length))
+            .ok_or(ImageError::Truncated)?;
+        self.position += length;
+
+        Ok(if wide {
+            bytes
+                .chunks(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]) as u32)
+                .collect()
+        } else {
+            bytes.iter().map(|&byte| byte as u32).collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::images::image::srgb_to_linear;
+
+    fn srgb(data: &[u8]) -> Result<Image, ImageError> {
+        decode(data, ColorSpace::Srgb)
+    }
+
+    #[test]
+    fn decodes_ascii_with_comments() {
+        let image = srgb(b"P3\n# a comment\n2 1 # size\n255\n255 0 0  0 0 51\n").unwrap();
+
+        assert_eq!((image.width(), image.height()), (2, 1));
+        assert_eq!(image.pixel(0, 0).r(), 1.0);
+        assert_eq!(image.pixel(0, 0).g(), 0.0);
+        assert_eq!(image.pixel(1, 0).b(), srgb_to_linear(0.2));
+    }
+
+    #[test]
+    fn decodes_sixteen_bit_binary() {
+        let image = srgb(b"P5\n1 2\n65535\n\xff\xff\x00\x00").unwrap();
+
+        assert_eq!(image.pixel(0, 0).g(), 1.0);
+        assert_eq!(image.pixel(0, 1).g(), 0.0);
+    }
+
+    #[test]
+    fn linear_samples_are_read_as_stored() {
+        let image = decode(b"P2\n2 1\n255\n128 51\n", ColorSpace::Linear).unwrap();
+
+        assert_eq!(image.pixel(0, 0).g(), 128.0 / 255.0);
+        assert_eq!(image.pixel(1, 0).g(), 0.2);
+    }
+
+    #[test]
+    fn short_raster_is_truncated() {
+        assert!(matches!(
+            srgb(b"P6\n2 2\n255\nabc"),
+            Err(ImageError::Truncated)
+        ));
+        assert!(matches!(
+            srgb(b"P3\n1 1\n255\n1 2"),
+            Err(ImageError::Truncated)
+        ));
+        assert!(matches!(
+            srgb(b"P6\n65536 65536\n255\nabc"),
+            Err(ImageError::Truncated)
+        ));
+    }
+
+    #[test]
+    fn oversized_header_is_malformed() {
+        assert!(matches!(
+            srgb(b"P6\n4294967295 4294967295\n255\nabc"),
+            Err(ImageError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn truncated_files_are_errors() {
+        let file = b"P6\n1 1\n255\n\x01\x02\x03";
+        assert!(srgb(file).is_ok());
+        for length in 0..file.len() {
+            assert!(srgb(&file[..length]).is_err(), "length {}", length);
+        }
+    }
+}
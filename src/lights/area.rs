@@ -0,0 +1,213 @@
+use crate::lights::light::{Light, LightBounds, LightSample, Power};
+use crate::materials::material::DiffuseLight;
+use crate::math::color::Color;
+use crate::math::point3::Point3;
+use crate::math::ray::Ray;
+use crate::math::vector3::{dot, Vector3};
+use crate::objects::aabb::Aabb;
+use crate::objects::hittable::{HitRecord, Hittable, Shape};
+use crate::textures::texture::{SolidColor, Texture};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// Samples per side of the grid that the average emission of a textured
+/// light is estimated over, to convert a power into radiance.
+const POWER_ESTIMATE_GRID: usize = 16;
+
+/// A shape that glows, given off evenly in every direction from its front
+/// face, or from both faces if it is two-sided. It goes into the world like
+/// any object, where it replaces the shape's material wherever it emits;
+/// the back of a one-sided light keeps the shape's own material, as the
+/// housing of a panel would.
+pub struct AreaLight {
+    shape: Arc<dyn Shape>,
+    emission: Arc<dyn Texture>,
+    two_sided: bool,
+    power: Option<Power>,
+    emitter: Arc<DiffuseLight>,
+    /// Luminance of the emitter averaged over the surface.
+    average_luminance: f64,
+}
+
+impl AreaLight {
+    /// A light of the given `emission` radiance.
+    pub fn new(shape: Arc<dyn Shape>, emission: Color) -> Self {
+        Self::from_texture(shape, Arc::new(SolidColor::new(emission)))
+    }
+
+    pub fn from_texture(shape: Arc<dyn Shape>, emission: Arc<dyn Texture>) -> Self {
+        Self {
+            shape,
+            emitter: Arc::new(DiffuseLight::from_texture(emission.clone())),
+            emission,
+            two_sided: false,
+            power: None,
+            average_luminance: 0.0,
+        }
+        .rebuild()
+    }
+
+    pub fn two_sided(mut self, two_sided: bool) -> Self {
+        self.two_sided = two_sided;
+        self.rebuild()
+    }
+
+    /// Scales the emission so that the light gives off `power` in total,
+    /// keeping the emission's color and pattern.
+    pub fn power(mut self, power: Power) -> Self {
+        self.power = Some(power);
+        self.rebuild()
+    }
+
+    fn rebuild(mut self) -> Self {
+        let mut emission = self.emission.clone();
+        self.average_luminance = self.mean_luminance(emission.as_ref());
+        if let Some(power) = self.power {
+            let scale = if self.average_luminance > 0.0 {
+                power.watts() / (PI * self.shape.area() * self.sides() * self.average_luminance)
+            } else {
+                0.0
+            };
+            emission = Arc::new(ScaledTexture {
+                texture: emission,
+                scale,
+            });
+            self.average_luminance *= scale;
+        }
+
+        self.emitter = Arc::new(DiffuseLight::from_texture(emission).two_sided(self.two_sided));
+        self
+    }
+
+    fn sides(&self) -> f64 {
+        if self.two_sided {
+            2.0
+        } else {
+            1.0
+        }
+    }
+
+    /// Luminance of `emission` averaged over the surface.
+    fn mean_luminance(&self, emission: &dyn Texture) -> f64 {
+        let n = POWER_ESTIMATE_GRID;
+        let mut total = 0.0;
+        for i in 0..n {
+            for j in 0..n {
+                let u = (i as f64 + 0.5) / n as f64;
+                let v = (j as f64 + 0.5) / n as f64;
+                let surface = self.shape.sample_surface(u, v);
+                total += emission
+                    .value(surface.u, surface.v, &surface.point, &surface.normal)
+                    .luminance();
+            }
+        }
+
+        total / (n * n) as f64
+    }
+
+    /// Converts a density over the light's area, seen from `distance` at
+    /// `cos_light` to its normal, to one over solid angle.
+    fn solid_angle_pdf(&self, distance: f64, cos_light: f64) -> f64 {
+        distance * distance / (cos_light * self.shape.area())
+    }
+}
+
+impl Hittable for AreaLight {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, hit_record: &mut HitRecord) -> bool {
+        if !self.shape.hit(ray, t_min, t_max, hit_record) {
+            return false;
+        }
+
+        if hit_record.front_face || self.two_sided {
+            hit_record.material = Some(self.emitter.clone());
+        }
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.shape.bounding_box()
+    }
+}
+
+impl Light for AreaLight {
+    fn sample(&self, point: &Point3, u: f64, v: f64) -> Option<LightSample> {
+        let surface = self.shape.sample_surface(u, v);
+        let to_light = surface.point - *point;
+        let distance = to_light.length();
+        if distance == 0.0 {
+            return None;
+        }
+
+        let direction = to_light / distance;
+        let cos_light = dot(&surface.normal, &-direction);
+        let facing = cos_light > 0.0 || (self.two_sided && cos_light < 0.0);
+        if !facing {
+            return None;
+        }
+
+        let radiance =
+            self.emitter
+                .emit
+                .value(surface.u, surface.v, &surface.point, &surface.normal);
+        Some(LightSample {
+            direction,
+            distance,
+            radiance,
+            pdf: self.solid_angle_pdf(distance, cos_light.abs()),
+        })
+    }
+
+    fn pdf(&self, ray: &Ray, record: &HitRecord) -> f64 {
+        // Only if the shape is what the ray reached; the same ray meets it
+        // at the same distance.
+        let mut surface = HitRecord::new();
+        let slack = 1e-9 * record.t.max(1.0);
+        if !self
+            .shape
+            .hit(ray, record.t - slack, record.t + slack, &mut surface)
+        {
+            return 0.0;
+        }
+        if !surface.front_face && !self.two_sided {
+            return 0.0;
+        }
+
+        let length = ray.direction.length();
+        let cos_light = dot(&surface.geometric_normal, &ray.direction).abs() / length;
+        if cos_light == 0.0 {
+            return 0.0;
+        }
+
+        self.solid_angle_pdf(record.t * length, cos_light)
+    }
+
+    fn power(&self, _scene_radius: f64) -> f64 {
+        PI * self.sides() * self.shape.area() * self.average_luminance
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        let (axis, cos_theta_o) = self.shape.normal_bounds();
+
+        Some(LightBounds {
+            bounds: self.shape.bounding_box(),
+            power: self.power(0.0),
+            axis,
+            cos_theta_o,
+            // Emission spreads over the hemisphere about each normal.
+            cos_theta_e: 0.0,
+            two_sided: self.two_sided,
+        })
+    }
+}
+
+/// A texture multiplied by a constant.
+struct ScaledTexture {
+    texture: Arc<dyn Texture>,
+    scale: f64,
+}
+
+impl Texture for ScaledTexture {
+    fn value(&self, u: f64, v: f64, point: &Point3, normal: &Vector3) -> Color {
+        self.scale * self.texture.value(u, v, point, normal)
+    }
+}
@@ -0,0 +1,44 @@
+use crate::lights::environment::EnvironmentLight;
+use crate::lights::light::InfiniteLight;
+use crate::lights::sky::Sky;
+use crate::math::color::Color;
+use crate::math::vector3::{unit_vector, Vector3};
+use std::sync::Arc;
+
+/// What rays see when they leave the scene without hitting anything.
+#[derive(Clone)]
+pub enum Background {
+    /// White at the horizon blending to light blue overhead.
+    Gradient,
+    Solid(Color),
+    /// An image-based light, which is also importance sampled at every
+    /// diffuse bounce.
+    Environment(Arc<EnvironmentLight>),
+    /// Analytic daylight, sampled like an environment map.
+    Sky(Arc<Sky>),
+}
+
+impl Background {
+    /// Radiance seen looking along `direction`.
+    pub fn radiance(&self, direction: &Vector3) -> Color {
+        match self {
+            Background::Gradient => {
+                let unit_direction = unit_vector(direction);
+                let t = 0.5 * (unit_direction.y() + 1.0);
+                (1.0 - t) * Color::new(1.0, 1.0, 1.0) + t * Color::new(0.5, 0.7, 1.0)
+            }
+            Background::Solid(color) => *color,
+            Background::Environment(environment) => environment.radiance(direction),
+            Background::Sky(sky) => sky.radiance(direction),
+        }
+    }
+
+    /// The light to sample for direct lighting, if the background is one.
+    pub fn light(&self) -> Option<&dyn InfiniteLight> {
+        match self {
+            Background::Environment(environment) => Some(environment.as_ref()),
+            Background::Sky(sky) => Some(sky.as_ref()),
+            _ => None,
+        }
+    }
+}
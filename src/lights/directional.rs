@@ -0,0 +1,45 @@
+use crate::lights::light::{Light, LightSample};
+use crate::math::color::Color;
+use crate::math::point3::Point3;
+use crate::math::vector3::{unit_vector, Vector3};
+use std::f64::consts::PI;
+
+/// Parallel light from a source so far away that only its direction
+/// matters, like sunlight without the sky.
+pub struct DirectionalLight {
+    /// Unit vector pointing back towards the source.
+    to_light: Vector3,
+    /// Irradiance on a surface facing the light, in watts per square metre.
+    irradiance: Color,
+}
+
+impl DirectionalLight {
+    /// Light travelling along `direction`, giving `irradiance` in watts per
+    /// square metre on a surface facing it. For a value in lux, divide by
+    /// 683.
+    pub fn new(direction: Vector3, irradiance: Color) -> Self {
+        Self {
+            to_light: -unit_vector(&direction),
+            irradiance,
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _point: &Point3, _u: f64, _v: f64) -> Option<LightSample> {
+        Some(LightSample {
+            direction: self.to_light,
+            distance: f64::INFINITY,
+            radiance: self.irradiance,
+            pdf: 1.0,
+        })
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+
+    fn power(&self, scene_radius: f64) -> f64 {
+        PI * scene_radius * scene_radius * self.irradiance.luminance()
+    }
+}
@@ -0,0 +1,125 @@
+use crate::images::error::ImageError;
+use crate::images::image::{ColorSpace, Image};
+use crate::images::loader::load_image;
+use crate::lights::light::InfiniteLight;
+use crate::math::color::Color;
+use crate::math::distribution::Distribution2D;
+use crate::math::vector3::{unit_vector, Vector3};
+use std::f64::consts::PI;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Light arriving from infinitely far away in every direction, given by an
+/// equirectangular (latitude-longitude) image such as an HDR photograph of
+/// the sky. The top row of the image is straight up, and its center column
+/// faces +x, the same mapping `Sphere` uses for texture coordinates.
+///
+/// Directions are importance sampled in proportion to the brightness of
+/// the map, so a small, bright sun is found by light sampling rather than
+/// by luck.
+pub struct EnvironmentLight {
+    image: Arc<Image>,
+    /// Rotation of the map about the vertical axis, in radians.
+    rotation: f64,
+    intensity: f64,
+    distribution: Distribution2D,
+}
+
+impl EnvironmentLight {
+    pub fn new(image: Arc<Image>) -> Self {
+        let width = image.width() as usize;
+        let height = image.height() as usize;
+        assert!(width > 0 && height > 0, "environment map is empty");
+
+        // Rows near the poles cover less of the sphere than they do of the
+        // image, so each row is weighted by the sine of its polar angle.
+        let mut weights = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let sin_theta = (PI * (y as f64 + 0.5) / height as f64).sin();
+            for x in 0..width {
+                weights.push(image.pixel(x as u32, y as u32).luminance() * sin_theta);
+            }
+        }
+
+        Self {
+            distribution: Distribution2D::new(&weights, width, height),
+            image,
+            rotation: 0.0,
+            intensity: 1.0,
+        }
+    }
+
+    /// Loads any format `load_image` reads, though only .hdr and .pfm files
+    /// hold the dynamic range a sky needs.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ImageError> {
+        Ok(Self::new(Arc::new(load_image(
+            path.as_ref(),
+            ColorSpace::Srgb,
+        )?)))
+    }
+
+    /// Turns the map about the vertical axis by `degrees`, to bring the sun
+    /// round to where the scene needs it.
+    pub fn rotation(mut self, degrees: f64) -> Self {
+        self.rotation = degrees.to_radians();
+        self
+    }
+
+    /// Scales the radiance of the whole map.
+    pub fn intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Position in the image, as fractions of its width and height from the
+    /// top left corner, that `direction` looks at.
+    fn map_coordinates(&self, direction: &Vector3) -> (f64, f64) {
+        let d = unit_vector(direction);
+        let theta = (-d.y()).clamp(-1.0, 1.0).acos();
+        let phi = (-d.z()).atan2(d.x()) + PI + self.rotation;
+
+        ((phi / (2.0 * PI)).rem_euclid(1.0), 1.0 - theta / PI)
+    }
+}
+
+impl InfiniteLight for EnvironmentLight {
+    fn radiance(&self, direction: &Vector3) -> Color {
+        let (x, y) = self.map_coordinates(direction);
+        let column = ((x * self.image.width() as f64) as u32).min(self.image.width() - 1);
+        let row = ((y * self.image.height() as f64) as u32).min(self.image.height() - 1);
+
+        self.intensity * self.image.pixel(column, row)
+    }
+
+    fn sample(&self, u: f64, v: f64) -> (Vector3, Color, f64) {
+        let ((x, y), map_pdf) = self.distribution.sample(u, v);
+        let theta = PI * (1.0 - y);
+        let phi = 2.0 * PI * x - self.rotation;
+        let sin_theta = theta.sin();
+
+        let direction = Vector3::new(-sin_theta * phi.cos(), -theta.cos(), sin_theta * phi.sin());
+        if map_pdf == 0.0 || sin_theta == 0.0 {
+            return (direction, Color::black(), 0.0);
+        }
+
+        let pdf = map_pdf / (2.0 * PI * PI * sin_theta);
+        (direction, self.radiance(&direction), pdf)
+    }
+
+    fn pdf(&self, direction: &Vector3) -> f64 {
+        let (x, y) = self.map_coordinates(direction);
+        let sin_theta = (PI * y).sin();
+        if sin_theta == 0.0 {
+            return 0.0;
+        }
+
+        self.distribution.pdf(x, y) / (2.0 * PI * PI * sin_theta)
+    }
+
+    fn power(&self, scene_radius: f64) -> f64 {
+        // The distribution integrates luminance times the sine of the polar
+        // angle over the image, which covers 2π by π radians.
+        let integral = 2.0 * PI * PI * self.distribution.integral();
+        PI * scene_radius * scene_radius * self.intensity * integral
+    }
+}
@@ -0,0 +1,236 @@
+use crate::math::color::Color;
+use crate::math::point3::Point3;
+use crate::math::ray::Ray;
+use crate::math::vector3::{cross, dot, unit_vector, Vector3};
+use crate::objects::aabb::Aabb;
+use crate::objects::hittable::HitRecord;
+use std::f64::consts::PI;
+
+/// Lumens per watt of light at 555 nm, where the eye is most sensitive.
+const LUMINOUS_EFFICACY: f64 = 683.0;
+
+/// How much light a lamp gives off in total.
+#[derive(Copy, Clone, Debug)]
+pub enum Power {
+    Watts(f64),
+    /// Converted at the peak luminous efficacy of 683 lm/W.
+    Lumens(f64),
+}
+
+impl Power {
+    pub fn watts(&self) -> f64 {
+        match self {
+            Power::Watts(watts) => *watts,
+            Power::Lumens(lumens) => lumens / LUMINOUS_EFFICACY,
+        }
+    }
+}
+
+/// Light arriving at a point from one sample of a light.
+pub struct LightSample {
+    /// Unit vector from the lit point towards the light.
+    pub direction: Vector3,
+    /// Distance to the sampled point on the light, infinite for lights
+    /// with no position.
+    pub distance: f64,
+    pub radiance: Color,
+    /// Density over solid angle of picking `direction`, or one for lights
+    /// that only light from a single direction.
+    pub pdf: f64,
+}
+
+/// A light in the scene that direct lighting can sample.
+pub trait Light: Send + Sync {
+    /// Picks light arriving at `point`, from `u` and `v` in [0, 1), or none
+    /// if no light from this sample can reach it.
+    fn sample(&self, point: &Point3, u: f64, v: f64) -> Option<LightSample>;
+
+    /// Density over solid angle with which `sample` would have picked the
+    /// light that `ray` reached at `record`, zero if it's not this light.
+    fn pdf(&self, _ray: &Ray, _record: &HitRecord) -> f64 {
+        0.0
+    }
+
+    /// True for lights at a single point or from a single direction, which
+    /// rays can't hit and only sampling finds.
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    /// Total power given off, as luminance, for choosing brighter lights
+    /// more often. Lights with no position spread theirs over a disk the
+    /// size of the scene, of radius `scene_radius`.
+    fn power(&self, scene_radius: f64) -> f64;
+
+    /// Where the light is and which way it shines, or none for lights with
+    /// no position.
+    fn bounds(&self) -> Option<LightBounds> {
+        None
+    }
+}
+
+/// Light arriving from infinitely far away, which rays see when they leave
+/// the scene and which materials can also sample directly.
+pub trait InfiniteLight: Send + Sync {
+    /// Radiance seen looking along `direction`.
+    fn radiance(&self, direction: &Vector3) -> Color;
+
+    /// Picks a direction to look for light in, from `u` and `v` in [0, 1),
+    /// returning it with the radiance from there and its density over solid
+    /// angle. The density is zero for directions that bring no light.
+    fn sample(&self, u: f64, v: f64) -> (Vector3, Color, f64);
+
+    /// Density over solid angle with which `sample` picks `direction`.
+    fn pdf(&self, direction: &Vector3) -> f64;
+
+    /// Total power reaching a scene of radius `scene_radius`, as luminance.
+    fn power(&self, scene_radius: f64) -> f64;
+}
+
+/// Bounds on where some lights are, how much they give off and in which
+/// directions, after Conty Estevez and Kulla, "Importance Sampling of Many
+/// Lights with Adaptive Tree Splitting" (2018). Emission leaves within
+/// `cos_theta_e` of some direction within `cos_theta_o` of `axis`.
+#[derive(Copy, Clone)]
+pub struct LightBounds {
+    pub bounds: Aabb,
+    pub power: f64,
+    pub axis: Vector3,
+    pub cos_theta_o: f64,
+    pub cos_theta_e: f64,
+    pub two_sided: bool,
+}
+
+impl LightBounds {
+    /// Bounds on lights shining every way from within `bounds`.
+    pub fn omnidirectional(bounds: Aabb, power: f64) -> Self {
+        Self {
+            bounds,
+            power,
+            axis: Vector3::new(0.0, 0.0, 1.0),
+            cos_theta_o: -1.0,
+            cos_theta_e: 0.0,
+            two_sided: false,
+        }
+    }
+
+    pub fn union(&self, other: &LightBounds) -> Self {
+        if self.power == 0.0 {
+            return *other;
+        }
+        if other.power == 0.0 {
+            return *self;
+        }
+
+        let (axis, cos_theta_o) = cone_union(
+            (self.axis, self.cos_theta_o),
+            (other.axis, other.cos_theta_o),
+        );
+        Self {
+            bounds: Aabb::surrounding(&self.bounds, &other.bounds),
+            power: self.power + other.power,
+            axis,
+            cos_theta_o,
+            cos_theta_e: self.cos_theta_e.min(other.cos_theta_e),
+            two_sided: self.two_sided || other.two_sided,
+        }
+    }
+
+    /// An estimate of how much these lights contribute at `point`: their
+    /// power over the squared distance, reduced by how far `point` lies
+    /// outside the directions they can shine in.
+    pub fn importance(&self, point: &Point3) -> f64 {
+        if self.power == 0.0 {
+            return 0.0;
+        }
+
+        // Keep the distance at least half the box's diagonal, so points
+        // inside or close to it don't blow up.
+        let center = self.bounds.centroid();
+        let offset = *point - center;
+        let half_diagonal = 0.5 * self.bounds.extent().length();
+        let distance_squared = offset.length_squared().max(half_diagonal * half_diagonal);
+
+        let mut cos_theta_w = if offset.length_squared() > 0.0 {
+            dot(&self.axis, &unit_vector(&offset))
+        } else {
+            1.0
+        };
+        if self.two_sided {
+            cos_theta_w = cos_theta_w.abs();
+        }
+        let sin_theta_w = (1.0 - cos_theta_w * cos_theta_w).max(0.0).sqrt();
+
+        // The angle the box subtends from the point.
+        let cos_theta_b = if offset.length_squared() <= half_diagonal * half_diagonal {
+            -1.0
+        } else {
+            (1.0 - half_diagonal * half_diagonal / offset.length_squared())
+                .max(0.0)
+                .sqrt()
+        };
+        let sin_theta_b = (1.0 - cos_theta_b * cos_theta_b).max(0.0).sqrt();
+
+        // cos(max(0, θw - θo - θb)).
+        let sin_theta_o = (1.0 - self.cos_theta_o * self.cos_theta_o).max(0.0).sqrt();
+        let (sin_theta_x, cos_theta_x) =
+            angle_difference((sin_theta_w, cos_theta_w), (sin_theta_o, self.cos_theta_o));
+        let (_, cos_theta_p) =
+            angle_difference((sin_theta_x, cos_theta_x), (sin_theta_b, cos_theta_b));
+        if cos_theta_p <= self.cos_theta_e {
+            return 0.0;
+        }
+
+        self.power * cos_theta_p / distance_squared
+    }
+}
+
+/// Sine and cosine of the angle a - b, clamped at zero, from those of a
+/// and b.
+fn angle_difference((sin_a, cos_a): (f64, f64), (sin_b, cos_b): (f64, f64)) -> (f64, f64) {
+    if cos_a > cos_b {
+        return (0.0, 1.0);
+    }
+
+    (sin_a * cos_b - cos_a * sin_b, cos_a * cos_b + sin_a * sin_b)
+}
+
+/// Smallest cone, as an axis and the cosine of its half-angle, holding two
+/// others.
+fn cone_union(a: (Vector3, f64), b: (Vector3, f64)) -> (Vector3, f64) {
+    let theta_a = a.1.clamp(-1.0, 1.0).acos();
+    let theta_b = b.1.clamp(-1.0, 1.0).acos();
+    let theta_d = dot(&a.0, &b.0).clamp(-1.0, 1.0).acos();
+    if (theta_d + theta_b).min(PI) <= theta_a {
+        return a;
+    }
+    if (theta_d + theta_a).min(PI) <= theta_b {
+        return b;
+    }
+
+    let theta_o = 0.5 * (theta_a + theta_d + theta_b);
+    let normal = cross(&a.0, &b.0);
+    if theta_o >= PI || normal.length_squared() == 0.0 {
+        return (a.0, -1.0);
+    }
+
+    // Turn a's axis towards b's by the angle between a's edge and the new
+    // one's, about the axis perpendicular to both.
+    let k = unit_vector(&normal);
+    let angle = theta_o - theta_a;
+    let axis =
+        angle.cos() * a.0 + angle.sin() * cross(&k, &a.0) + (1.0 - angle.cos()) * dot(&k, &a.0) * k;
+
+    (unit_vector(&axis), theta_o.cos())
+}
+
+/// Scales `color` to a luminance of one, so that it only sets the tint of a
+/// light whose strength is given separately.
+pub(crate) fn normalized_tint(color: &Color) -> Color {
+    let luminance = color.luminance();
+    if luminance > 0.0 {
+        *color / luminance
+    } else {
+        Color::black()
+    }
+}
@@ -0,0 +1,334 @@
+use crate::lights::background::Background;
+use crate::lights::light::{InfiniteLight, Light, LightBounds};
+use crate::math::distribution::Distribution1D;
+use crate::math::point3::Point3;
+use crate::objects::aabb::Aabb;
+use std::sync::Arc;
+
+/// How direct lighting chooses which light to sample at each bounce.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum LightSampling {
+    /// Every light equally often.
+    Uniform,
+    /// In proportion to each light's total power.
+    Power,
+    /// Down a tree bounding the lights' positions, power and the directions
+    /// they shine in, which favours lights that are near, bright and facing
+    /// the point being lit.
+    #[default]
+    Bvh,
+}
+
+/// Everything direct lighting needs: the background, the scene's lights
+/// and how to choose among them.
+pub struct Lighting {
+    pub background: Background,
+    pub lights: Vec<Arc<dyn Light>>,
+    pub sampler: LightSampler,
+}
+
+impl Lighting {
+    /// `scene_bounds` sets how much power lights with no position, such as
+    /// the sky, deliver, which weighs them against the rest.
+    pub fn new(
+        background: Background,
+        lights: Vec<Arc<dyn Light>>,
+        sampling: LightSampling,
+        scene_bounds: &Aabb,
+    ) -> Self {
+        let sampler = LightSampler::new(sampling, &lights, background.light(), scene_bounds);
+
+        Self {
+            background,
+            lights,
+            sampler,
+        }
+    }
+
+    /// Index the background's light has in the sampler, after the scene's
+    /// lights.
+    pub fn environment_index(&self) -> usize {
+        self.lights.len()
+    }
+}
+
+/// Chooses a light to sample. Lights are numbered in the order they were
+/// given, with the background's light, if any, last.
+pub struct LightSampler {
+    count: usize,
+    strategy: Strategy,
+}
+
+enum Strategy {
+    Uniform,
+    Power(Distribution1D),
+    Bvh(LightBvh),
+}
+
+impl LightSampler {
+    pub fn new(
+        sampling: LightSampling,
+        lights: &[Arc<dyn Light>],
+        environment: Option<&dyn InfiniteLight>,
+        scene_bounds: &Aabb,
+    ) -> Self {
+        let count = lights.len() + environment.is_some() as usize;
+        let scene_radius = if scene_bounds.is_empty() {
+            0.0
+        } else {
+            0.5 * scene_bounds.extent().length()
+        };
+
+        let strategy = match sampling {
+            _ if count == 0 => Strategy::Uniform,
+            LightSampling::Uniform => Strategy::Uniform,
+            LightSampling::Power => {
+                let mut powers: Vec<f64> = lights
+                    .iter()
+                    .map(|light| light.power(scene_radius))
+                    .collect();
+                powers.extend(environment.map(|light| light.power(scene_radius)));
+                Strategy::Power(Distribution1D::new(powers))
+            }
+            LightSampling::Bvh => Strategy::Bvh(LightBvh::new(lights, environment.is_some())),
+        };
+
+        Self { count, strategy }
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Picks a light to sample for `point` from `u` in [0, 1), returning
+    /// its index and the probability of having picked it.
+    pub fn sample(&self, point: &Point3, u: f64) -> Option<(usize, f64)> {
+        if self.count == 0 {
+            return None;
+        }
+
+        match &self.strategy {
+            Strategy::Uniform => {
+                let index = ((u * self.count as f64) as usize).min(self.count - 1);
+                Some((index, 1.0 / self.count as f64))
+            }
+            Strategy::Power(distribution) => {
+                let (_, _, index) = distribution.sample(u);
+                Some((index, self.pmf(point, index)))
+            }
+            Strategy::Bvh(bvh) => bvh.sample(point, u),
+        }
+    }
+
+    /// Probability that `sample` picks light `index` for `point`.
+    pub fn pmf(&self, point: &Point3, index: usize) -> f64 {
+        if index >= self.count {
+            return 0.0;
+        }
+
+        match &self.strategy {
+            Strategy::Uniform => 1.0 / self.count as f64,
+            Strategy::Power(distribution) => distribution.pdf_at(index) / self.count as f64,
+            Strategy::Bvh(bvh) => bvh.pmf(point, index),
+        }
+    }
+}
+
+/// A binary tree over the lights that have bounds, split at the median
+/// along the longest axis like `BvhNode`. Each node bounds the lights below
+/// it, and sampling walks down choosing between children by their
+/// importance to the point being lit. Lights without bounds are picked
+/// apart from the tree, as one more choice alongside it.
+struct LightBvh {
+    /// Depth first, each interior node followed by its first child.
+    nodes: Vec<LightBvhNode>,
+    /// Lights without bounds.
+    infinite: Vec<usize>,
+    /// For each light in the tree, the way down to its leaf: one bit per
+    /// level, lowest first, set where the path takes the second child.
+    trails: Vec<Option<u64>>,
+}
+
+struct LightBvhNode {
+    bounds: LightBounds,
+    /// The light at a leaf, or none for an interior node.
+    light: Option<usize>,
+    /// Index of an interior node's second child.
+    second_child: usize,
+}
+
+impl LightBvh {
+    fn new(lights: &[Arc<dyn Light>], has_environment: bool) -> Self {
+        let mut bounded = vec![];
+        let mut infinite = vec![];
+        for (index, light) in lights.iter().enumerate() {
+            match light.bounds() {
+                Some(bounds) if bounds.power > 0.0 => bounded.push((index, bounds)),
+                Some(_) => {}
+                None => infinite.push(index),
+            }
+        }
+        if has_environment {
+            infinite.push(lights.len());
+        }
+
+        let mut bvh = Self {
+            nodes: vec![],
+            infinite,
+            trails: vec![None; lights.len() + has_environment as usize],
+        };
+        if !bounded.is_empty() {
+            bvh.build(&mut bounded, 0, 0);
+        }
+
+        bvh
+    }
+
+    /// Adds the subtree over `lights`, reached by `trail` at `depth`, and
+    /// returns its bounds.
+    fn build(
+        &mut self,
+        lights: &mut [(usize, LightBounds)],
+        trail: u64,
+        depth: u32,
+    ) -> LightBounds {
+        if let [(index, bounds)] = lights {
+            self.trails[*index] = Some(trail);
+            self.nodes.push(LightBvhNode {
+                bounds: *bounds,
+                light: Some(*index),
+                second_child: 0,
+            });
+            return *bounds;
+        }
+
+        let centroid_bounds = lights.iter().fold(Aabb::empty(), |bbox, (_, bounds)| {
+            let centroid = bounds.bounds.centroid();
+            Aabb::surrounding(&bbox, &Aabb::new(centroid, centroid))
+        });
+        let axis = centroid_bounds.longest_axis();
+        lights.sort_by(|(_, a), (_, b)| {
+            a.bounds.centroid()[axis].total_cmp(&b.bounds.centroid()[axis])
+        });
+
+        let node = self.nodes.len();
+        self.nodes.push(LightBvhNode {
+            bounds: lights[0].1,
+            light: None,
+            second_child: 0,
+        });
+        let (first, second) = lights.split_at_mut(lights.len() / 2);
+        let first_bounds = self.build(first, trail, depth + 1);
+        self.nodes[node].second_child = self.nodes.len();
+        let second_bounds = self.build(second, trail | (1 << depth), depth + 1);
+
+        let bounds = first_bounds.union(&second_bounds);
+        self.nodes[node].bounds = bounds;
+        bounds
+    }
+
+    /// Chance of choosing among the lights without bounds rather than going
+    /// down the tree.
+    fn infinite_probability(&self) -> f64 {
+        let choices = self.infinite.len() + !self.nodes.is_empty() as usize;
+        if choices == 0 {
+            return 0.0;
+        }
+
+        self.infinite.len() as f64 / choices as f64
+    }
+
+    fn sample(&self, point: &Point3, u: f64) -> Option<(usize, f64)> {
+        let infinite_probability = self.infinite_probability();
+        if u < infinite_probability {
+            let u = u / infinite_probability;
+            let count = self.infinite.len();
+            let choice = ((u * count as f64) as usize).min(count - 1);
+            return Some((self.infinite[choice], infinite_probability / count as f64));
+        }
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let mut u = (u - infinite_probability) / (1.0 - infinite_probability);
+        u = u.min(ONE_MINUS_EPSILON);
+        let mut pmf = 1.0 - infinite_probability;
+        let mut node = 0;
+        loop {
+            let current = &self.nodes[node];
+            if let Some(light) = current.light {
+                // A lone root still has to be able to light the point.
+                if node > 0 || current.bounds.importance(point) > 0.0 {
+                    return Some((light, pmf));
+                }
+                return None;
+            }
+
+            let (first, second) = self.child_probabilities(node, point)?;
+            if u < first {
+                u = (u / first).min(ONE_MINUS_EPSILON);
+                pmf *= first;
+                node += 1;
+            } else {
+                u = ((u - first) / second).min(ONE_MINUS_EPSILON);
+                pmf *= second;
+                node = current.second_child;
+            }
+        }
+    }
+
+    fn pmf(&self, point: &Point3, index: usize) -> f64 {
+        let infinite_probability = self.infinite_probability();
+        if self.infinite.contains(&index) {
+            return infinite_probability / self.infinite.len() as f64;
+        }
+        let Some(Some(mut trail)) = self.trails.get(index).copied() else {
+            return 0.0;
+        };
+
+        let mut pmf = 1.0 - infinite_probability;
+        let mut node = 0;
+        loop {
+            let current = &self.nodes[node];
+            if current.light.is_some() {
+                if node > 0 || current.bounds.importance(point) > 0.0 {
+                    return pmf;
+                }
+                return 0.0;
+            }
+
+            let Some((first, second)) = self.child_probabilities(node, point) else {
+                return 0.0;
+            };
+            if trail & 1 == 0 {
+                pmf *= first;
+                node += 1;
+            } else {
+                pmf *= second;
+                node = current.second_child;
+            }
+            trail >>= 1;
+        }
+    }
+
+    /// Probabilities of going down to each child of interior `node`, or none
+    /// if neither can light `point`.
+    fn child_probabilities(&self, node: usize, point: &Point3) -> Option<(f64, f64)> {
+        let first = self.nodes[node + 1].bounds.importance(point);
+        let second = self.nodes[self.nodes[node].second_child]
+            .bounds
+            .importance(point);
+        if first + second == 0.0 {
+            return None;
+        }
+
+        Some((first / (first + second), second / (first + second)))
+    }
+}
+
+/// The largest f64 below one, to keep remapped random numbers in [0, 1).
+const ONE_MINUS_EPSILON: f64 = 1.0 - f64::EPSILON / 2.0;
@@ -0,0 +1,64 @@
+use crate::lights::light::{normalized_tint, Light, LightBounds, LightSample, Power};
+use crate::math::color::Color;
+use crate::math::point3::Point3;
+use crate::objects::aabb::Aabb;
+use std::f64::consts::PI;
+
+/// Light given off evenly in every direction from a single point. It casts
+/// perfectly sharp shadows and can't be seen directly.
+pub struct PointLight {
+    position: Point3,
+    /// Radiant intensity, in watts per steradian.
+    intensity: Color,
+}
+
+impl PointLight {
+    /// A light of radiant `intensity`, in watts per steradian.
+    pub fn new(position: Point3, intensity: Color) -> Self {
+        Self {
+            position,
+            intensity,
+        }
+    }
+
+    /// A light giving off `power` in total, tinted by `color`.
+    pub fn from_power(position: Point3, color: Color, power: Power) -> Self {
+        Self::new(
+            position,
+            power.watts() / (4.0 * PI) * normalized_tint(&color),
+        )
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, point: &Point3, _u: f64, _v: f64) -> Option<LightSample> {
+        let to_light = self.position - *point;
+        let distance_squared = to_light.length_squared();
+        if distance_squared == 0.0 {
+            return None;
+        }
+
+        let distance = distance_squared.sqrt();
+        Some(LightSample {
+            direction: to_light / distance,
+            distance,
+            radiance: self.intensity / distance_squared,
+            pdf: 1.0,
+        })
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+
+    fn power(&self, _scene_radius: f64) -> f64 {
+        4.0 * PI * self.intensity.luminance()
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        Some(LightBounds::omnidirectional(
+            Aabb::new(self.position, self.position),
+            self.power(0.0),
+        ))
+    }
+}
@@ -0,0 +1,292 @@
+//! Analytic daylight after Preetham, Shirley and Smits, "A Practical
+//! Analytic Model for Daylight" (1999): a clear sky whose brightness and
+//! color follow the sun's position and the haziness of the air, with the
+//! sun's disk on top.
+
+use crate::lights::light::InfiniteLight;
+use crate::math::color::Color;
+use crate::math::distribution::Distribution2D;
+use crate::math::onb::Onb;
+use crate::math::vector3::{dot, unit_vector, Vector3};
+use std::f64::consts::{FRAC_PI_2, PI};
+
+/// Luminance of the sun's disk above the atmosphere, in thousands of
+/// candela per square metre, the unit the model gives the sky in.
+const SUN_LUMINANCE: f64 = 2.0e6;
+
+/// Scale from the model's units to the renderer's, so that a white surface
+/// under a high sun comes out near one.
+const INTENSITY: f64 = 0.03;
+
+/// Resolution of the grid the dome is importance sampled from, in azimuth
+/// and zenith angle.
+const GRID_WIDTH: usize = 64;
+const GRID_HEIGHT: usize = 32;
+
+/// CIE XYZ to linear sRGB, for the D65 white point.
+const XYZ_TO_SRGB: [[f64; 3]; 3] = [
+    [3.240_454_2, -1.537_138_5, -0.498_531_4],
+    [-0.969_266_0, 1.876_010_8, 0.041_556_0],
+    [0.055_643_4, -0.204_025_9, 1.057_225_2],
+];
+
+/// A clear daytime sky with the sun. Up is +y; below the horizon is black,
+/// so outdoor scenes want a ground.
+pub struct Sky {
+    sun_direction: Vector3,
+    /// Sun zenith angle, held at the horizon once the sun has set.
+    sun_zenith: f64,
+    /// Cosine of the angular radius of the sun's disk.
+    sun_cos_max: f64,
+    sun_radiance: Color,
+    /// Perez distribution coefficients A to E for luminance Y and the
+    /// chromaticities x and y.
+    perez: [[f64; 5]; 3],
+    /// Y, x and y at the zenith.
+    zenith: [f64; 3],
+    distribution: Distribution2D,
+}
+
+impl Sky {
+    /// The sun at `elevation` degrees above the horizon and `azimuth`
+    /// degrees clockwise, seen from above, from -z towards +x. `turbidity`
+    /// runs from about 2 for a very clear sky to 10 for haze.
+    pub fn new(elevation: f64, azimuth: f64, turbidity: f64) -> Self {
+        let t = turbidity.clamp(1.7, 10.0);
+        let (elevation, azimuth) = (elevation.to_radians(), azimuth.to_radians());
+        let sun_direction = Vector3::new(
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+            -elevation.cos() * azimuth.cos(),
+        );
+        let sun_zenith = (FRAC_PI_2 - elevation).clamp(0.0, FRAC_PI_2);
+
+        let perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * sun_zenith);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let chromaticity = |coefficients: [[f64; 4]; 3]| {
+            let s = sun_zenith;
+            let angles = [s * s * s, s * s, s, 1.0];
+            let row = |r: [f64; 4]| (0..4).map(|i| r[i] * angles[i]).sum::<f64>();
+            t * t * row(coefficients[0]) + t * row(coefficients[1]) + row(coefficients[2])
+        };
+        let zenith_x = chromaticity([
+            [0.00166, -0.00375, 0.00209, 0.0],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let zenith_y = chromaticity([
+            [0.00275, -0.00610, 0.00317, 0.0],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+
+        let sun_radius = (0.53f64 / 2.0).to_radians();
+        let mut sky = Self {
+            sun_direction,
+            sun_zenith,
+            sun_cos_max: sun_radius.cos(),
+            sun_radiance: SUN_LUMINANCE * sun_transmittance(sun_zenith, t),
+            perez,
+            zenith: [zenith_luminance.max(0.0), zenith_x, zenith_y],
+            distribution: Distribution2D::new(&[1.0], 1, 1),
+        };
+
+        // Weighted by the sine of the zenith angle, as rows near the zenith
+        // cover less of the dome than they do of the grid.
+        let mut weights = Vec::with_capacity(GRID_WIDTH * GRID_HEIGHT);
+        for y in 0..GRID_HEIGHT {
+            for x in 0..GRID_WIDTH {
+                let (direction, sin_theta) = dome_direction(
+                    (x as f64 + 0.5) / GRID_WIDTH as f64,
+                    (y as f64 + 0.5) / GRID_HEIGHT as f64,
+                );
+                weights.push(sky.sky_radiance(&direction).luminance() * sin_theta);
+            }
+        }
+        sky.distribution = Distribution2D::new(&weights, GRID_WIDTH, GRID_HEIGHT);
+
+        sky
+    }
+
+    /// Sets the angular diameter of the sun's disk, 0.53 degrees by default.
+    /// A bigger sun gives softer shadows and more light.
+    pub fn sun_size(mut self, degrees: f64) -> Self {
+        self.sun_cos_max = (degrees / 2.0).to_radians().cos();
+        self
+    }
+
+    /// Radiance of the sky alone along the unit vector `direction`, before
+    /// `INTENSITY` is applied.
+    fn sky_radiance(&self, direction: &Vector3) -> Color {
+        let cos_theta = direction.y();
+        if cos_theta <= 0.0 {
+            return Color::black();
+        }
+
+        let theta = cos_theta.acos();
+        let gamma = dot(direction, &self.sun_direction).clamp(-1.0, 1.0).acos();
+        let [luminance, x, y] = [0, 1, 2].map(|i| {
+            let [a, b, c, d, e] = self.perez[i];
+            let perez = |theta: f64, gamma: f64| {
+                (1.0 + a * (b / theta.cos().max(1e-3)).exp())
+                    * (1.0 + c * (d * gamma).exp() + e * gamma.cos() * gamma.cos())
+            };
+            self.zenith[i] * perez(theta, gamma) / perez(0.0, self.sun_zenith)
+        });
+        if y <= 0.0 {
+            return Color::black();
+        }
+
+        let xyz = [x * luminance / y, luminance, (1.0 - x - y) * luminance / y];
+        let [r, g, b] =
+            XYZ_TO_SRGB.map(|row| (row[0] * xyz[0] + row[1] * xyz[1] + row[2] * xyz[2]).max(0.0));
+        Color::new(r, g, b)
+    }
+
+    fn sun_visible(&self) -> bool {
+        self.sun_direction.y() > -(1.0 - self.sun_cos_max * self.sun_cos_max).sqrt()
+    }
+
+    /// Chance that `sample` aims for the sun rather than the dome.
+    fn sun_probability(&self) -> f64 {
+        if self.sun_visible() {
+            0.5
+        } else {
+            0.0
+        }
+    }
+
+    fn sun_pdf(&self) -> f64 {
+        1.0 / (2.0 * PI * (1.0 - self.sun_cos_max))
+    }
+}
+
+impl InfiniteLight for Sky {
+    fn radiance(&self, direction: &Vector3) -> Color {
+        let direction = unit_vector(direction);
+        let mut radiance = self.sky_radiance(&direction);
+        if direction.y() > 0.0
+            && self.sun_visible()
+            && dot(&direction, &self.sun_direction) >= self.sun_cos_max
+        {
+            radiance += self.sun_radiance;
+        }
+
+        INTENSITY * radiance
+    }
+
+    fn sample(&self, u: f64, v: f64) -> (Vector3, Color, f64) {
+        let sun_probability = self.sun_probability();
+        let direction = if u < sun_probability {
+            // Uniformly within the cone the disk subtends.
+            let u = u / sun_probability;
+            let cos_theta = 1.0 - u * (1.0 - self.sun_cos_max);
+            let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+            let phi = 2.0 * PI * v;
+            Onb::new(&self.sun_direction).local(
+                sin_theta * phi.cos(),
+                sin_theta * phi.sin(),
+                cos_theta,
+            )
+        } else {
+            let u = (u - sun_probability) / (1.0 - sun_probability);
+            let ((x, y), _) = self.distribution.sample(u, v);
+            dome_direction(x, y).0
+        };
+
+        let pdf = self.pdf(&direction);
+        if pdf == 0.0 {
+            return (direction, Color::black(), 0.0);
+        }
+
+        (direction, self.radiance(&direction), pdf)
+    }
+
+    fn pdf(&self, direction: &Vector3) -> f64 {
+        let direction = unit_vector(direction);
+        if direction.y() <= 0.0 {
+            return 0.0;
+        }
+
+        let sun_probability = self.sun_probability();
+        let mut pdf = 0.0;
+        if dot(&direction, &self.sun_direction) >= self.sun_cos_max {
+            pdf += sun_probability * self.sun_pdf();
+        }
+
+        let theta = direction.y().clamp(-1.0, 1.0).acos();
+        let sin_theta = theta.sin();
+        if sin_theta > 0.0 {
+            let x = (direction.z().atan2(direction.x()) / (2.0 * PI)).rem_euclid(1.0);
+            let y = theta / FRAC_PI_2;
+            let dome = self.distribution.pdf(x, y) / (PI * PI * sin_theta);
+            pdf += (1.0 - sun_probability) * dome;
+        }
+
+        pdf
+    }
+
+    fn power(&self, scene_radius: f64) -> f64 {
+        // The grid covers 2π radians of azimuth by π/2 of zenith angle.
+        let mut integral = PI * PI * self.distribution.integral();
+        if self.sun_visible() {
+            integral += self.sun_radiance.luminance() / self.sun_pdf();
+        }
+
+        PI * scene_radius * scene_radius * INTENSITY * integral
+    }
+}
+
+/// Direction above the horizon at fractions `x` of the way round in azimuth
+/// and `y` of the way down from the zenith, with the sine of its zenith
+/// angle.
+fn dome_direction(x: f64, y: f64) -> (Vector3, f64) {
+    let theta = FRAC_PI_2 * y;
+    let phi = 2.0 * PI * x;
+    let sin_theta = theta.sin();
+
+    (
+        Vector3::new(sin_theta * phi.cos(), theta.cos(), sin_theta * phi.sin()),
+        sin_theta,
+    )
+}
+
+/// Fraction of sunlight at 650, 550 and 450 nm that gets through the
+/// atmosphere with the sun at `sun_zenith`: Rayleigh scattering by air and
+/// Ångström's fit for aerosols, as in Preetham et al.'s appendix.
+fn sun_transmittance(sun_zenith: f64, turbidity: f64) -> Color {
+    let degrees = sun_zenith.to_degrees();
+    let air_mass = 1.0 / (sun_zenith.cos() + 0.15 * (93.885 - degrees).powf(-1.253));
+    let beta = 0.04608 * turbidity - 0.04586;
+
+    let [r, g, b] = [0.65f64, 0.55, 0.45].map(|micrometres| {
+        let rayleigh = 0.008735 * micrometres.powf(-4.08);
+        let aerosol = beta * micrometres.powf(-1.3);
+        (-air_mass * (rayleigh + aerosol)).exp()
+    });
+    Color::new(r, g, b)
+}
@@ -0,0 +1,121 @@
+use crate::lights::light::{normalized_tint, Light, LightBounds, LightSample, Power};
+use crate::math::color::Color;
+use crate::math::point3::Point3;
+use crate::math::vector3::{dot, unit_vector, Vector3};
+use crate::objects::aabb::Aabb;
+use std::f64::consts::PI;
+
+/// A point light that only shines within a cone. Its intensity is full out
+/// to `falloff_start` degrees from the axis and fades smoothly to nothing
+/// at `cone_angle` degrees.
+pub struct SpotLight {
+    position: Point3,
+    /// Unit vector along the axis of the cone.
+    axis: Vector3,
+    /// Radiant intensity along the axis, in watts per steradian.
+    intensity: Color,
+    cos_cone: f64,
+    cos_falloff_start: f64,
+}
+
+impl SpotLight {
+    /// A spotlight at `position` aimed at `target`, with radiant
+    /// `intensity` along its axis in watts per steradian.
+    pub fn new(
+        position: Point3,
+        target: Point3,
+        intensity: Color,
+        cone_angle: f64,
+        falloff_start: f64,
+    ) -> Self {
+        let cos_cone = cone_angle.to_radians().cos();
+
+        Self {
+            position,
+            axis: unit_vector(&(target - position)),
+            intensity,
+            cos_cone,
+            cos_falloff_start: falloff_start.to_radians().cos().max(cos_cone),
+        }
+    }
+
+    /// A spotlight giving off `power` in total, tinted by `color`.
+    pub fn from_power(
+        position: Point3,
+        target: Point3,
+        color: Color,
+        power: Power,
+        cone_angle: f64,
+        falloff_start: f64,
+    ) -> Self {
+        let light = Self::new(position, target, Color::black(), cone_angle, falloff_start);
+
+        Self {
+            intensity: power.watts() / light.solid_angle() * normalized_tint(&color),
+            ..light
+        }
+    }
+
+    /// The solid angle of a cone halfway between the full and fading parts,
+    /// which the smooth falloff integrates to.
+    fn solid_angle(&self) -> f64 {
+        2.0 * PI * (1.0 - 0.5 * (self.cos_cone + self.cos_falloff_start))
+    }
+
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if cos_theta >= self.cos_falloff_start {
+            return 1.0;
+        }
+        if cos_theta <= self.cos_cone {
+            return 0.0;
+        }
+
+        let t = (cos_theta - self.cos_cone) / (self.cos_falloff_start - self.cos_cone);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, point: &Point3, _u: f64, _v: f64) -> Option<LightSample> {
+        let to_light = self.position - *point;
+        let distance_squared = to_light.length_squared();
+        if distance_squared == 0.0 {
+            return None;
+        }
+
+        let distance = distance_squared.sqrt();
+        let direction = to_light / distance;
+        let falloff = self.falloff(dot(&-direction, &self.axis));
+        if falloff == 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: falloff / distance_squared * self.intensity,
+            pdf: 1.0,
+        })
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+
+    fn power(&self, _scene_radius: f64) -> f64 {
+        self.intensity.luminance() * self.solid_angle()
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        let spread = self.cos_cone.acos() - self.cos_falloff_start.acos();
+
+        Some(LightBounds {
+            bounds: Aabb::new(self.position, self.position),
+            power: self.power(0.0),
+            axis: self.axis,
+            cos_theta_o: self.cos_falloff_start,
+            cos_theta_e: spread.cos(),
+            two_sided: false,
+        })
+    }
+}
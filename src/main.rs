@@ -1,34 +1,203 @@
 mod math {
     pub(crate) mod color;
+    pub(crate) mod distribution;
+    pub(crate) mod noise;
+    pub(crate) mod onb;
     pub(crate) mod point3;
+    pub(crate) mod quaternion;
+    pub(crate) mod random;
     pub(crate) mod ray;
+    pub(crate) mod spectrum;
     pub(crate) mod vector3;
 }
 
 mod objects {
+    pub mod aabb;
+    pub mod alpha_mask;
+    pub mod bvh;
+    pub mod constant_medium;
+    pub mod density_field;
+    pub mod heterogeneous_medium;
     pub mod hittable;
     pub mod hittable_list;
+    pub mod quad;
     pub mod sphere;
+    pub mod subsurface;
+    pub mod transform;
+    pub mod triangle;
 }
 
 mod camera {
+    pub mod animation;
+    pub mod aperture;
+    #[allow(clippy::module_inception)]
     pub mod camera;
+    pub mod crop;
+    pub mod lens;
+    pub mod projection;
+    pub mod sequence;
+    pub mod stereo;
+}
+
+mod lights {
+    pub mod area;
+    pub mod background;
+    pub mod directional;
+    pub mod environment;
+    pub mod light;
+    pub mod light_sampler;
+    pub mod point;
+    pub mod sky;
+    pub mod spot;
 }
 
 mod materials {
+    pub mod layered;
     pub mod material;
+    pub mod microfacet;
+    pub mod mtl;
+    pub mod normal_map;
+    pub mod phase_function;
+    pub mod principled;
+    pub mod thin_film;
+}
+
+mod textures {
+    pub mod image_texture;
+    pub mod noise_texture;
+    pub mod texture;
 }
 
+mod images {
+    pub mod error;
+    pub mod hdr;
+    pub mod image;
+    pub mod inflate;
+    pub mod loader;
+    pub mod pfm;
+    pub mod png;
+    pub mod pnm;
+}
+
+use crate::camera::animation::{CameraAnimation, CameraKeyframe, Interpolation};
+use crate::camera::aperture::{Aperture, ApertureMask};
 use crate::camera::camera::{Camera, CameraBuilder};
-use crate::materials::material::{Dielectric, Lambertian, Metal};
+use crate::camera::crop::{Crop, CropWindow};
+use crate::camera::lens::Lens;
+use crate::camera::projection::Projection;
+use crate::camera::sequence::ImageSequence;
+use crate::camera::stereo::{Stereo, StereoLayout};
+use crate::images::image::ColorSpace;
+use crate::images::loader::load_image;
+use crate::lights::area::AreaLight;
+use crate::lights::background::Background;
+use crate::lights::directional::DirectionalLight;
+use crate::lights::environment::EnvironmentLight;
+use crate::lights::light::{Light, Power};
+use crate::lights::light_sampler::LightSampling;
+use crate::lights::point::PointLight;
+use crate::lights::sky::Sky;
+use crate::lights::spot::SpotLight;
+use crate::materials::layered::{Coated, MixMaterial, Sheen};
+use crate::materials::material::{
+    directional_albedo, Dielectric, DiffuseLight, Dispersion, Lambertian, Material, Metal,
+};
+use crate::materials::microfacet::{Conductor, RoughDielectric};
+use crate::materials::mtl::load_mtl;
+use crate::materials::normal_map::{BumpMap, NormalMap};
+use crate::materials::phase_function::HenyeyGreenstein;
+use crate::materials::principled::Principled;
+use crate::materials::thin_film::ThinFilm;
 use crate::math::color::Color;
+use crate::math::noise::Simplex;
 use crate::math::point3::Point3;
+use crate::math::quaternion::Quaternion;
+use crate::math::random;
+use crate::math::vector3::Vector3;
+use crate::objects::aabb::Aabb;
+use crate::objects::alpha_mask::{AlphaMask, AlphaTest};
+use crate::objects::bvh::BvhNode;
+use crate::objects::constant_medium::ConstantMedium;
+use crate::objects::density_field::{NoiseDensity, VoxelGrid};
+use crate::objects::heterogeneous_medium::HeterogeneousMedium;
 use crate::objects::hittable_list::HittableList;
+use crate::objects::quad::Quad;
 use crate::objects::sphere::Sphere;
+use crate::objects::subsurface::Subsurface;
+use crate::objects::transform::{Keyframe, KeyframedTransform};
+use crate::objects::triangle::Triangle;
+use crate::textures::image_texture::{Filter, ImageTexture, TextureCache, WrapMode};
+use crate::textures::noise_texture::{GraniteTexture, MarbleTexture, NoiseTexture, WoodTexture};
+use crate::textures::texture::{
+    CheckerTexture, GradientAxis, GradientTexture, SolidColor, Texture, UvCheckerTexture,
+};
 use rand::Rng;
+use std::env;
+use std::f64::consts::PI;
+use std::path::Path;
+use std::process;
 use std::sync::Arc;
 
 fn main() {
+    match env::args().nth(1).as_deref() {
+        Some("bouncing_spheres") => bouncing_spheres(),
+        Some("spinning_spheres") => spinning_spheres(),
+        Some("checkered_spheres") => checkered_spheres(),
+        Some("noise_spheres") => noise_spheres(),
+        Some("foggy_spheres") => foggy_spheres(),
+        Some("volumes") => volumes(env::args().nth(2).as_deref()),
+        Some("microfacet_spheres") => microfacet_spheres(),
+        Some("glass_spheres") => glass_spheres(),
+        Some("dispersion") => dispersion(),
+        Some("layered_spheres") => layered_spheres(),
+        Some("iridescence") => iridescence(),
+        Some("subsurface_spheres") => subsurface_spheres(),
+        Some("cutouts") => cutouts(env::args().nth(2).as_deref()),
+        Some("bump_mapping") => {
+            bump_mapping(env::args().nth(2).as_deref(), env::args().nth(3).as_deref())
+        }
+        Some("principled_spheres") => principled_spheres(env::args().nth(2).as_deref()),
+        Some("white_furnace") => white_furnace(),
+        Some("textured_sphere") => {
+            textured_sphere(env::args().nth(2).as_deref(), env::args().nth(3).as_deref())
+        }
+        Some("lights") => lights(),
+        Some("bokeh") => bokeh(env::args().nth(2).as_deref(), env::args().nth(3).as_deref()),
+        Some("tilt_shift") => tilt_shift(
+            env::args().nth(2).as_deref(),
+            env::args().nth(3).as_deref(),
+            env::args().nth(4).as_deref(),
+        ),
+        Some("lens") => lens(
+            env::args().nth(2).as_deref(),
+            env::args().nth(3).as_deref(),
+            env::args().nth(4).as_deref(),
+        ),
+        Some("crop") => crop(&env::args().skip(2).collect::<Vec<_>>()),
+        Some("turntable") => turntable(
+            env::args().nth(2).as_deref(),
+            env::args().nth(3).as_deref(),
+            env::args().nth(4).as_deref(),
+        ),
+        Some("stereo") => stereo(env::args().nth(2).as_deref()),
+        Some("projections") => projections(env::args().nth(2).as_deref()),
+        Some("many_lights") => many_lights(env::args().nth(2).as_deref()),
+        Some("sky") => sky(
+            env::args().nth(2).as_deref(),
+            env::args().nth(3).as_deref(),
+            env::args().nth(4).as_deref(),
+            env::args().nth(5).as_deref(),
+        ),
+        Some("environment_light") => environment_light(
+            env::args().nth(2).as_deref(),
+            env::args().nth(3).as_deref(),
+            env::args().nth(4).as_deref(),
+        ),
+        _ => random_spheres(),
+    }
+}
+
+fn random_spheres() {
     const ASPECT_RATIO: f64 = 16.0 / 9.0;
     const WIDTH: u32 = 1200;
 
@@ -46,7 +215,7 @@ fn main() {
 
     for a in -11..11 {
         for b in -11..11 {
-            let mut rng = rand::rng();
+            let mut rng = random::rng();
             let choose_material = rng.random_range(0.0..1.0);
             let center = Point3::new(
                 a as f64 + 0.9 * rng.random_range(0.0..1.0),
@@ -59,7 +228,7 @@ fn main() {
                     let albedo = Color::random() * Color::random();
                     let material = Lambertian::new(albedo);
                     world.add(Box::new(Sphere::new(center, 0.2, Arc::new(material))));
-                } else if (choose_material < 0.95) {
+                } else if choose_material < 0.95 {
                     let albedo = Color::random_range(0.5..1.0);
                     let fuzz = rng.random_range(0.0..0.5);
                     let material = Metal::new(albedo, fuzz);
@@ -101,9 +270,1967 @@ fn main() {
         .fov(20.0)
         .look_from(Point3::new(13.0, 2.0, 3.0))
         .look_at(Point3::zero())
+        .up(Vector3::new(0.0, 1.0, 0.0))
         .defocus_angle(0.6)
         .focus_dist(10.0);
 
     let camera = Camera::new_from_builder(&builder);
-    camera.render(&world);
+    camera.render(&BvhNode::new(world));
+}
+
+fn bouncing_spheres() {
+    let mut world = HittableList::new();
+
+    let ground_material = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(ground_material),
+    )));
+
+    let mut rng = random::rng();
+    for a in -11..11 {
+        for b in -11..11 {
+            let center = Point3::new(
+                a as f64 + 0.9 * rng.random_range(0.0..1.0),
+                0.2,
+                b as f64 + 0.9 * rng.random_range(0.0..1.0),
+            );
+            if (center - Point3::new(4.0, 0.2, 0.0)).length() <= 0.9 {
+                continue;
+            }
+
+            let albedo = Color::random() * Color::random();
+            let bounce = Vector3::new(0.0, rng.random_range(0.0..0.5), 0.0);
+            world.add(Box::new(Sphere::new_moving(
+                center,
+                center + bounce,
+                0.2,
+                Arc::new(Lambertian::new(albedo)),
+            )));
+        }
+    }
+
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        Arc::new(Dielectric::new(1.5)),
+    )));
+    world.add(Box::new(Sphere::new(
+        Point3::new(4.0, 1.0, 0.0),
+        1.0,
+        Arc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0)),
+    )));
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(400)
+        .samples_per_pixel(100)
+        .max_depth(50)
+        .fov(20.0)
+        .look_from(Point3::new(13.0, 2.0, 3.0))
+        .look_at(Point3::zero())
+        .defocus_angle(0.6)
+        .focus_dist(10.0)
+        .shutter_open(0.0)
+        .shutter_close(1.0);
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+fn spinning_spheres() {
+    let mut world = HittableList::new();
+
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+
+    // Two spheres on an arm, swung a quarter turn around the vertical axis
+    // while the whole group drifts sideways during the exposure.
+    let mut arm = HittableList::new();
+    arm.add(Box::new(Sphere::new(
+        Point3::new(-1.2, 0.0, 0.0),
+        0.5,
+        Arc::new(Lambertian::new(Color::new(0.8, 0.3, 0.1))),
+    )));
+    arm.add(Box::new(Sphere::new(
+        Point3::new(1.2, 0.0, 0.0),
+        0.5,
+        Arc::new(Metal::new(Color::new(0.8, 0.8, 0.9), 0.05)),
+    )));
+
+    let up = Vector3::new(0.0, 1.0, 0.0);
+    let keyframes = vec![
+        Keyframe::new(
+            0.0,
+            Point3::new(-0.3, 1.0, 0.0),
+            Quaternion::identity(),
+            1.0,
+        ),
+        Keyframe::new(
+            0.5,
+            Point3::new(0.0, 1.0, 0.0),
+            Quaternion::from_axis_angle(&up, 45.0),
+            1.0,
+        ),
+        Keyframe::new(
+            1.0,
+            Point3::new(0.3, 1.0, 0.0),
+            Quaternion::from_axis_angle(&up, 90.0),
+            1.0,
+        ),
+    ];
+    world.add(Box::new(KeyframedTransform::new(Box::new(arm), keyframes)));
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(400)
+        .samples_per_pixel(100)
+        .max_depth(50)
+        .fov(30.0)
+        .look_from(Point3::new(0.0, 3.0, 8.0))
+        .look_at(Point3::new(0.0, 0.8, 0.0))
+        .shutter_open(0.0)
+        .shutter_close(1.0);
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+fn checkered_spheres() {
+    let mut world = HittableList::new();
+
+    let checker = Arc::new(CheckerTexture::from_colors(
+        0.32,
+        Color::new(0.2, 0.3, 0.1),
+        Color::new(0.9, 0.9, 0.9),
+    ));
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, -10.0, 0.0),
+        10.0,
+        Arc::new(Lambertian::from_texture(checker)),
+    )));
+
+    let uv_checker = Arc::new(UvCheckerTexture::from_colors(
+        16,
+        8,
+        Color::new(0.8, 0.1, 0.1),
+        Color::new(0.9, 0.9, 0.9),
+    ));
+    world.add(Box::new(Sphere::new(
+        Point3::new(-1.1, 1.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::from_texture(uv_checker)),
+    )));
+
+    let gradient = Arc::new(GradientTexture::new(
+        Color::new(0.1, 0.2, 0.5),
+        Color::new(0.9, 0.8, 0.6),
+        GradientAxis::V,
+    ));
+    world.add(Box::new(Sphere::new(
+        Point3::new(1.1, 1.0, 0.0),
+        1.0,
+        Arc::new(Metal::from_texture(gradient, 0.2)),
+    )));
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(400)
+        .samples_per_pixel(100)
+        .max_depth(50)
+        .fov(30.0)
+        .look_from(Point3::new(0.0, 2.0, 8.0))
+        .look_at(Point3::new(0.0, 0.8, 0.0));
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+fn noise_spheres() {
+    let mut rng = random::rng();
+    let mut world = HittableList::new();
+
+    let perlin = Arc::new(NoiseTexture::perlin(4.0, &mut rng));
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::from_texture(perlin)),
+    )));
+
+    let marble = Arc::new(MarbleTexture::new(
+        10.0,
+        Color::new(0.9, 0.9, 0.88),
+        Color::new(0.2, 0.2, 0.25),
+        &mut rng,
+    ));
+    world.add(Box::new(Sphere::new(
+        Point3::new(-2.2, 1.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::from_texture(marble)),
+    )));
+
+    let wood = Arc::new(WoodTexture::new(
+        6.0,
+        Color::new(0.75, 0.55, 0.3),
+        Color::new(0.4, 0.22, 0.1),
+        &mut rng,
+    ));
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::from_texture(wood)),
+    )));
+
+    let granite = Arc::new(GraniteTexture::new(
+        8.0,
+        Color::new(0.7, 0.65, 0.62),
+        Color::new(0.1, 0.1, 0.1),
+        &mut rng,
+    ));
+    world.add(Box::new(Sphere::new(
+        Point3::new(2.2, 1.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::from_texture(granite)),
+    )));
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(400)
+        .samples_per_pixel(100)
+        .max_depth(50)
+        .fov(30.0)
+        .look_from(Point3::new(0.0, 2.0, 9.0))
+        .look_at(Point3::new(0.0, 1.0, 0.0));
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+fn textured_sphere(path: Option<&str>, filter: Option<&str>) {
+    let Some(path) = path else {
+        eprintln!("usage: rtiow textured_sphere <image.png|.ppm|.pgm|.hdr|.pfm> [nearest]");
+        process::exit(2);
+    };
+    let filter = match filter {
+        None | Some("bilinear") => Filter::Bilinear,
+        Some("nearest") => Filter::Nearest,
+        Some(other) => {
+            eprintln!("{}: expected bilinear or nearest", other);
+            process::exit(2);
+        }
+    };
+
+    let texture = match load_image(Path::new(path), ColorSpace::Srgb) {
+        Ok(image) => Arc::new(ImageTexture::new(Arc::new(image)).filter(filter)),
+        Err(error) => {
+            eprintln!("{}: {}", path, error);
+            process::exit(1);
+        }
+    };
+
+    let mut world = HittableList::new();
+    world.add(Box::new(Sphere::new(
+        Point3::zero(),
+        2.0,
+        Arc::new(Lambertian::from_texture(texture)),
+    )));
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(400)
+        .samples_per_pixel(100)
+        .max_depth(50)
+        .fov(20.0)
+        .look_from(Point3::new(0.0, 0.0, 12.0))
+        .look_at(Point3::zero());
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+/// Spheres in the dark, lit by one of each kind of light: a warm point
+/// light, a spotlight, faint moonlight, a two-sided checkered panel and a
+/// glowing ball.
+fn lights() {
+    let mut world = HittableList::new();
+    let mut lights: Vec<Arc<dyn Light>> = vec![];
+
+    let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    world.add(Box::new(Quad::new(
+        Point3::new(-20.0, 0.0, 20.0),
+        Vector3::new(40.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, -40.0),
+        white.clone(),
+    )));
+    world.add(Box::new(Sphere::new(
+        Point3::new(-2.2, 1.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::new(Color::new(0.7, 0.3, 0.2))),
+    )));
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        Arc::new(Conductor::gold(0.3)),
+    )));
+    world.add(Box::new(Sphere::new(
+        Point3::new(2.2, 1.0, 0.0),
+        1.0,
+        white.clone(),
+    )));
+
+    lights.push(Arc::new(PointLight::from_power(
+        Point3::new(-3.5, 3.0, 2.5),
+        Color::new(1.0, 0.8, 0.6),
+        Power::Watts(400.0),
+    )));
+    lights.push(Arc::new(SpotLight::from_power(
+        Point3::new(2.2, 6.0, 1.0),
+        Point3::new(2.2, 0.0, 0.0),
+        Color::new(0.6, 0.8, 1.0),
+        Power::Lumens(8000.0),
+        25.0,
+        18.0,
+    )));
+    lights.push(Arc::new(DirectionalLight::new(
+        Vector3::new(1.0, -1.0, -0.5),
+        Color::new(0.05, 0.06, 0.1),
+    )));
+
+    let checker = Arc::new(UvCheckerTexture::from_colors(
+        4,
+        2,
+        Color::new(1.0, 0.3, 0.1),
+        Color::new(1.0, 1.0, 1.0),
+    ));
+    let panel = Arc::new(
+        AreaLight::from_texture(
+            Arc::new(Quad::new(
+                Point3::new(-2.0, 0.5, -3.0),
+                Vector3::new(4.0, 0.0, 0.0),
+                Vector3::new(0.0, 2.0, 0.0),
+                white.clone(),
+            )),
+            checker,
+        )
+        .two_sided(true)
+        .power(Power::Watts(30.0)),
+    );
+    world.add(Box::new(panel.clone()));
+    lights.push(panel);
+
+    let ball = Arc::new(AreaLight::new(
+        Arc::new(Sphere::new(Point3::new(1.1, 0.3, 1.6), 0.3, white)),
+        Color::new(0.5, 3.0, 0.8),
+    ));
+    world.add(Box::new(ball.clone()));
+    lights.push(ball);
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(400)
+        .samples_per_pixel(100)
+        .max_depth(50)
+        .fov(35.0)
+        .look_from(Point3::new(0.0, 3.0, 10.0))
+        .look_at(Point3::new(0.0, 1.0, 0.0))
+        .background(Background::Solid(Color::black()))
+        .lights(lights);
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+/// Spheres in focus against a field of small, far lamps blurred through an
+/// aperture of `shape`: a number of blades, six by default, turned by
+/// `rotation` degrees, or an image of the opening.
+fn bokeh(shape: Option<&str>, rotation: Option<&str>) {
+    let aperture = match shape.map(|shape| (shape, shape.parse::<u32>())) {
+        None => Aperture::Polygon {
+            blades: 6,
+            rotation: parse_number(rotation, 0.0),
+        },
+        Some((_, Ok(blades))) => Aperture::Polygon {
+            blades,
+            rotation: parse_number(rotation, 0.0),
+        },
+        Some((path, Err(_))) => match ApertureMask::load(path) {
+            Ok(mask) => Aperture::Image(Arc::new(mask)),
+            Err(error) => {
+                eprintln!("{}: {}", path, error);
+                process::exit(1);
+            }
+        },
+    };
+
+    let mut world = HittableList::new();
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::new(Color::new(0.3, 0.3, 0.3))),
+    )));
+    world.add(Box::new(Sphere::new(
+        Point3::new(-1.1, 1.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::new(Color::new(0.8, 0.2, 0.1))),
+    )));
+    world.add(Box::new(Sphere::new(
+        Point3::new(1.1, 1.0, 0.0),
+        1.0,
+        Arc::new(Metal::new(Color::new(0.8, 0.8, 0.9), 0.0)),
+    )));
+
+    let mut rng = random::rng();
+    for _ in 0..120 {
+        let center = Point3::new(
+            rng.random_range(-30.0..30.0),
+            rng.random_range(1.0..20.0),
+            rng.random_range(-60.0..-40.0),
+        );
+        let emit = 10.0 * Color::random_range(0.4..1.0);
+        world.add(Box::new(Sphere::new(
+            center,
+            0.15,
+            Arc::new(DiffuseLight::new(emit)),
+        )));
+    }
+
+    let lights: Vec<Arc<dyn Light>> = vec![Arc::new(PointLight::from_power(
+        Point3::new(3.0, 5.0, 4.0),
+        Color::new(1.0, 0.9, 0.8),
+        Power::Watts(2000.0),
+    ))];
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(600)
+        .samples_per_pixel(200)
+        .max_depth(20)
+        .fov(40.0)
+        .look_from(Point3::new(0.0, 1.5, 7.0))
+        .look_at(Point3::new(0.0, 2.5, 0.0))
+        .focus_dist(7.0)
+        .defocus_angle(4.0)
+        .aperture(aperture)
+        .background(Background::Solid(Color::new(0.01, 0.01, 0.02)))
+        .lights(lights);
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+/// A town of little colored blocks seen from above, with the plane of focus
+/// tilted by `tilt` degrees, -30 by default for a toy-like band of focus,
+/// and the sensor shifted up by `shift` of the frame. `swing` turns the
+/// plane of focus sideways, so the band runs across the town diagonally.
+fn tilt_shift(tilt: Option<&str>, shift: Option<&str>, swing: Option<&str>) {
+    let mut world = HittableList::new();
+    world.add(Box::new(Quad::new(
+        Point3::new(-30.0, 0.0, 30.0),
+        Vector3::new(60.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, -60.0),
+        Arc::new(Lambertian::new(Color::new(0.35, 0.45, 0.3))),
+    )));
+
+    let mut rng = random::rng();
+    for i in -8..=8 {
+        for j in -12..=4 {
+            if rng.random_range(0.0..1.0) < 0.35 {
+                continue;
+            }
+            let height = rng.random_range(0.3..1.5);
+            let corner = Point3::new(1.2 * i as f64 - 0.4, 0.0, 1.2 * j as f64 + 0.4);
+            let material: Arc<dyn Material> =
+                Arc::new(Lambertian::new(Color::random_range(0.3..0.9)));
+            add_block(&mut world, corner, Vector3::new(0.8, height, 0.8), material);
+        }
+    }
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(600)
+        .samples_per_pixel(100)
+        .max_depth(20)
+        .fov(35.0)
+        .look_from(Point3::new(0.0, 9.0, 14.0))
+        .look_at(Point3::new(0.0, 0.0, 0.0))
+        .focus_dist(16.6)
+        .defocus_angle(1.5)
+        .tilt(parse_number(tilt, -30.0))
+        .swing(parse_number(swing, 0.0))
+        .shift(0.0, parse_number(shift, 0.0));
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+/// Adds an axis-aligned box with its lower front left corner at `corner`,
+/// open at the bottom.
+fn add_block(world: &mut HittableList, corner: Point3, size: Vector3, material: Arc<dyn Material>) {
+    let (dx, dy, dz) = (
+        Vector3::new(size.x(), 0.0, 0.0),
+        Vector3::new(0.0, size.y(), 0.0),
+        Vector3::new(0.0, 0.0, -size.z()),
+    );
+    let back = corner + dz;
+    for (origin, u, v) in [
+        (corner, dx, dy),
+        (back + dx, -dx, dy),
+        (back, -dz, dy),
+        (corner + dx, dz, dy),
+        (corner + dy, dx, dz),
+    ] {
+        world.add(Box::new(Quad::new(origin, u, v, material.clone())));
+    }
+}
+
+/// A line of spheres receding from a real lens, in metres, with a field of
+/// small lamps far behind to show its bokeh. The lens focuses at `focus`
+/// metres, 1.5 by default, with its aperture stopped down to `aperture`
+/// millimetres if given, and is a 50 mm double Gauss unless a pbrt lens
+/// table is named.
+fn lens(focus: Option<&str>, aperture: Option<&str>, path: Option<&str>) {
+    let mut lens = match path {
+        None => Lens::double_gauss(),
+        Some(path) => match Lens::load(path) {
+            Ok(lens) => lens,
+            Err(error) => {
+                eprintln!("{}: {}", path, error);
+                process::exit(1);
+            }
+        },
+    };
+    if aperture.is_some() {
+        lens = lens.aperture(parse_number(aperture, 0.0));
+    }
+
+    let mut world = HittableList::new();
+    let checker = Arc::new(CheckerTexture::from_colors(
+        0.1,
+        Color::new(0.2, 0.3, 0.1),
+        Color::new(0.9, 0.9, 0.9),
+    ));
+    world.add(Box::new(Quad::new(
+        Point3::new(-20.0, 0.0, 20.0),
+        Vector3::new(40.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, -40.0),
+        Arc::new(Lambertian::from_texture(checker)),
+    )));
+
+    for i in 0..8 {
+        let z = -0.8 - 0.5 * i as f64;
+        let material: Arc<dyn Material> = if i % 2 == 0 {
+            Arc::new(Lambertian::new(Color::new(0.8, 0.2, 0.1)))
+        } else {
+            Arc::new(Metal::new(Color::new(0.8, 0.8, 0.9), 0.0))
+        };
+        world.add(Box::new(Sphere::new(
+            Point3::new(0.15 * i as f64 - 0.4, 0.1, z),
+            0.1,
+            material,
+        )));
+    }
+
+    let mut rng = random::rng();
+    for _ in 0..150 {
+        let center = Point3::new(
+            rng.random_range(-6.0..6.0),
+            rng.random_range(0.3..4.0),
+            rng.random_range(-12.0..-9.0),
+        );
+        let emit = 8.0 * Color::random_range(0.4..1.0);
+        world.add(Box::new(Sphere::new(
+            center,
+            0.02,
+            Arc::new(DiffuseLight::new(emit)),
+        )));
+    }
+
+    let lights: Vec<Arc<dyn Light>> = vec![Arc::new(PointLight::from_power(
+        Point3::new(1.0, 2.0, 0.0),
+        Color::new(1.0, 0.9, 0.8),
+        Power::Watts(200.0),
+    ))];
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(3.0 / 2.0)
+        .width(600)
+        .samples_per_pixel(200)
+        .max_depth(20)
+        .projection(Projection::Realistic(Arc::new(lens)))
+        .focus_dist(parse_number(focus, 1.5))
+        .look_from(Point3::new(0.0, 0.2, 0.0))
+        .look_at(Point3::new(0.0, 0.3, -2.0))
+        .background(Background::Solid(Color::new(0.01, 0.01, 0.02)))
+        .lights(lights);
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+/// A ring of spheres around the camera on a checkered floor, seen through
+/// the `perspective` (the default), `orthographic`, `equidistant` or
+/// `equisolid` fisheye, `equirectangular` or `cubemap` projection.
+fn projections(name: Option<&str>) {
+    let (projection, aspect_ratio) = match name {
+        None | Some("perspective") => (Projection::Perspective, 16.0 / 9.0),
+        Some("orthographic") => (Projection::Orthographic { size: 2.5 }, 16.0 / 9.0),
+        Some("equidistant") => (Projection::Equidistant { fov: 180.0 }, 1.0),
+        Some("equisolid") => (Projection::Equisolid { fov: 180.0 }, 1.0),
+        Some("equirectangular") => (Projection::Equirectangular, 2.0),
+        Some("cubemap") => (Projection::CubeMap, 3.0 / 2.0),
+        Some(other) => {
+            eprintln!("{}: unknown projection", other);
+            process::exit(2);
+        }
+    };
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(aspect_ratio)
+        .width(600)
+        .samples_per_pixel(50)
+        .max_depth(20)
+        .fov(60.0)
+        .projection(projection)
+        .look_from(Point3::new(0.0, 1.5, 0.0))
+        .look_at(Point3::new(0.0, 1.0, -5.0))
+        .focus_dist(5.0);
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(ring_of_spheres()));
+}
+
+/// The ring of spheres from `projections` in stereo, `side_by_side` (the
+/// default) or `top_bottom` through a perspective camera, or as an `ods`
+/// panorama with the left eye on top.
+fn stereo(layout: Option<&str>) {
+    let (projection, stereo, aspect_ratio, width) = match layout {
+        None | Some("side_by_side") => (
+            Projection::Perspective,
+            Stereo::new(StereoLayout::SideBySide).convergence(5.0),
+            4.0 / 3.0,
+            400,
+        ),
+        Some("top_bottom") => (
+            Projection::Perspective,
+            Stereo::new(StereoLayout::TopBottom).convergence(5.0),
+            16.0 / 9.0,
+            600,
+        ),
+        Some("ods") => (
+            Projection::Equirectangular,
+            Stereo::new(StereoLayout::TopBottom),
+            2.0,
+            600,
+        ),
+        Some(other) => {
+            eprintln!("{}: expected side_by_side, top_bottom or ods", other);
+            process::exit(2);
+        }
+    };
+
+    // The ring is five metres across, so the eyes stand farther apart
+    // than a person's to make the depth easy to see.
+    let builder = CameraBuilder::new()
+        .aspect_ratio(aspect_ratio)
+        .width(width)
+        .samples_per_pixel(50)
+        .max_depth(20)
+        .fov(60.0)
+        .projection(projection)
+        .stereo(stereo.interocular(0.3))
+        .look_from(Point3::new(0.0, 1.5, 0.0))
+        .look_at(Point3::new(0.0, 1.0, -5.0))
+        .focus_dist(5.0);
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(ring_of_spheres()));
+}
+
+/// The perspective view of the ring of spheres cropped to the window from
+/// (`x0`, `y0`) to (`x1`, `y1`), in fractions of the frame from the top
+/// left or, after `pixels`, in pixels, written alone or, given `full`, in
+/// place in a black frame.
+fn crop(arguments: &[String]) {
+    let pixels = arguments.first().map(String::as_str) == Some("pixels");
+    let arguments = if pixels { &arguments[1..] } else { arguments };
+    let number = |index: usize| parse_number(arguments.get(index).map(String::as_str), f64::NAN);
+    let [x0, y0, x1, y1] = [0, 1, 2, 3].map(number);
+    if [x0, y0, x1, y1].iter().any(|value| value.is_nan()) {
+        eprintln!("usage: rtiow crop [pixels] <x0> <y0> <x1> <y1> [full]");
+        process::exit(2);
+    }
+    let full_frame = arguments.get(4).map(String::as_str) == Some("full");
+    let window = if pixels {
+        CropWindow::Pixels {
+            x: x0 as u32,
+            y: y0 as u32,
+            width: (x1 - x0) as u32,
+            height: (y1 - y0) as u32,
+        }
+    } else {
+        CropWindow::Normalized { x0, y0, x1, y1 }
+    };
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(600)
+        .samples_per_pixel(50)
+        .max_depth(20)
+        .fov(60.0)
+        .look_from(Point3::new(0.0, 1.5, 0.0))
+        .look_at(Point3::new(0.0, 1.0, -5.0))
+        .focus_dist(5.0)
+        .crop(Crop::new(window).full_frame(full_frame));
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(ring_of_spheres()));
+}
+
+/// A four second orbit of the ring of spheres at twelve frames a second
+/// with a 180° shutter, zooming in as it passes each side, written to
+/// `path` (by default
+/// `turntable_##.ppm`) from frame `first` up to, but not including, `end`.
+/// Frames already written are skipped.
+fn turntable(path: Option<&str>, first: Option<&str>, end: Option<&str>) {
+    const SECONDS: f64 = 4.0;
+    const FRAME_RATE: f64 = 12.0;
+
+    // Keyframes every 30 degrees, and one past either end so the spline
+    // comes round smoothly where the orbit closes.
+    let look_at = Point3::new(0.0, 1.0, 0.0);
+    let keyframes = (-1..=13)
+        .map(|i| {
+            let angle = (30.0 * i as f64).to_radians();
+            let look_from = Point3::new(11.0 * angle.sin(), 4.0, 11.0 * angle.cos());
+            let fov = 40.0 - 8.0 * angle.sin().powi(2);
+            let time = SECONDS * i as f64 / 12.0;
+            CameraKeyframe::new(
+                time,
+                look_from,
+                look_at,
+                fov,
+                (look_from - look_at).length(),
+                0.6,
+            )
+        })
+        .collect();
+    let animation = CameraAnimation::new(keyframes, Interpolation::CatmullRom);
+
+    let first = parse_number(first, 0.0) as u32;
+    let end = parse_number(end, SECONDS * FRAME_RATE) as u32;
+    let sequence = ImageSequence::new(path.unwrap_or("turntable_##.ppm"), first..end)
+        .frame_rate(FRAME_RATE)
+        .shutter(0.5);
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(400)
+        .samples_per_pixel(30)
+        .max_depth(20);
+
+    let world = BvhNode::new(ring_of_spheres());
+    if let Err(error) = sequence.render(&builder, &animation, &world) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+/// Twelve spheres in a ring of radius five around the origin on a checkered
+/// floor, every third one metal.
+fn ring_of_spheres() -> HittableList {
+    let mut world = HittableList::new();
+    let checker = Arc::new(CheckerTexture::from_colors(
+        0.5,
+        Color::new(0.2, 0.3, 0.1),
+        Color::new(0.9, 0.9, 0.9),
+    ));
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::from_texture(checker)),
+    )));
+
+    for i in 0..12 {
+        let angle = (30.0 * i as f64).to_radians();
+        let center = Point3::new(5.0 * angle.sin(), 1.0, -5.0 * angle.cos());
+        let hue = i as f64 / 12.0;
+        let albedo = Color::new(
+            0.5 + 0.4 * (2.0 * PI * hue).cos(),
+            0.5 + 0.4 * (2.0 * PI * (hue - 1.0 / 3.0)).cos(),
+            0.5 + 0.4 * (2.0 * PI * (hue - 2.0 / 3.0)).cos(),
+        );
+        let material: Arc<dyn Material> = if i % 3 == 0 {
+            Arc::new(Metal::new(albedo, 0.05))
+        } else {
+            Arc::new(Lambertian::new(albedo))
+        };
+        world.add(Box::new(Sphere::new(center, 1.0, material)));
+    }
+
+    world
+}
+
+/// Spheres under a ceiling of several hundred small lamps of very different
+/// power, lit with the `uniform`, `power` or `bvh` light sampling, the last
+/// by default.
+fn many_lights(sampling: Option<&str>) {
+    let sampling = match sampling {
+        None | Some("bvh") => LightSampling::Bvh,
+        Some("power") => LightSampling::Power,
+        Some("uniform") => LightSampling::Uniform,
+        Some(other) => {
+            eprintln!("{}: expected uniform, power or bvh", other);
+            process::exit(2);
+        }
+    };
+
+    let mut world = HittableList::new();
+    let mut lights: Vec<Arc<dyn Light>> = vec![];
+    let mut rng = random::rng();
+
+    let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    world.add(Box::new(Quad::new(
+        Point3::new(-20.0, 0.0, 20.0),
+        Vector3::new(40.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, -40.0),
+        white.clone(),
+    )));
+    for i in -3..=3 {
+        let x = 2.2 * i as f64;
+        let albedo = Color::random_range(0.2..0.9);
+        world.add(Box::new(Sphere::new(
+            Point3::new(x, 0.8, 0.0),
+            0.8,
+            Arc::new(Lambertian::new(albedo)),
+        )));
+    }
+
+    // Mostly dim lamps, a few of them a hundred times brighter.
+    for i in 0..24 {
+        for j in 0..16 {
+            let corner = Point3::new(-12.0 + i as f64, 4.0, 6.0 - j as f64);
+            let watts = if rng.random_range(0.0..1.0) < 0.05 {
+                40.0
+            } else {
+                rng.random_range(0.1..0.6)
+            };
+            let lamp = Arc::new(
+                AreaLight::new(
+                    Arc::new(Quad::new(
+                        corner,
+                        Vector3::new(0.2, 0.0, 0.0),
+                        Vector3::new(0.0, 0.0, 0.2),
+                        white.clone(),
+                    )),
+                    Color::random_range(0.5..1.0),
+                )
+                .power(Power::Watts(watts)),
+            );
+            world.add(Box::new(lamp.clone()));
+            lights.push(lamp);
+        }
+    }
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(400)
+        .samples_per_pixel(64)
+        .max_depth(50)
+        .fov(40.0)
+        .look_from(Point3::new(0.0, 2.5, 12.0))
+        .look_at(Point3::new(0.0, 1.0, 0.0))
+        .background(Background::Solid(Color::black()))
+        .lights(lights)
+        .light_sampling(sampling);
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+/// Diffuse, rough metal and glass spheres lit only by an HDR sky, optionally
+/// turned by `rotation` degrees and scaled by `intensity`.
+fn environment_light(path: Option<&str>, rotation: Option<&str>, intensity: Option<&str>) {
+    let Some(path) = path else {
+        eprintln!("usage: rtiow environment_light <sky.hdr|.pfm> [rotation] [intensity]");
+        process::exit(2);
+    };
+
+    let environment = match EnvironmentLight::load(path) {
+        Ok(environment) => environment
+            .rotation(parse_number(rotation, 0.0))
+            .intensity(parse_number(intensity, 1.0)),
+        Err(error) => {
+            eprintln!("{}: {}", path, error);
+            process::exit(1);
+        }
+    };
+
+    render_outdoors(Background::Environment(Arc::new(environment)));
+}
+
+/// The spheres of `environment_light` under a daylight sky, with the sun at
+/// `elevation` and `azimuth` degrees and `sun_size` degrees across.
+/// Rendering a run of elevations gives a time-of-day sweep.
+fn sky(
+    elevation: Option<&str>,
+    azimuth: Option<&str>,
+    turbidity: Option<&str>,
+    sun_size: Option<&str>,
+) {
+    let sky = Sky::new(
+        parse_number(elevation, 35.0),
+        parse_number(azimuth, 60.0),
+        parse_number(turbidity, 3.0),
+    )
+    .sun_size(parse_number(sun_size, 0.53));
+
+    render_outdoors(Background::Sky(Arc::new(sky)));
+}
+
+fn render_outdoors(background: Background) {
+    let mut world = HittableList::new();
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    world.add(Box::new(Sphere::new(
+        Point3::new(-2.2, 1.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::new(Color::new(0.7, 0.3, 0.2))),
+    )));
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        Arc::new(Conductor::gold(0.3)),
+    )));
+    world.add(Box::new(Sphere::new(
+        Point3::new(2.2, 1.0, 0.0),
+        1.0,
+        Arc::new(Dielectric::new(1.5)),
+    )));
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(400)
+        .samples_per_pixel(100)
+        .max_depth(50)
+        .fov(30.0)
+        .look_from(Point3::new(0.0, 2.0, 10.0))
+        .look_at(Point3::new(0.0, 1.0, 0.0))
+        .background(background);
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+/// Parses an optional numeric argument, exiting with a message if it isn't
+/// a number.
+fn parse_number(text: Option<&str>, default: f64) -> f64 {
+    match text {
+        None => default,
+        Some(text) => text.parse().unwrap_or_else(|_| {
+            eprintln!("{}: not a number", text);
+            process::exit(2);
+        }),
+    }
+}
+
+fn foggy_spheres() {
+    let mut world = HittableList::new();
+
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+
+    // Dark smoke behind a glass shell, and a forward-scattering haze ball.
+    world.add(Box::new(Sphere::new(
+        Point3::new(-2.2, 1.0, 0.0),
+        1.0,
+        Arc::new(Dielectric::new(1.5)),
+    )));
+    world.add(Box::new(ConstantMedium::new(
+        Box::new(Sphere::new(
+            Point3::new(-2.2, 1.0, 0.0),
+            0.9,
+            Arc::new(Dielectric::new(1.5)),
+        )),
+        2.0,
+        Color::new(0.2, 0.2, 0.2),
+    )));
+    world.add(Box::new(ConstantMedium::with_phase_function(
+        Box::new(Sphere::new(
+            Point3::new(0.0, 1.0, 0.0),
+            1.0,
+            Arc::new(Dielectric::new(1.5)),
+        )),
+        3.0,
+        Arc::new(HenyeyGreenstein::new(Color::new(0.9, 0.6, 0.3), 0.7)),
+    )));
+    world.add(Box::new(Sphere::new(
+        Point3::new(2.2, 1.0, 0.0),
+        1.0,
+        Arc::new(Metal::new(Color::new(0.8, 0.8, 0.8), 0.0)),
+    )));
+
+    // A thin fog enclosing the whole scene, camera included.
+    world.add(Box::new(ConstantMedium::new(
+        Box::new(Sphere::new(
+            Point3::zero(),
+            50.0,
+            Arc::new(Dielectric::new(1.5)),
+        )),
+        0.02,
+        Color::new(1.0, 1.0, 1.0),
+    )));
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(400)
+        .samples_per_pixel(100)
+        .max_depth(50)
+        .fov(30.0)
+        .look_from(Point3::new(0.0, 2.0, 9.0))
+        .look_at(Point3::new(0.0, 1.0, 0.0));
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+fn volumes(grid_path: Option<&str>) {
+    let mut rng = random::rng();
+    let mut world = HittableList::new();
+
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+
+    // A white, mostly scattering cloud, whose droplets scatter forward.
+    let cloud_center = Point3::new(-2.2, 1.2, 0.0);
+    let cloud = NoiseDensity::new(
+        Box::new(Simplex::new(&mut rng)),
+        1.5,
+        5,
+        0.6,
+        8.0,
+        cloud_center,
+        1.2,
+    );
+    let white = Color::new(0.95, 0.95, 0.95);
+    world.add(Box::new(
+        HeterogeneousMedium::new(
+            Box::new(Sphere::new(
+                cloud_center,
+                1.2,
+                Arc::new(Dielectric::new(1.0)),
+            )),
+            Box::new(cloud),
+            0.05,
+            1.0,
+            white,
+        )
+        .phase_function(Arc::new(HenyeyGreenstein::new(white, 0.6))),
+    ));
+
+    // Fire: strongly absorbing and emitting, with little scattering.
+    let fire_center = Point3::new(0.0, 1.0, 0.0);
+    let fire = NoiseDensity::new(
+        Box::new(Simplex::new(&mut rng)),
+        2.5,
+        4,
+        0.7,
+        6.0,
+        fire_center,
+        1.0,
+    );
+    world.add(Box::new(
+        HeterogeneousMedium::new(
+            Box::new(Sphere::new(
+                fire_center,
+                1.0,
+                Arc::new(Dielectric::new(1.0)),
+            )),
+            Box::new(fire),
+            1.0,
+            0.1,
+            Color::new(0.3, 0.3, 0.3),
+        )
+        .emission(Color::new(4.0, 1.4, 0.3), None),
+    ));
+
+    // A voxel grid: loaded from a dense grid file if one is given, otherwise
+    // a soft ball generated in place.
+    let grid_bounds = Aabb::new(Point3::new(1.4, 0.2, -0.8), Point3::new(3.0, 1.8, 0.8));
+    let grid = match grid_path {
+        Some(path) => match VoxelGrid::load_dense(Path::new(path), grid_bounds) {
+            Ok(grid) => grid,
+            Err(error) => {
+                eprintln!("{}: {}", path, error);
+                process::exit(1);
+            }
+        },
+        None => {
+            let n = 16;
+            let mut values = Vec::with_capacity(n * n * n);
+            for z in 0..n {
+                for y in 0..n {
+                    for x in 0..n {
+                        let p = Vector3::new(x as f64, y as f64, z as f64) / (n - 1) as f64;
+                        let r = (p - Vector3::new(0.5, 0.5, 0.5)).length();
+                        values.push((1.0 - 2.0 * r).max(0.0) * 6.0);
+                    }
+                }
+            }
+            VoxelGrid::new(n, n, n, values, grid_bounds)
+        }
+    };
+    world.add(Box::new(HeterogeneousMedium::new(
+        Box::new(Sphere::new(
+            grid_bounds.centroid(),
+            grid_bounds.extent().length() / 2.0,
+            Arc::new(Dielectric::new(1.0)),
+        )),
+        Box::new(grid),
+        0.2,
+        1.0,
+        Color::new(0.4, 0.6, 0.9),
+    )));
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(400)
+        .samples_per_pixel(100)
+        .max_depth(50)
+        .fov(30.0)
+        .look_from(Point3::new(0.0, 2.0, 9.0))
+        .look_at(Point3::new(0.0, 1.0, 0.0));
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+fn microfacet_spheres() {
+    let mut world = HittableList::new();
+
+    let checker = Arc::new(CheckerTexture::from_colors(
+        0.5,
+        Color::new(0.2, 0.2, 0.2),
+        Color::new(0.8, 0.8, 0.8),
+    ));
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::from_texture(checker)),
+    )));
+
+    // Front row: the conductor presets, rougher from left to right.
+    let conductors = [
+        Conductor::gold(0.0),
+        Conductor::copper(0.2),
+        Conductor::aluminium(0.4),
+        Conductor::silver(0.6),
+    ];
+    for (i, conductor) in conductors.into_iter().enumerate() {
+        world.add(Box::new(Sphere::new(
+            Point3::new(-3.3 + 2.2 * i as f64, 1.0, 0.0),
+            1.0,
+            Arc::new(conductor),
+        )));
+    }
+
+    // Back row: smooth and rough glass, and brushed (anisotropic) aluminium.
+    let back: [Arc<dyn Material>; 3] = [
+        Arc::new(RoughDielectric::new(1.5, 0.0)),
+        Arc::new(RoughDielectric::new(1.5, 0.3)),
+        Arc::new(Conductor::anisotropic(
+            Color::new(1.657, 0.880, 0.521),
+            Color::new(9.224, 6.270, 4.837),
+            0.1,
+            0.6,
+        )),
+    ];
+    for (i, material) in back.into_iter().enumerate() {
+        world.add(Box::new(Sphere::new(
+            Point3::new(-2.2 + 2.2 * i as f64, 1.0, -3.0),
+            1.0,
+            material,
+        )));
+    }
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(400)
+        .samples_per_pixel(100)
+        .max_depth(50)
+        .fov(35.0)
+        .look_from(Point3::new(0.0, 3.0, 10.0))
+        .look_at(Point3::new(0.0, 1.0, -1.0));
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+fn glass_spheres() {
+    let mut world = HittableList::new();
+
+    let checker = Arc::new(CheckerTexture::from_colors(
+        0.5,
+        Color::new(0.2, 0.2, 0.2),
+        Color::new(0.8, 0.8, 0.8),
+    ));
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::from_texture(checker)),
+    )));
+
+    // Front row: the same green glass at three sizes, so the colour deepens
+    // with the distance travelled inside, next to clear glass.
+    let green = Color::new(0.2, 0.8, 0.4);
+    let front: [(f64, Arc<dyn Material>); 4] = [
+        (1.0, Arc::new(Dielectric::new(1.5))),
+        (0.4, Arc::new(Dielectric::new(1.5).absorption(green, 1.0))),
+        (0.7, Arc::new(Dielectric::new(1.5).absorption(green, 1.0))),
+        (1.0, Arc::new(Dielectric::new(1.5).absorption(green, 1.0))),
+    ];
+    for (i, (radius, material)) in front.into_iter().enumerate() {
+        world.add(Box::new(Sphere::new(
+            Point3::new(-3.3 + 2.2 * i as f64, radius, 0.0),
+            radius,
+            material,
+        )));
+    }
+
+    // Back row: rough amber glass, a thin-walled bubble and a thin frosted shell.
+    let amber = Color::new(0.9, 0.5, 0.1);
+    let back: [Arc<dyn Material>; 3] = [
+        Arc::new(RoughDielectric::new(1.5, 0.3).absorption(amber, 1.0)),
+        Arc::new(Dielectric::new(1.33).thin_walled(true)),
+        Arc::new(RoughDielectric::new(1.5, 0.4).thin_walled(true)),
+    ];
+    for (i, material) in back.into_iter().enumerate() {
+        world.add(Box::new(Sphere::new(
+            Point3::new(-2.2 + 2.2 * i as f64, 1.0, -3.0),
+            1.0,
+            material,
+        )));
+    }
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(400)
+        .samples_per_pixel(100)
+        .max_depth(50)
+        .fov(35.0)
+        .look_from(Point3::new(0.0, 4.0, 10.0))
+        .look_at(Point3::new(0.0, 0.8, -1.5));
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+fn dispersion() {
+    let mut world = HittableList::new();
+
+    let checker = Arc::new(CheckerTexture::from_colors(
+        0.25,
+        Color::new(0.05, 0.05, 0.05),
+        Color::new(0.95, 0.95, 0.95),
+    ));
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::from_texture(checker)),
+    )));
+
+    // Crown glass, water, dense flint and diamond, in order of increasing
+    // dispersion, with a matte sphere to compare the white balance against
+    // RGB renders.
+    let materials: [Arc<dyn Material>; 5] = [
+        Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8))),
+        Arc::new(Dielectric::dispersive(Dispersion::bk7())),
+        Arc::new(Dielectric::dispersive(Dispersion::water())),
+        Arc::new(Dielectric::dispersive(Dispersion::dense_flint())),
+        Arc::new(Dielectric::dispersive(Dispersion::diamond())),
+    ];
+    for (i, material) in materials.into_iter().enumerate() {
+        world.add(Box::new(Sphere::new(
+            Point3::new(-4.4 + 2.2 * i as f64, 1.0, 0.0),
+            1.0,
+            material,
+        )));
+    }
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(400)
+        .samples_per_pixel(200)
+        .max_depth(50)
+        .fov(45.0)
+        .look_from(Point3::new(0.0, 3.0, 10.0))
+        .look_at(Point3::new(0.0, 1.0, 0.0))
+        .spectral(true);
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+fn layered_spheres() {
+    let mut world = HittableList::new();
+    let mut rng = random::rng();
+
+    let checker = Arc::new(CheckerTexture::from_colors(
+        0.5,
+        Color::new(0.2, 0.2, 0.2),
+        Color::new(0.8, 0.8, 0.8),
+    ));
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::from_texture(checker)),
+    )));
+
+    // Streaks of rust on polished steel, masked by a black and white marble.
+    let rust_mask: Arc<dyn Texture> = Arc::new(MarbleTexture::new(
+        4.0,
+        Color::black(),
+        Color::white(),
+        &mut rng,
+    ));
+    let rusty_steel = MixMaterial::new(
+        Arc::new(Conductor::aluminium(0.1)),
+        Arc::new(Lambertian::new(Color::new(0.45, 0.2, 0.08))),
+        rust_mask,
+    );
+
+    // Amber varnish over wood, and glossy lacquer over matte red paint.
+    let wood = Arc::new(WoodTexture::new(
+        6.0,
+        Color::new(0.75, 0.5, 0.3),
+        Color::new(0.4, 0.22, 0.1),
+        &mut rng,
+    ));
+    let varnished_wood = Coated::new(Arc::new(Lambertian::from_texture(wood)), 1.5, 0.05)
+        .tint(Color::new(0.95, 0.85, 0.6));
+    let car_paint = Coated::new(
+        Arc::new(Lambertian::new(Color::new(0.6, 0.05, 0.05))),
+        1.5,
+        0.0,
+    );
+    let coated_gold = Coated::new(Arc::new(Conductor::gold(0.5)), 1.5, 0.0);
+
+    let materials: [Arc<dyn Material>; 4] = [
+        Arc::new(rusty_steel),
+        Arc::new(varnished_wood),
+        Arc::new(car_paint),
+        Arc::new(coated_gold),
+    ];
+    for (i, material) in materials.into_iter().enumerate() {
+        world.add(Box::new(Sphere::new(
+            Point3::new(-3.3 + 2.2 * i as f64, 1.0, 0.0),
+            1.0,
+            material,
+        )));
+    }
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(400)
+        .samples_per_pixel(100)
+        .max_depth(50)
+        .fov(35.0)
+        .look_from(Point3::new(0.0, 3.0, 10.0))
+        .look_at(Point3::new(0.0, 1.0, 0.0));
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+fn iridescence() {
+    let mut world = HittableList::new();
+    let mut rng = random::rng();
+
+    let checker = Arc::new(CheckerTexture::from_colors(
+        0.5,
+        Color::new(0.2, 0.2, 0.2),
+        Color::new(0.8, 0.8, 0.8),
+    ));
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::from_texture(checker)),
+    )));
+
+    // A soap bubble: nothing but a swirling water film.
+    let swirl = Arc::new(NoiseTexture::perlin(2.0, &mut rng));
+    let bubble = Dielectric::new(1.0)
+        .thin_walled(true)
+        .thin_film(ThinFilm::from_texture(swirl, 200.0, 900.0, 1.33));
+
+    // Steel tempered from straw at the bottom to blue at the top.
+    let temper = Arc::new(GradientTexture::new(
+        Color::black(),
+        Color::white(),
+        GradientAxis::Linear {
+            origin: Point3::new(0.0, 0.0, 0.0),
+            direction: Vector3::new(0.0, 1.0, 0.0),
+            length: 2.0,
+        },
+    ));
+    let steel = Conductor::new(Color::new(2.9, 2.9, 2.6), Color::new(3.1, 3.0, 2.8), 0.15)
+        .thin_film(ThinFilm::from_texture(temper, 20.0, 110.0, 2.6));
+
+    // An anti-reflection coated lens next to velvet.
+    let lens = Dielectric::new(1.5).thin_film(ThinFilm::new(100.0, 1.38));
+    let velvet = Sheen::new(
+        Arc::new(Lambertian::new(Color::new(0.3, 0.02, 0.05))),
+        Color::new(1.0, 0.6, 0.7),
+        0.4,
+    );
+
+    let materials: [Arc<dyn Material>; 4] = [
+        Arc::new(bubble),
+        Arc::new(steel),
+        Arc::new(lens),
+        Arc::new(velvet),
+    ];
+    for (i, material) in materials.into_iter().enumerate() {
+        world.add(Box::new(Sphere::new(
+            Point3::new(-3.3 + 2.2 * i as f64, 1.0, 0.0),
+            1.0,
+            material,
+        )));
+    }
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(400)
+        .samples_per_pixel(100)
+        .max_depth(50)
+        .fov(40.0)
+        .look_from(Point3::new(0.0, 2.5, 9.0))
+        .look_at(Point3::new(0.0, 1.0, 0.0));
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+fn subsurface_spheres() {
+    let mut world = HittableList::new();
+
+    let checker = Arc::new(CheckerTexture::from_colors(
+        0.5,
+        Color::new(0.2, 0.2, 0.2),
+        Color::new(0.8, 0.8, 0.8),
+    ));
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::from_texture(checker)),
+    )));
+
+    // Lambertian for reference, then wax, skin and marble with the same
+    // overall albedo as far as the mean free path allows.
+    let wax = Color::new(0.9, 0.75, 0.5);
+    world.add(Box::new(Sphere::new(
+        Point3::new(-3.3, 1.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::new(wax)),
+    )));
+    world.add(Box::new(Subsurface::new(
+        Box::new(Sphere::new(
+            Point3::new(-1.1, 1.0, 0.0),
+            1.0,
+            Arc::new(Lambertian::new(wax)),
+        )),
+        wax,
+        Color::new(0.25, 0.15, 0.08),
+    )));
+    world.add(Box::new(
+        Subsurface::new(
+            Box::new(Sphere::new(
+                Point3::new(1.1, 1.0, 0.0),
+                1.0,
+                Arc::new(Lambertian::new(wax)),
+            )),
+            Color::new(0.85, 0.6, 0.5),
+            Color::new(0.15, 0.06, 0.03),
+        )
+        .anisotropy(0.8),
+    ));
+    world.add(Box::new(
+        Subsurface::new(
+            Box::new(Sphere::new(
+                Point3::new(3.3, 1.0, 0.0),
+                1.0,
+                Arc::new(Lambertian::new(wax)),
+            )),
+            Color::new(0.85, 0.85, 0.85),
+            Color::new(0.08, 0.08, 0.08),
+        )
+        .ior(1.5),
+    ));
+
+    // A single triangle has no inside to walk through and so reflects
+    // diffusely.
+    world.add(Box::new(Subsurface::new(
+        Box::new(Triangle::new(
+            Point3::new(-4.5, 0.0, -2.0),
+            Point3::new(4.5, 0.0, -2.0),
+            Point3::new(0.0, 3.0, -2.5),
+            Arc::new(Lambertian::new(wax)),
+        )),
+        Color::new(0.5, 0.6, 0.7),
+        Color::new(0.2, 0.2, 0.2),
+    )));
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(400)
+        .samples_per_pixel(100)
+        .max_depth(50)
+        .fov(40.0)
+        .look_from(Point3::new(0.0, 2.5, 9.0))
+        .look_at(Point3::new(0.0, 1.0, 0.0));
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+fn cutouts(card_path: Option<&str>) {
+    let mut world = HittableList::new();
+
+    let checker = Arc::new(CheckerTexture::from_colors(
+        0.5,
+        Color::new(0.2, 0.3, 0.1),
+        Color::new(0.9, 0.9, 0.9),
+    ));
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::from_texture(checker)),
+    )));
+
+    // A lattice of holes cut into a sphere with a hard alpha test, and a
+    // sphere at half coverage with the stochastic one.
+    let lattice = Arc::new(UvCheckerTexture::from_colors(
+        16,
+        8,
+        Color::white(),
+        Color::black(),
+    ));
+    world.add(Box::new(
+        AlphaMask::new(
+            Box::new(Sphere::new(
+                Point3::new(-1.2, 1.0, 0.0),
+                1.0,
+                Arc::new(Lambertian::new(Color::new(0.8, 0.3, 0.1))),
+            )),
+            lattice,
+        )
+        .test(AlphaTest::Threshold(0.5)),
+    ));
+    world.add(Box::new(AlphaMask::new(
+        Box::new(Sphere::new(
+            Point3::new(1.2, 1.0, 0.0),
+            1.0,
+            Arc::new(Lambertian::new(Color::new(0.1, 0.3, 0.8))),
+        )),
+        Arc::new(SolidColor::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+
+    // A card in front, cut out by the alpha channel of the given image or
+    // else by a grid of square holes like a wire fence.
+    let (color, alpha): (Arc<dyn Texture>, Arc<dyn Texture>) = match card_path {
+        Some(path) => {
+            let mut textures = TextureCache::new();
+            let texture = match textures.load(path) {
+                Ok(texture) => texture,
+                Err(error) => {
+                    eprintln!("{}: {}", path, error);
+                    process::exit(1);
+                }
+            };
+            // Clamped, so the card's edges aren't blended with the image's
+            // opposite sides.
+            let alpha = texture
+                .alpha()
+                .wrap(WrapMode::ClampToEdge, WrapMode::ClampToEdge);
+            (texture, Arc::new(alpha))
+        }
+        None => (
+            Arc::new(SolidColor::new(Color::new(0.6, 0.6, 0.6))),
+            Arc::new(UvCheckerTexture::from_colors(
+                24,
+                12,
+                Color::white(),
+                Color::black(),
+            )),
+        ),
+    };
+    let card = Arc::new(Lambertian::from_texture(color));
+    let corners = [
+        Point3::new(-2.0, 0.0, 1.5),
+        Point3::new(2.0, 0.0, 1.5),
+        Point3::new(2.0, 2.0, 1.5),
+        Point3::new(-2.0, 2.0, 1.5),
+    ];
+    let mut panel = HittableList::new();
+    panel.add(Box::new(
+        Triangle::new(corners[0], corners[1], corners[2], card.clone()).uvs(
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (1.0, 1.0),
+        ),
+    ));
+    panel.add(Box::new(
+        Triangle::new(corners[0], corners[2], corners[3], card).uvs(
+            (0.0, 0.0),
+            (1.0, 1.0),
+            (0.0, 1.0),
+        ),
+    ));
+    world.add(Box::new(
+        AlphaMask::new(Box::new(panel), alpha).test(AlphaTest::Threshold(0.5)),
+    ));
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(400)
+        .samples_per_pixel(100)
+        .max_depth(50)
+        .fov(35.0)
+        .look_from(Point3::new(0.0, 2.0, 9.0))
+        .look_at(Point3::new(0.0, 1.0, 0.0));
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+fn bump_mapping(normal_map_path: Option<&str>, strength: Option<&str>) {
+    let mut world = HittableList::new();
+    let mut rng = random::rng();
+
+    let checker = Arc::new(CheckerTexture::from_colors(
+        0.5,
+        Color::new(0.2, 0.2, 0.2),
+        Color::new(0.8, 0.8, 0.8),
+    ));
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::from_texture(checker)),
+    )));
+
+    // Solid-texture bumps on a matte sphere and hammered metal.
+    let lumps: Arc<dyn Texture> = Arc::new(NoiseTexture::perlin(4.0, &mut rng));
+    let dents: Arc<dyn Texture> = Arc::new(NoiseTexture::simplex(10.0, &mut rng));
+    let spheres: [Arc<dyn Material>; 3] = [
+        Arc::new(Lambertian::new(Color::new(0.7, 0.3, 0.2))),
+        Arc::new(BumpMap::new(
+            Arc::new(Lambertian::new(Color::new(0.7, 0.3, 0.2))),
+            lumps,
+            0.15,
+        )),
+        Arc::new(BumpMap::new(Arc::new(Conductor::silver(0.1)), dents, 0.02)),
+    ];
+    for (i, material) in spheres.into_iter().enumerate() {
+        world.add(Box::new(Sphere::new(
+            Point3::new(-2.2 + 2.2 * i as f64, 1.0, 0.0),
+            1.0,
+            material,
+        )));
+    }
+
+    // A UV-mapped wall of two triangles, with the normal map given on the
+    // command line, at `strength`, or else marble ridges as a bump map.
+    let wall_color = Arc::new(Lambertian::new(Color::new(0.6, 0.6, 0.55)));
+    let wall: Arc<dyn Material> = match normal_map_path {
+        Some(path) => {
+            let mut textures = TextureCache::new();
+            let map = match textures.load_data(path) {
+                Ok(map) => map,
+                Err(error) => {
+                    eprintln!("{}: {}", path, error);
+                    process::exit(1);
+                }
+            };
+            Arc::new(NormalMap::new(wall_color, map).strength(parse_number(strength, 1.0)))
+        }
+        None => {
+            let ridges = Arc::new(MarbleTexture::new(
+                6.0,
+                Color::black(),
+                Color::white(),
+                &mut rng,
+            ));
+            Arc::new(BumpMap::new(wall_color, ridges, 0.3))
+        }
+    };
+
+    let corners = [
+        Point3::new(-4.0, 0.0, -2.5),
+        Point3::new(4.0, 0.0, -2.5),
+        Point3::new(4.0, 4.0, -2.5),
+        Point3::new(-4.0, 4.0, -2.5),
+    ];
+    world.add(Box::new(
+        Triangle::new(corners[0], corners[1], corners[2], wall.clone()).uvs(
+            (0.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 1.0),
+        ),
+    ));
+    world.add(Box::new(
+        Triangle::new(corners[0], corners[2], corners[3], wall).uvs(
+            (0.0, 0.0),
+            (2.0, 1.0),
+            (0.0, 1.0),
+        ),
+    ));
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(400)
+        .samples_per_pixel(100)
+        .max_depth(50)
+        .fov(40.0)
+        .look_from(Point3::new(0.0, 2.5, 8.0))
+        .look_at(Point3::new(0.0, 1.2, 0.0));
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+fn principled_spheres(mtl_path: Option<&str>) {
+    let mut world = HittableList::new();
+
+    let checker = Arc::new(CheckerTexture::from_colors(
+        0.5,
+        Color::new(0.2, 0.2, 0.2),
+        Color::new(0.8, 0.8, 0.8),
+    ));
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::from_texture(checker)),
+    )));
+
+    // With an MTL file, show its materials in name order; otherwise a
+    // sweep over the main parameters.
+    let materials: Vec<Arc<dyn Material>> = match mtl_path {
+        Some(path) => {
+            let mut textures = TextureCache::new();
+            let library = match load_mtl(Path::new(path), &mut textures) {
+                Ok(library) => library,
+                Err(error) => {
+                    eprintln!("{}: {}", path, error);
+                    process::exit(1);
+                }
+            };
+            let mut names: Vec<_> = library.keys().cloned().collect();
+            names.sort();
+            names
+                .iter()
+                .map(|name| library[name].clone() as Arc<dyn Material>)
+                .collect()
+        }
+        None => {
+            let red = Color::new(0.8, 0.1, 0.1);
+            vec![
+                Arc::new(Principled::new(red).roughness(0.1)),
+                Arc::new(Principled::new(red).roughness(0.6)),
+                Arc::new(Principled::new(red).roughness(0.3).clearcoat(1.0)),
+                Arc::new(Principled::new(red).roughness(1.0).sheen(1.0)),
+                Arc::new(
+                    Principled::new(Color::new(0.95, 0.64, 0.54))
+                        .metallic(1.0)
+                        .roughness(0.2),
+                ),
+                Arc::new(
+                    Principled::new(Color::new(0.9, 0.9, 0.9))
+                        .metallic(1.0)
+                        .roughness(0.5),
+                ),
+                Arc::new(
+                    Principled::new(Color::new(0.7, 0.9, 1.0))
+                        .transmission(1.0)
+                        .roughness(0.0),
+                ),
+                Arc::new(
+                    Principled::new(Color::new(1.0, 1.0, 1.0))
+                        .transmission(1.0)
+                        .roughness(0.3),
+                ),
+                // Tinted specular and sheen, then gold and glass given as
+                // glTF metallic-roughness factors.
+                Arc::new(
+                    Principled::new(red)
+                        .roughness(0.3)
+                        .specular(1.0)
+                        .specular_tint(1.0),
+                ),
+                Arc::new(
+                    Principled::new(red)
+                        .roughness(1.0)
+                        .sheen(1.0)
+                        .sheen_tint(1.0),
+                ),
+                Arc::new(Principled::from_gltf(
+                    Arc::new(SolidColor::new(Color::new(1.0, 0.77, 0.34))),
+                    1.0,
+                    0.35,
+                    None,
+                    None,
+                )),
+                Arc::new(Principled::from_gltf(
+                    Arc::new(SolidColor::new(Color::new(1.0, 1.0, 1.0))),
+                    0.0,
+                    0.1,
+                    Some(1.0),
+                    Some(1.45),
+                )),
+            ]
+        }
+    };
+
+    // Rows of four spheres, front to back.
+    for (i, material) in materials.into_iter().enumerate() {
+        let column = (i % 4) as f64;
+        let row = (i / 4) as f64;
+        world.add(Box::new(Sphere::new(
+            Point3::new(-3.3 + 2.2 * column, 1.0, -3.0 * row),
+            1.0,
+            material,
+        )));
+    }
+
+    let builder = CameraBuilder::new()
+        .aspect_ratio(16.0 / 9.0)
+        .width(400)
+        .samples_per_pixel(100)
+        .max_depth(50)
+        .fov(35.0)
+        .look_from(Point3::new(0.0, 6.0, 11.0))
+        .look_at(Point3::new(0.0, 0.5, -1.5));
+
+    let camera = Camera::new_from_builder(&builder);
+    camera.render(&BvhNode::new(world));
+}
+
+fn white_furnace() {
+    const SAMPLES: u32 = 200_000;
+    // Allowance for Monte Carlo noise at this sample count.
+    const TOLERANCE: f64 = 0.01;
+
+    let white = Color::new(1.0, 1.0, 1.0);
+    let cases: Vec<(&str, Box<dyn Material>)> = vec![
+        ("lambertian", Box::new(Lambertian::new(white))),
+        (
+            "principled diffuse",
+            Box::new(Principled::new(white).roughness(0.0)),
+        ),
+        (
+            "principled rough diffuse",
+            Box::new(Principled::new(white).roughness(1.0)),
+        ),
+        (
+            "principled sheen",
+            Box::new(Principled::new(white).roughness(1.0).sheen(1.0)),
+        ),
+        (
+            "principled clearcoat",
+            Box::new(Principled::new(white).clearcoat(1.0)),
+        ),
+        (
+            "principled specular",
+            Box::new(Principled::new(white).specular(1.0)),
+        ),
+        (
+            "principled metal",
+            Box::new(Principled::new(white).metallic(1.0).roughness(0.3)),
+        ),
+        (
+            "principled rough metal",
+            Box::new(Principled::new(white).metallic(1.0).roughness(1.0)),
+        ),
+        (
+            "principled glass",
+            Box::new(Principled::new(white).transmission(1.0).roughness(0.2)),
+        ),
+        ("rough dielectric", Box::new(RoughDielectric::new(1.5, 0.5))),
+        (
+            "mix lambertian/metal",
+            Box::new(MixMaterial::new(
+                Arc::new(Lambertian::new(white)),
+                Arc::new(Metal::new(white, 0.0)),
+                0.3,
+            )),
+        ),
+        (
+            "coated lambertian",
+            Box::new(Coated::new(Arc::new(Lambertian::new(white)), 1.5, 0.0)),
+        ),
+        (
+            "rough coated lambertian",
+            Box::new(Coated::new(Arc::new(Lambertian::new(white)), 1.5, 0.4)),
+        ),
+        (
+            "sheen lambertian",
+            Box::new(Sheen::new(Arc::new(Lambertian::new(white)), white, 0.3)),
+        ),
+        (
+            "rough sheen lambertian",
+            Box::new(Sheen::new(Arc::new(Lambertian::new(white)), white, 1.0)),
+        ),
+        (
+            "thin-film glass",
+            Box::new(Dielectric::new(1.5).thin_film(ThinFilm::new(350.0, 1.33))),
+        ),
+        (
+            "soap bubble",
+            Box::new(
+                Dielectric::new(1.0)
+                    .thin_walled(true)
+                    .thin_film(ThinFilm::new(350.0, 1.33)),
+            ),
+        ),
+        (
+            "thin-film silver",
+            Box::new(Conductor::silver(0.2).thin_film(ThinFilm::new(250.0, 2.0))),
+        ),
+    ];
+
+    let mut passed = true;
+    for (name, material) in &cases {
+        let mut worst = 0.0f64;
+        let mut line = String::new();
+        for cos_theta in [1.0, 0.7, 0.4, 0.1] {
+            let albedo = directional_albedo(material.as_ref(), cos_theta, SAMPLES);
+            let largest = albedo.r().max(albedo.g()).max(albedo.b());
+            worst = worst.max(largest);
+            line.push_str(&format!(" {:.4}", largest));
+        }
+
+        let ok = worst <= 1.0 + TOLERANCE;
+        passed &= ok;
+        println!(
+            "{:<26}{} {}",
+            name,
+            line,
+            if ok { "ok" } else { "GAINS ENERGY" }
+        );
+    }
+
+    if !passed {
+        process::exit(1);
+    }
 }
@@ -0,0 +1,457 @@
+use crate::materials::material::Material;
+use crate::materials::microfacet::{
+    facet_reflection, fresnel_dielectric, reflect_off_facet, sample_facet, TrowbridgeReitz,
+};
+use crate::materials::principled::Parameter;
+use crate::math::color::Color;
+use crate::math::onb::Onb;
+use crate::math::random;
+use crate::math::ray::{random_unit_vector, Ray};
+use crate::math::vector3::{dot, unit_vector, Vector3};
+use crate::objects::hittable::HitRecord;
+use rand::Rng;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// Picks `second` with probability `weight` and `first` otherwise, so a
+/// texture can paint one material over another (rust on steel, moss on
+/// stone). Emission is blended by the same weight.
+pub struct MixMaterial {
+    first: Arc<dyn Material>,
+    second: Arc<dyn Material>,
+    weight: Parameter,
+}
+
+impl MixMaterial {
+    pub fn new(
+        first: Arc<dyn Material>,
+        second: Arc<dyn Material>,
+        weight: impl Into<Parameter>,
+    ) -> Self {
+        Self {
+            first,
+            second,
+            weight: weight.into(),
+        }
+    }
+}
+
+impl Material for MixMaterial {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        let weight = self.weight.value(record).clamp(0.0, 1.0);
+        let mut rng = random::rng();
+        let material = if rng.random_range(0.0..1.0) < weight {
+            &self.second
+        } else {
+            &self.first
+        };
+
+        if !material.scatter(ray, record, attenuation, scattered) {
+            return false;
+        }
+        mark_unevaluated(material.as_ref(), ray, record, scattered);
+
+        true
+    }
+
+    fn emitted(&self, ray: &Ray, record: &HitRecord) -> Color {
+        let weight = self.weight.value(record).clamp(0.0, 1.0);
+
+        (1.0 - weight) * self.first.emitted(ray, record) + weight * self.second.emitted(ray, record)
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        let weight = self.weight.value(record).clamp(0.0, 1.0);
+
+        (1.0 - weight) * self.first.eval(ray, record, direction)
+            + weight * self.second.eval(ray, record, direction)
+    }
+
+    fn pdf(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        let weight = self.weight.value(record).clamp(0.0, 1.0);
+
+        (1.0 - weight) * self.first.pdf(ray, record, direction)
+            + weight * self.second.pdf(ray, record, direction)
+    }
+}
+
+/// Marks `scattered` as specular when `material`, which picked it, can't
+/// evaluate it: the layer around it can, at other directions, so the
+/// tracer would otherwise weigh this one against light sampling.
+fn mark_unevaluated(material: &dyn Material, ray: &Ray, record: &HitRecord, scattered: &mut Ray) {
+    if material.pdf(ray, record, &scattered.direction) == 0.0 {
+        scattered.specular = true;
+    }
+}
+
+/// A clear dielectric layer, such as varnish or car-paint lacquer, over any
+/// base material. The coat reflects by its Fresnel term; what it doesn't
+/// reflect reaches the base, and whatever the base sends back loses the
+/// coat's Fresnel reflectance again on the way out. Light reflected back
+/// down by the underside of the coat is dropped, so the layer can only
+/// darken, never brighten, the base.
+pub struct Coated {
+    base: Arc<dyn Material>,
+    index_of_refraction: f64,
+    distribution: TrowbridgeReitz,
+    /// Transmittance of one pass straight through the coat.
+    tint: Color,
+}
+
+impl Coated {
+    pub fn new(base: Arc<dyn Material>, index_of_refraction: f64, roughness: f64) -> Self {
+        Self {
+            base,
+            index_of_refraction,
+            distribution: TrowbridgeReitz::from_roughness(roughness, roughness),
+            tint: Color::white(),
+        }
+    }
+
+    /// Colors the coat, like an amber varnish. Light crossing it at an angle
+    /// travels further and is tinted more strongly.
+    pub fn tint(mut self, tint: Color) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    /// Transmittance along crossings of the coat at the given cosines, each
+    /// one `1 / cos` times as long as a straight pass.
+    fn coat_transmittance(&self, cosines: &[f64]) -> Color {
+        if self.tint.r() >= 1.0 && self.tint.g() >= 1.0 && self.tint.b() >= 1.0 {
+            return Color::white();
+        }
+
+        let path: f64 = cosines.iter().map(|cos| 1.0 / cos.max(1e-4)).sum();
+        Color::new(
+            self.tint.r().max(0.0).powf(path),
+            self.tint.g().max(0.0).powf(path),
+            self.tint.b().max(0.0).powf(path),
+        )
+    }
+}
+
+impl Material for Coated {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        // Seen from inside (a coated glass, say), there is no coat to hit.
+        if !record.front_face {
+            return self.base.scatter(ray, record, attenuation, scattered);
+        }
+
+        let frame = Onb::new(&record.normal);
+        let wo = frame.world_to_local(&-unit_vector(&ray.direction));
+        if wo.z() <= 0.0 {
+            return false;
+        }
+
+        // The coat is picked by its reflectance towards the viewer, which
+        // `eval` can reproduce, and the sampled facet's own Fresnel term is
+        // applied as a weight.
+        let reflectance = fresnel_dielectric(wo.z(), self.index_of_refraction);
+        let mut rng = random::rng();
+        if rng.random_range(0.0..1.0) < reflectance {
+            let wm = sample_facet(&self.distribution, &wo);
+            let Some((wi, weight)) = reflect_off_facet(&self.distribution, &wo, &wm) else {
+                return false;
+            };
+            let fresnel = fresnel_dielectric(dot(&wo, &wm), self.index_of_refraction);
+            *scattered = Ray::new(record.point, frame.local_vector(&wi), ray.time)
+                .specular(self.distribution.is_smooth());
+            *attenuation = (weight * fresnel / reflectance) * Color::white();
+            return true;
+        }
+
+        if !self.base.scatter(ray, record, attenuation, scattered) {
+            return false;
+        }
+        mark_unevaluated(self.base.as_ref(), ray, record, scattered);
+
+        let cos_out = dot(&unit_vector(&scattered.direction), &record.normal).abs();
+        let exit = 1.0 - fresnel_dielectric(cos_out, self.index_of_refraction);
+        *attenuation = exit * (*attenuation * self.coat_transmittance(&[wo.z(), cos_out]));
+
+        true
+    }
+
+    fn emitted(&self, ray: &Ray, record: &HitRecord) -> Color {
+        let emitted = self.base.emitted(ray, record);
+        if !record.front_face || emitted.is_black() {
+            return emitted;
+        }
+
+        let cos_view = dot(&-unit_vector(&ray.direction), &record.normal).abs();
+        let exit = 1.0 - fresnel_dielectric(cos_view, self.index_of_refraction);
+        exit * (emitted * self.coat_transmittance(&[cos_view]))
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        if !record.front_face {
+            return self.base.eval(ray, record, direction);
+        }
+
+        let frame = Onb::new(&record.normal);
+        let wo = frame.world_to_local(&-unit_vector(&ray.direction));
+        let wi = frame.world_to_local(&unit_vector(direction));
+        if wo.z() <= 0.0 {
+            return Color::black();
+        }
+
+        let mut value = Color::black();
+        if let Some(coat) = facet_reflection(&self.distribution, &wo, &wi) {
+            let fresnel = fresnel_dielectric(dot(&wo, &coat.wm), self.index_of_refraction);
+            value += (fresnel * coat.value) * Color::white();
+        }
+
+        let base = self.base.eval(ray, record, direction);
+        if !base.is_black() {
+            let reflectance = fresnel_dielectric(wo.z(), self.index_of_refraction);
+            let cos_in = wi.z().abs();
+            let exit = 1.0 - fresnel_dielectric(cos_in, self.index_of_refraction);
+            value +=
+                ((1.0 - reflectance) * exit) * (base * self.coat_transmittance(&[wo.z(), cos_in]));
+        }
+
+        value
+    }
+
+    fn pdf(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        if !record.front_face {
+            return self.base.pdf(ray, record, direction);
+        }
+
+        let frame = Onb::new(&record.normal);
+        let wo = frame.world_to_local(&-unit_vector(&ray.direction));
+        let wi = frame.world_to_local(&unit_vector(direction));
+        if wo.z() <= 0.0 {
+            return 0.0;
+        }
+
+        let reflectance = fresnel_dielectric(wo.z(), self.index_of_refraction);
+        let coat = facet_reflection(&self.distribution, &wo, &wi).map_or(0.0, |coat| coat.pdf);
+
+        reflectance * coat + (1.0 - reflectance) * self.base.pdf(ray, record, direction)
+    }
+}
+
+/// Number of viewing angles the sheen albedo is tabulated at.
+const SHEEN_TABLE_SIZE: usize = 32;
+
+/// A velvety sheen over any base material, for cloth: fibres standing up
+/// from the surface catch light at grazing angles and brighten the rim.
+/// The lobe is the "Charlie" distribution of Estevez and Kulla (2017) with
+/// Ashikhmin's visibility term. Whatever the sheen reflects is taken away
+/// from the base, so the layer never adds energy.
+pub struct Sheen {
+    base: Arc<dyn Material>,
+    color: Color,
+    alpha: f64,
+    /// Directional albedo of a white sheen, by the cosine of the view angle.
+    albedo: [f64; SHEEN_TABLE_SIZE],
+}
+
+impl Sheen {
+    /// `roughness` in (0, 1] sets how far the sheen spreads from grazing
+    /// angles; low values give a tight rim as on satin.
+    pub fn new(base: Arc<dyn Material>, color: Color, roughness: f64) -> Self {
+        let roughness = roughness.clamp(0.07, 1.0);
+        let alpha = roughness * roughness;
+
+        let mut albedo = [0.0; SHEEN_TABLE_SIZE];
+        for (i, entry) in albedo.iter_mut().enumerate() {
+            let cos_o = (i as f64 + 0.5) / SHEEN_TABLE_SIZE as f64;
+            *entry = sheen_albedo(alpha, cos_o);
+        }
+
+        Self {
+            base,
+            color,
+            alpha,
+            albedo,
+        }
+    }
+
+    fn albedo_at(&self, cos_o: f64) -> f64 {
+        let x = (cos_o * SHEEN_TABLE_SIZE as f64 - 0.5).clamp(0.0, (SHEEN_TABLE_SIZE - 1) as f64);
+        let i = (x.floor() as usize).min(SHEEN_TABLE_SIZE - 2);
+        let t = x - i as f64;
+
+        self.albedo[i] + t * (self.albedo[i + 1] - self.albedo[i])
+    }
+}
+
+impl Material for Sheen {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        let wo = -unit_vector(&ray.direction);
+        let cos_o = dot(&wo, &record.normal);
+        if !record.front_face || cos_o <= 0.0 {
+            return self.base.scatter(ray, record, attenuation, scattered);
+        }
+
+        let albedo = self.albedo_at(cos_o);
+        let probability = albedo.min(1.0);
+        let mut rng = random::rng();
+        if rng.random_range(0.0..1.0) < probability {
+            // Uniform hemisphere sampling, so f * cos / pdf is f * cos * 2 pi.
+            // The lobe peaks towards grazing directions, where cosine
+            // sampling would rarely look.
+            let mut wi = random_unit_vector();
+            if dot(&wi, &record.normal) < 0.0 {
+                wi = -wi;
+            }
+            let cos_i = dot(&wi, &record.normal);
+            if cos_i <= 0.0 {
+                return false;
+            }
+
+            let f = charlie_sheen(self.alpha, cos_o, cos_i, &wo, &wi, &record.normal);
+            *scattered = Ray::new(record.point, wi, ray.time);
+            *attenuation = (f * cos_i * 2.0 * PI / albedo) * self.color;
+            return true;
+        }
+
+        if !self.base.scatter(ray, record, attenuation, scattered) {
+            return false;
+        }
+        mark_unevaluated(self.base.as_ref(), ray, record, scattered);
+        let remaining = Color::white() - probability * self.color;
+        *attenuation = *attenuation * remaining / (1.0 - probability);
+
+        true
+    }
+
+    fn emitted(&self, ray: &Ray, record: &HitRecord) -> Color {
+        self.base.emitted(ray, record)
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        let wo = -unit_vector(&ray.direction);
+        let cos_o = dot(&wo, &record.normal);
+        if !record.front_face || cos_o <= 0.0 {
+            return self.base.eval(ray, record, direction);
+        }
+
+        let albedo = self.albedo_at(cos_o);
+        let probability = albedo.min(1.0);
+        let remaining = Color::white() - probability * self.color;
+        let mut value = self.base.eval(ray, record, direction) * remaining;
+
+        let wi = unit_vector(direction);
+        let cos_i = dot(&wi, &record.normal);
+        if cos_i > 0.0 && albedo > 0.0 {
+            let f = charlie_sheen(self.alpha, cos_o, cos_i, &wo, &wi, &record.normal);
+            value += (probability / albedo * f * cos_i) * self.color;
+        }
+
+        value
+    }
+
+    fn pdf(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        let cos_o = dot(&-unit_vector(&ray.direction), &record.normal);
+        if !record.front_face || cos_o <= 0.0 {
+            return self.base.pdf(ray, record, direction);
+        }
+
+        let probability = self.albedo_at(cos_o).min(1.0);
+        let sheen = if dot(direction, &record.normal) > 0.0 {
+            1.0 / (2.0 * PI)
+        } else {
+            0.0
+        };
+
+        probability * sheen + (1.0 - probability) * self.base.pdf(ray, record, direction)
+    }
+}
+
+/// The sheen BRDF for a white sheen, between directions at cosines `cos_o`
+/// and `cos_i` to `normal`.
+fn charlie_sheen(
+    alpha: f64,
+    cos_o: f64,
+    cos_i: f64,
+    wo: &Vector3,
+    wi: &Vector3,
+    normal: &Vector3,
+) -> f64 {
+    let half = *wo + *wi;
+    if half.length_squared() == 0.0 {
+        return 0.0;
+    }
+    let cos_h = dot(&unit_vector(&half), normal).clamp(-1.0, 1.0);
+    let sin_h = (1.0 - cos_h * cos_h).max(0.0).sqrt();
+
+    let inverse_alpha = 1.0 / alpha;
+    let distribution = (2.0 + inverse_alpha) * sin_h.powf(inverse_alpha) / (2.0 * PI);
+    let visibility = 1.0 / (4.0 * (cos_i + cos_o - cos_i * cos_o));
+
+    distribution * visibility
+}
+
+/// Integrates the white sheen over the hemisphere of lighting directions
+/// with the midpoint rule, which is plenty for a lobe this smooth.
+fn sheen_albedo(alpha: f64, cos_o: f64) -> f64 {
+    const THETA_STEPS: usize = 32;
+    const PHI_STEPS: usize = 64;
+
+    let normal = Vector3::new(0.0, 0.0, 1.0);
+    let wo = Vector3::new((1.0 - cos_o * cos_o).sqrt(), 0.0, cos_o);
+    let d_cos = 1.0 / THETA_STEPS as f64;
+    let d_phi = 2.0 * PI / PHI_STEPS as f64;
+
+    let mut total = 0.0;
+    for i in 0..THETA_STEPS {
+        let cos_i = (i as f64 + 0.5) * d_cos;
+        let sin_i = (1.0 - cos_i * cos_i).sqrt();
+        for j in 0..PHI_STEPS {
+            let phi = (j as f64 + 0.5) * d_phi;
+            let wi = Vector3::new(sin_i * phi.cos(), sin_i * phi.sin(), cos_i);
+            total += charlie_sheen(alpha, cos_o, cos_i, &wo, &wi, &normal) * cos_i;
+        }
+    }
+
+    total * d_cos * d_phi
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::material::{assert_eval_matches_scatter, Dielectric, Lambertian};
+
+    #[test]
+    fn layered_eval_matches_scatter() {
+        let base: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.7, 0.3, 0.2)));
+        let materials: [Box<dyn Material>; 4] = [
+            Box::new(Coated::new(base.clone(), 1.5, 0.3)),
+            Box::new(Coated::new(base.clone(), 1.5, 0.0)),
+            Box::new(MixMaterial::new(
+                base.clone(),
+                Arc::new(Dielectric::new(1.5)),
+                0.5,
+            )),
+            Box::new(Sheen::new(base, Color::new(1.0, 1.0, 1.0), 0.5)),
+        ];
+
+        for material in &materials {
+            for cos_theta in [0.9, 0.4] {
+                assert_eval_matches_scatter(&**material, cos_theta);
+            }
+        }
+    }
+}
@@ -1,11 +1,16 @@
+use crate::materials::thin_film::ThinFilm;
 use crate::math::color::Color;
+use crate::math::random;
 use crate::math::ray;
 use crate::math::ray::{random_in_unit_sphere, Ray};
 use crate::math::vector3::{dot, unit_vector, Vector3};
 use crate::objects::hittable::HitRecord;
+use crate::textures::texture::{SolidColor, Texture};
 use rand::Rng;
+use std::f64::consts::PI;
+use std::sync::Arc;
 
-pub trait Material {
+pub trait Material: Send + Sync {
     fn scatter(
         &self,
         ray: &Ray,
@@ -13,14 +18,146 @@ pub trait Material {
         attenuation: &mut Color,
         scattered: &mut Ray,
     ) -> bool;
+
+    /// Radiance given off at the hit point, independent of any scattering.
+    fn emitted(&self, _ray: &Ray, _record: &HitRecord) -> Color {
+        Color::black()
+    }
+
+    /// Fraction of light arriving from `direction` that is scattered back
+    /// along `ray`, with the cosine factor included: the BSDF times the
+    /// cosine to the normal. Light sampling uses it at every hit; perfectly
+    /// specular lobes are left out, as no light sample can land in them.
+    fn eval(&self, _ray: &Ray, _record: &HitRecord, _direction: &Vector3) -> Color {
+        Color::black()
+    }
+
+    /// Density over solid angle with which `scatter` picks `direction`. Zero,
+    /// the default, marks materials that can't be evaluated this way, such
+    /// as mirrors and glass; those only reach lights by scattering into
+    /// them.
+    fn pdf(&self, _ray: &Ray, _record: &HitRecord, _direction: &Vector3) -> f64 {
+        0.0
+    }
+}
+
+/// Monte Carlo estimate of the fraction of light a material scatters,
+/// reflected or transmitted, when lit from `cos_theta` off its normal. Lit
+/// from inside a uniform white environment (the "white furnace"), an
+/// energy-conserving material never exceeds one.
+pub fn directional_albedo(material: &dyn Material, cos_theta: f64, samples: u32) -> Color {
+    let (incoming, record) = lit_from(cos_theta);
+
+    let mut total = Color::black();
+    for _ in 0..samples {
+        let mut attenuation = Color::black();
+        let mut scattered = Ray::new(Vector3::zero(), Vector3::zero(), 0.0);
+        if material.scatter(&incoming, &record, &mut attenuation, &mut scattered) {
+            total += attenuation;
+        }
+    }
+
+    total / samples as f64
+}
+
+/// A ray arriving at `cos_theta` off the normal of a front-facing hit at the
+/// origin, with the normal along +Z.
+fn lit_from(cos_theta: f64) -> (Ray, HitRecord) {
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let mut record = HitRecord::new();
+    record.normal = Vector3::new(0.0, 0.0, 1.0);
+    record.geometric_normal = record.normal;
+    record.front_face = true;
+    record.u = 0.5;
+    record.v = 0.5;
+
+    let incoming = Ray::new(
+        Vector3::new(sin_theta, 0.0, cos_theta),
+        Vector3::new(-sin_theta, 0.0, -cos_theta),
+        0.0,
+    );
+
+    (incoming, record)
+}
+
+/// Checks `eval` and `pdf` against `scatter` for light arriving at
+/// `cos_theta`: over each patch of a grid on the sphere, the density must
+/// integrate to the fraction of samples `scatter` sends there, and `eval`
+/// to the weight they carry. Samples marked specular aren't counted.
+#[cfg(test)]
+pub(crate) fn assert_eval_matches_scatter(material: &dyn Material, cos_theta: f64) {
+    const SAMPLES: u32 = 200_000;
+    const BANDS: usize = 8;
+    const SECTORS: usize = 8;
+    // Quadrature cells along each side of a patch.
+    const CELLS: usize = 32;
+
+    let (incoming, record) = lit_from(cos_theta);
+    let patch = |direction: &Vector3| {
+        let direction = unit_vector(direction);
+        let band = ((direction.z() + 1.0) / 2.0 * BANDS as f64) as usize;
+        let phi = direction.y().atan2(direction.x()).rem_euclid(2.0 * PI);
+        let sector = (phi / (2.0 * PI) * SECTORS as f64) as usize;
+        band.min(BANDS - 1) * SECTORS + sector.min(SECTORS - 1)
+    };
+
+    random::seed(43);
+    let mut sampled = vec![(0.0, 0.0); BANDS * SECTORS];
+    for _ in 0..SAMPLES {
+        let mut attenuation = Color::black();
+        let mut scattered = Ray::new(Vector3::zero(), Vector3::zero(), 0.0);
+        if material.scatter(&incoming, &record, &mut attenuation, &mut scattered)
+            && !scattered.specular
+        {
+            let (fraction, weight) = &mut sampled[patch(&scattered.direction)];
+            *fraction += 1.0 / SAMPLES as f64;
+            *weight += attenuation.luminance() / SAMPLES as f64;
+        }
+    }
+
+    let mut integrated = vec![(0.0, 0.0); BANDS * SECTORS];
+    let (rows, columns) = (BANDS * CELLS, SECTORS * CELLS);
+    let solid_angle = (2.0 / rows as f64) * (2.0 * PI / columns as f64);
+    for row in 0..rows {
+        let z = -1.0 + 2.0 * (row as f64 + 0.5) / rows as f64;
+        let r = (1.0 - z * z).sqrt();
+        for column in 0..columns {
+            let phi = 2.0 * PI * (column as f64 + 0.5) / columns as f64;
+            let direction = Vector3::new(r * phi.cos(), r * phi.sin(), z);
+            let (pdf, value) = &mut integrated[patch(&direction)];
+            *pdf += material.pdf(&incoming, &record, &direction) * solid_angle;
+            *value += material.eval(&incoming, &record, &direction).luminance() * solid_angle;
+        }
+    }
+
+    for (i, (&(fraction, weight), &(pdf, value))) in sampled.iter().zip(&integrated).enumerate() {
+        assert!(
+            (fraction - pdf).abs() <= 0.003 + 0.03 * pdf,
+            "patch {}: scatter sent {} there, pdf integrates to {}",
+            i,
+            fraction,
+            pdf
+        );
+        assert!(
+            (weight - value).abs() <= 0.003 + 0.03 * value,
+            "patch {}: scatter carried {} there, eval integrates to {}",
+            i,
+            weight,
+            value
+        );
+    }
 }
 
 pub struct Lambertian {
-    pub albedo: Color,
+    pub albedo: Arc<dyn Texture>,
 }
 
 impl Lambertian {
     pub fn new(albedo: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture>) -> Self {
         Self { albedo }
     }
 }
@@ -28,26 +165,92 @@ impl Lambertian {
 impl Material for Lambertian {
     fn scatter(
         &self,
-        _ray: &Ray,
+        ray: &Ray,
         record: &HitRecord,
         attenuation: &mut Color,
         scattered: &mut Ray,
     ) -> bool {
         let scatter_direction = record.normal + ray::random_unit_vector();
-        *scattered = Ray::new(record.point, scatter_direction);
-        *attenuation = self.albedo;
+        *scattered = Ray::new(record.point, scatter_direction, ray.time);
+        *attenuation = self
+            .albedo
+            .value(record.u, record.v, &record.point, &record.normal);
 
         true
     }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        let albedo = self
+            .albedo
+            .value(record.u, record.v, &record.point, &record.normal);
+
+        self.pdf(ray, record, direction) * albedo
+    }
+
+    fn pdf(&self, _ray: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        // `scatter` offsets a random unit vector from the normal, which
+        // distributes directions by the cosine.
+        dot(&unit_vector(direction), &record.normal).max(0.0) / PI
+    }
+}
+
+/// A surface that gives off light evenly in every direction and reflects
+/// none. Only the front face glows unless it is two-sided.
+pub struct DiffuseLight {
+    pub emit: Arc<dyn Texture>,
+    pub two_sided: bool,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(emit)))
+    }
+
+    pub fn from_texture(emit: Arc<dyn Texture>) -> Self {
+        Self {
+            emit,
+            two_sided: false,
+        }
+    }
+
+    pub fn two_sided(mut self, two_sided: bool) -> Self {
+        self.two_sided = two_sided;
+        self
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _ray: &Ray,
+        _record: &HitRecord,
+        _attenuation: &mut Color,
+        _scattered: &mut Ray,
+    ) -> bool {
+        false
+    }
+
+    fn emitted(&self, _ray: &Ray, record: &HitRecord) -> Color {
+        if !record.front_face && !self.two_sided {
+            return Color::black();
+        }
+
+        self.emit
+            .value(record.u, record.v, &record.point, &record.normal)
+    }
 }
 
 pub struct Metal {
-    pub albedo: Color,
+    pub albedo: Arc<dyn Texture>,
     pub fuzz: f64,
 }
 
 impl Metal {
     pub fn new(albedo: Color, fuzz: f64) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)), fuzz)
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture>, fuzz: f64) -> Self {
         Self { albedo, fuzz }
     }
 }
@@ -64,27 +267,198 @@ impl Material for Metal {
         *scattered = Ray::new(
             record.point,
             reflected + self.fuzz * random_in_unit_sphere(),
+            ray.time,
         );
-        *attenuation = self.albedo;
+        *attenuation = self
+            .albedo
+            .value(record.u, record.v, &record.point, &record.normal);
 
         dot(&scattered.direction, &record.normal) > 0.0
     }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        let albedo = self
+            .albedo
+            .value(record.u, record.v, &record.point, &record.normal);
+
+        self.pdf(ray, record, direction) * albedo
+    }
+
+    /// `scatter` offsets the mirror direction by `fuzz` times a point drawn
+    /// uniformly from the cube [-1, 1]³ outside the unit ball, which is how
+    /// `random_in_unit_sphere` draws it. A direction's density is that
+    /// volume's density integrated along it, ∫ t² dt over the stretch of
+    /// the ray inside the volume.
+    fn pdf(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        let w = unit_vector(direction);
+        if self.fuzz <= 0.0 || dot(&w, &record.normal) <= 0.0 {
+            return 0.0;
+        }
+        let center = reflect(&unit_vector(&ray.direction), &record.normal);
+        let half = self.fuzz;
+
+        let (mut near, mut far) = (0.0f64, f64::INFINITY);
+        for axis in 0..3 {
+            if w[axis] == 0.0 {
+                if center[axis].abs() > half {
+                    return 0.0;
+                }
+                continue;
+            }
+            let a = (center[axis] - half) / w[axis];
+            let b = (center[axis] + half) / w[axis];
+            near = near.max(a.min(b));
+            far = far.min(a.max(b));
+        }
+        if far <= near {
+            return 0.0;
+        }
+        let in_cube = (far.powi(3) - near.powi(3)) / 3.0;
+
+        let b = dot(&w, &center);
+        let discriminant = b * b - (center.length_squared() - half * half);
+        let in_ball = if discriminant > 0.0 {
+            let root = discriminant.sqrt();
+            let (near, far) = ((b - root).max(0.0), (b + root).max(0.0));
+            (far.powi(3) - near.powi(3)) / 3.0
+        } else {
+            0.0
+        };
+
+        (in_cube - in_ball) / (half.powi(3) * (8.0 - 4.0 * PI / 3.0))
+    }
 }
 
-fn reflect(v: &Vector3, n: &Vector3) -> Vector3 {
+pub(crate) fn reflect(v: &Vector3, n: &Vector3) -> Vector3 {
     *v - 2.0 * dot(v, n) * *n
 }
 
+/// Clear or absorbing glass with a smooth surface. Light travelling inside
+/// is attenuated by the Beer-Lambert law, applied when the ray reaches the
+/// back face, so the colour deepens with the thickness of the glass.
 pub struct Dielectric {
     pub index_of_refraction: f64,
+    /// Absorption coefficient per unit length, for each channel.
+    pub absorption: Color,
+    /// Treats the surface as an infinitely thin sheet, like a window pane or
+    /// a soap bubble: rays pass straight through instead of refracting, and
+    /// there is no interior to absorb light.
+    pub thin_walled: bool,
+    /// How the index varies with wavelength. Only spectral rays see this;
+    /// RGB rays use `index_of_refraction`.
+    pub dispersion: Option<Dispersion>,
+    /// A coating on the outside of the surface. With an index of
+    /// refraction of one and thin walls, the film alone is a soap bubble.
+    pub thin_film: Option<ThinFilm>,
 }
 
 impl Dielectric {
     pub fn new(index_of_refraction: f64) -> Self {
         Self {
             index_of_refraction,
+            absorption: Color::black(),
+            thin_walled: false,
+            dispersion: None,
+            thin_film: None,
         }
     }
+
+    /// Glass whose index follows `dispersion`, with RGB rays using the index
+    /// at the sodium d-line (587.6 nm) as catalogues quote it.
+    pub fn dispersive(dispersion: Dispersion) -> Self {
+        Self {
+            dispersion: Some(dispersion),
+            ..Self::new(dispersion.index_at(587.6))
+        }
+    }
+
+    /// Absorbs so that white light comes out as `color` after travelling
+    /// `distance` through the glass.
+    pub fn absorption(mut self, color: Color, distance: f64) -> Self {
+        self.absorption = absorption_from_color(&color, distance);
+        self
+    }
+
+    pub fn thin_walled(mut self, thin_walled: bool) -> Self {
+        self.thin_walled = thin_walled;
+        self
+    }
+
+    pub fn thin_film(mut self, thin_film: ThinFilm) -> Self {
+        self.thin_film = Some(thin_film);
+        self
+    }
+
+    /// Reflectance of the coated interface, with the glass on the far side
+    /// for front faces and the film's outside medium (air) for back faces.
+    fn film_reflectance(
+        &self,
+        thin_film: &ThinFilm,
+        record: &HitRecord,
+        cos_theta: f64,
+        index_of_refraction: f64,
+    ) -> Color {
+        let (outside, substrate) = if record.front_face {
+            (1.0, index_of_refraction)
+        } else {
+            (index_of_refraction, 1.0)
+        };
+        let substrate = Color::new(substrate, substrate, substrate);
+
+        thin_film.reflectance(record, cos_theta, outside, &substrate, &Color::black())
+    }
+
+    fn scatter_thin(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        let unit_direction = unit_vector(&ray.direction);
+        let cos_theta = f64::min(dot(&-unit_direction, &record.normal), 1.0);
+        let mut rng = random::rng();
+
+        let Some(thin_film) = &self.thin_film else {
+            let direction = if rng.random_range(0.0..1.0)
+                < thin_sheet_reflectance(schlick(cos_theta, self.index_of_refraction))
+            {
+                reflect(&unit_direction, &record.normal)
+            } else {
+                unit_direction
+            };
+            *scattered = Ray::new(record.point, direction, ray.time);
+            return true;
+        };
+
+        // The coated face and the bare one combine incoherently, as the two
+        // faces of an uncoated sheet do in `thin_sheet_reflectance`.
+        let coated = thin_film.reflectance(
+            record,
+            cos_theta,
+            1.0,
+            &Color::new(
+                self.index_of_refraction,
+                self.index_of_refraction,
+                self.index_of_refraction,
+            ),
+            &Color::black(),
+        );
+        let bare = schlick(cos_theta, self.index_of_refraction);
+        let sheet = |r: f64| r + (1.0 - r) * (1.0 - r) * bare / (1.0 - r * bare);
+        let reflectance = Color::new(sheet(coated.r()), sheet(coated.g()), sheet(coated.b()));
+
+        let (reflects, weight) = choose_by_reflectance(&reflectance, &mut rng);
+        *attenuation = weight;
+        let direction = if reflects {
+            reflect(&unit_direction, &record.normal)
+        } else {
+            unit_direction
+        };
+        *scattered = Ray::new(record.point, direction, ray.time);
+
+        true
+    }
 }
 
 impl Material for Dielectric {
@@ -95,51 +469,199 @@ impl Material for Dielectric {
         attenuation: &mut Color,
         scattered: &mut Ray,
     ) -> bool {
-        *attenuation = Color::new(1.0, 1.0, 1.0);
-        let etai_over_etat: f64;
-        if record.front_face {
-            etai_over_etat = 1.0 / self.index_of_refraction;
-        } else {
-            etai_over_etat = self.index_of_refraction;
+        if self.thin_walled {
+            *attenuation = Color::white();
+            return self.scatter_thin(ray, record, attenuation, scattered);
         }
 
+        *attenuation = interior_transmittance(&self.absorption, ray, record);
+
+        // A dispersive interface bends each wavelength differently, so only
+        // the hero wavelength carries on.
+        let (index_of_refraction, wavelengths) = match (&self.dispersion, ray.wavelengths) {
+            (Some(dispersion), Some(wavelengths)) => (
+                dispersion.index_at(wavelengths.hero()),
+                Some(wavelengths.terminate_secondary()),
+            ),
+            _ => (self.index_of_refraction, ray.wavelengths),
+        };
+        let etai_over_etat = if record.front_face {
+            1.0 / index_of_refraction
+        } else {
+            index_of_refraction
+        };
+
         let unit_direction = unit_vector(&ray.direction);
         let cos_theta = f64::min(dot(&-unit_direction, &record.normal), 1.0);
         let sin_theta = (1.0f64 - cos_theta * cos_theta).sqrt();
         if etai_over_etat * sin_theta > 1.0 {
             let reflected = reflect(&unit_direction, &record.normal);
-            *scattered = Ray::new(record.point, reflected);
+            *scattered = Ray::new(record.point, reflected, ray.time).with_wavelengths(wavelengths);
+
+            return true;
+        }
+
+        let mut rng = random::rng();
+        if let Some(thin_film) = &self.thin_film {
+            let reflectance =
+                self.film_reflectance(thin_film, record, cos_theta, index_of_refraction);
+            let (reflects, weight) = choose_by_reflectance(&reflectance, &mut rng);
+            *attenuation = *attenuation * weight;
+            let direction = if reflects {
+                reflect(&unit_direction, &record.normal)
+            } else {
+                refract(&unit_direction, &record.normal, etai_over_etat)
+            };
+            *scattered = Ray::new(record.point, direction, ray.time).with_wavelengths(wavelengths);
 
             return true;
         }
 
         let reflect_prob = schlick(cos_theta, etai_over_etat);
-        let mut rng = rand::rng();
         if rng.random_range(0.0..1.0) < reflect_prob {
             let reflected = reflect(&unit_direction, &record.normal);
-            *scattered = Ray::new(record.point, reflected);
+            *scattered = Ray::new(record.point, reflected, ray.time).with_wavelengths(wavelengths);
 
             return true;
         }
 
         let refracted = refract(&unit_direction, &record.normal, etai_over_etat);
-        *scattered = Ray::new(record.point, refracted);
+        *scattered = Ray::new(record.point, refracted, ray.time).with_wavelengths(wavelengths);
 
         true
     }
 }
 
-fn refract(uv: &Vector3, n: &Vector3, etai_over_etat: f64) -> Vector3 {
-    let cos_theta = dot(&(-*uv), &n);
+/// Refractive index as a function of wavelength.
+#[derive(Copy, Clone)]
+pub enum Dispersion {
+    /// n = a + b / λ², with λ in micrometres.
+    Cauchy { a: f64, b: f64 },
+    /// n² = 1 + Σ bᵢ λ² / (λ² - cᵢ), with λ in micrometres and cᵢ in µm².
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Dispersion {
+    /// Schott N-BK7 crown glass.
+    pub fn bk7() -> Self {
+        Dispersion::Sellmeier {
+            b: [1.039_612_12, 0.231_792_344, 1.010_469_45],
+            c: [0.006_000_698_67, 0.020_017_914_4, 103.560_653],
+        }
+    }
+
+    /// Schott SF11 dense flint glass, which spreads colours far more than BK7.
+    pub fn dense_flint() -> Self {
+        Dispersion::Sellmeier {
+            b: [1.737_596_95, 0.313_747_346, 1.898_781_01],
+            c: [0.013_188_707, 0.062_306_814_2, 155.236_29],
+        }
+    }
+
+    /// Water at room temperature, for which Cauchy's two terms are enough.
+    pub fn water() -> Self {
+        Dispersion::Cauchy {
+            a: 1.324,
+            b: 0.003_06,
+        }
+    }
+
+    pub fn diamond() -> Self {
+        Dispersion::Sellmeier {
+            b: [0.3306, 4.3356, 0.0],
+            c: [0.030_625, 0.011_236, 0.0],
+        }
+    }
+
+    pub fn index_at(&self, wavelength_nm: f64) -> f64 {
+        let lambda = wavelength_nm / 1000.0;
+        let lambda2 = lambda * lambda;
+
+        match self {
+            Dispersion::Cauchy { a, b } => a + b / lambda2,
+            Dispersion::Sellmeier { b, c } => {
+                let sum: f64 = b
+                    .iter()
+                    .zip(c)
+                    .map(|(b, c)| b * lambda2 / (lambda2 - c))
+                    .sum();
+                (1.0 + sum).sqrt()
+            }
+        }
+    }
+}
+
+/// Beer-Lambert transmittance over the segment a ray has just travelled
+/// inside a dielectric. Every such segment ends on a back face, so the
+/// distance is simply the hit distance along the ray.
+pub(crate) fn interior_transmittance(absorption: &Color, ray: &Ray, record: &HitRecord) -> Color {
+    if record.front_face || absorption.is_black() {
+        return Color::white();
+    }
+
+    let distance = record.t * ray.direction.length();
+    Color::new(
+        (-absorption.r() * distance).exp(),
+        (-absorption.g() * distance).exp(),
+        (-absorption.b() * distance).exp(),
+    )
+}
+
+/// The absorption coefficient that leaves `color` of white light after
+/// `distance`.
+pub(crate) fn absorption_from_color(color: &Color, distance: f64) -> Color {
+    let coefficient = |channel: f64| -channel.clamp(1e-6, 1.0).ln() / distance;
+    Color::new(
+        coefficient(color.r()),
+        coefficient(color.g()),
+        coefficient(color.b()),
+    )
+}
+
+/// Total reflectance of a thin sheet with single-surface reflectance `r`,
+/// summing the light bounced back and forth between its two faces.
+pub(crate) fn thin_sheet_reflectance(r: f64) -> f64 {
+    2.0 * r / (1.0 + r)
+}
+
+pub(crate) fn refract(uv: &Vector3, n: &Vector3, etai_over_etat: f64) -> Vector3 {
+    let cos_theta = dot(&(-*uv), n);
     let r_out_parallel = etai_over_etat * (*uv + cos_theta * *n);
     let r_out_perp = -(1.0f64 - r_out_parallel.length_squared()).sqrt() * *n;
 
     r_out_parallel + r_out_perp
 }
 
-fn schlick(cosine: f64, refractive_index: f64) -> f64 {
+/// Picks reflection with probability of the average of a colored
+/// reflectance, returning whether it did and the weight that keeps each
+/// channel's reflected and transmitted shares unbiased.
+fn choose_by_reflectance(reflectance: &Color, rng: &mut impl Rng) -> (bool, Color) {
+    let probability = ((reflectance.r() + reflectance.g() + reflectance.b()) / 3.0).clamp(0.0, 1.0);
+    if rng.random_range(0.0..1.0) < probability {
+        (true, *reflectance / probability)
+    } else {
+        (false, (Color::white() - *reflectance) / (1.0 - probability))
+    }
+}
+
+pub(crate) fn schlick(cosine: f64, refractive_index: f64) -> f64 {
     let mut r0 = (1.0f64 - refractive_index) / (1.0 + refractive_index);
     r0 = r0 * r0;
 
     r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lambertian_and_metal_eval_matches_scatter() {
+        let lambertian = Lambertian::new(Color::new(0.8, 0.5, 0.2));
+        let metal = Metal::new(Color::new(0.9, 0.8, 0.6), 0.5);
+        for cos_theta in [0.9, 0.4] {
+            assert_eval_matches_scatter(&lambertian, cos_theta);
+            assert_eval_matches_scatter(&metal, cos_theta);
+        }
+    }
+}
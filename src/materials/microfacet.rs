@@ -0,0 +1,601 @@
+use crate::materials::material::{
+    absorption_from_color, interior_transmittance, reflect, refract, thin_sheet_reflectance,
+    Material,
+};
+use crate::materials::thin_film::ThinFilm;
+use crate::math::color::Color;
+use crate::math::onb::Onb;
+use crate::math::random;
+use crate::math::ray::Ray;
+use crate::math::vector3::{cross, dot, unit_vector, Vector3};
+use crate::objects::hittable::HitRecord;
+use rand::Rng;
+use std::f64::consts::PI;
+
+/// Below this alpha the distribution is treated as a perfect mirror, which
+/// avoids the numerical trouble of an almost-delta lobe.
+const SMOOTH_ALPHA: f64 = 1e-3;
+
+/// The GGX / Trowbridge-Reitz microfacet distribution with separate
+/// roughness along the tangent (`alpha_x`) and bitangent (`alpha_y`).
+/// All directions are in the local shading frame, with the normal along +Z.
+#[derive(Copy, Clone)]
+pub struct TrowbridgeReitz {
+    pub alpha_x: f64,
+    pub alpha_y: f64,
+}
+
+impl TrowbridgeReitz {
+    pub fn new(alpha_x: f64, alpha_y: f64) -> Self {
+        Self { alpha_x, alpha_y }
+    }
+
+    /// Maps perceptual roughness in [0, 1] to alpha, so that roughness
+    /// changes look roughly linear.
+    pub fn from_roughness(roughness_u: f64, roughness_v: f64) -> Self {
+        Self::new(
+            roughness_u.clamp(0.0, 1.0).powi(2),
+            roughness_v.clamp(0.0, 1.0).powi(2),
+        )
+    }
+
+    pub fn is_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < SMOOTH_ALPHA
+    }
+
+    pub fn d(&self, wm: &Vector3) -> f64 {
+        let cos2_theta = wm.z() * wm.z();
+        if cos2_theta <= 0.0 {
+            return 0.0;
+        }
+
+        let e = (wm.x() / self.alpha_x).powi(2) + (wm.y() / self.alpha_y).powi(2) + cos2_theta;
+        1.0 / (PI * self.alpha_x * self.alpha_y * e * e)
+    }
+
+    /// Smith's auxiliary function for the masking of direction `w`.
+    pub fn lambda(&self, w: &Vector3) -> f64 {
+        let cos2_theta = w.z() * w.z();
+        if cos2_theta <= 0.0 {
+            return f64::INFINITY;
+        }
+
+        let alpha2_tan2_theta =
+            ((self.alpha_x * w.x()).powi(2) + (self.alpha_y * w.y()).powi(2)) / cos2_theta;
+        ((1.0 + alpha2_tan2_theta).sqrt() - 1.0) / 2.0
+    }
+
+    pub fn g1(&self, w: &Vector3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Height-correlated Smith masking-shadowing.
+    pub fn g(&self, wo: &Vector3, wi: &Vector3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Density of the normals visible from `w`.
+    pub fn visible_d(&self, w: &Vector3, wm: &Vector3) -> f64 {
+        let cos_theta = w.z().abs();
+        if cos_theta == 0.0 {
+            return 0.0;
+        }
+
+        self.g1(w) / cos_theta * self.d(wm) * dot(w, wm).abs()
+    }
+
+    /// Samples a microfacet normal from the distribution of visible normals
+    /// (Heitz 2018), so that facets facing away from `w` are never chosen.
+    pub fn sample_wm(&self, w: &Vector3, u1: f64, u2: f64) -> Vector3 {
+        let flip = w.z() < 0.0;
+        let w = if flip { -*w } else { *w };
+
+        // Stretch to the hemisphere configuration.
+        let vh = unit_vector(&Vector3::new(
+            self.alpha_x * w.x(),
+            self.alpha_y * w.y(),
+            w.z(),
+        ));
+        let length_squared = vh.x() * vh.x() + vh.y() * vh.y();
+        let t1 = if length_squared > 0.0 {
+            Vector3::new(-vh.y(), vh.x(), 0.0) / length_squared.sqrt()
+        } else {
+            Vector3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = cross(&vh, &t1);
+
+        // Sample the projected area of the visible hemisphere.
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z());
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+        let nh = p1 * t1 + p2 * t2 + (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt() * vh;
+
+        // Unstretch back to the ellipsoid configuration.
+        let wm = unit_vector(&Vector3::new(
+            self.alpha_x * nh.x(),
+            self.alpha_y * nh.y(),
+            nh.z().max(1e-6),
+        ));
+
+        if flip {
+            -wm
+        } else {
+            wm
+        }
+    }
+}
+
+/// Samples a visible facet normal, or the macro normal for a smooth surface.
+pub(crate) fn sample_facet(distribution: &TrowbridgeReitz, wo: &Vector3) -> Vector3 {
+    if distribution.is_smooth() {
+        return Vector3::new(0.0, 0.0, 1.0);
+    }
+
+    let mut rng = random::rng();
+    distribution.sample_wm(wo, rng.random_range(0.0..1.0), rng.random_range(0.0..1.0))
+}
+
+/// Reflects `wo` off the facet `wm`, returning the direction and the G2 / G1
+/// weight that is left once the facet Fresnel has been applied.
+pub(crate) fn reflect_off_facet(
+    distribution: &TrowbridgeReitz,
+    wo: &Vector3,
+    wm: &Vector3,
+) -> Option<(Vector3, f64)> {
+    let wi = reflect(&-*wo, wm);
+    if wi.z() <= 0.0 {
+        return None;
+    }
+
+    let weight = if distribution.is_smooth() {
+        1.0
+    } else {
+        distribution.g(wo, &wi) / distribution.g1(wo)
+    };
+    Some((wi, weight))
+}
+
+/// Reflection off the facet halfway between two local directions, as
+/// `sample_facet` and `reflect_off_facet` produce it.
+pub(crate) struct FacetReflection {
+    pub wm: Vector3,
+    /// D G2 / (4 cos_o): the BRDF times the cosine, before Fresnel.
+    pub value: f64,
+    /// Density with which `wi` is sampled.
+    pub pdf: f64,
+}
+
+/// Evaluates reflection from `wo` into `wi`, or `None` below the surface
+/// and for a smooth surface, whose mirror lobe can't be evaluated.
+pub(crate) fn facet_reflection(
+    distribution: &TrowbridgeReitz,
+    wo: &Vector3,
+    wi: &Vector3,
+) -> Option<FacetReflection> {
+    if distribution.is_smooth() || wo.z() <= 0.0 || wi.z() <= 0.0 {
+        return None;
+    }
+
+    let wm = unit_vector(&(*wo + *wi));
+    Some(FacetReflection {
+        wm,
+        value: distribution.d(&wm) * distribution.g(wo, wi) / (4.0 * wo.z()),
+        pdf: distribution.visible_d(wo, &wm) / (4.0 * dot(wo, &wm)),
+    })
+}
+
+/// Unpolarized Fresnel reflectance of a dielectric interface. `eta` is the
+/// ratio of the index on the far side to the index on the incident side.
+pub fn fresnel_dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let (cos_theta_i, eta) = if cos_theta_i < 0.0 {
+        (-cos_theta_i, 1.0 / eta)
+    } else {
+        (cos_theta_i, eta)
+    };
+
+    let sin2_theta_t = (1.0 - cos_theta_i * cos_theta_i) / (eta * eta);
+    if sin2_theta_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+
+    let r_parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let r_perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+    (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.0
+}
+
+/// Unpolarized Fresnel reflectance of a conductor with complex index of
+/// refraction `eta + i k`, for one wavelength.
+pub fn fresnel_complex(cos_theta_i: f64, eta: f64, k: f64) -> f64 {
+    let cos2 = cos_theta_i.clamp(0.0, 1.0).powi(2);
+    let sin2 = 1.0 - cos2;
+    let eta2 = eta * eta;
+    let k2 = k * k;
+
+    let t0 = eta2 - k2 - sin2;
+    let a2_plus_b2 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+    let t1 = a2_plus_b2 + cos2;
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+    let t2 = 2.0 * cos_theta_i.abs() * a;
+    let rs = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+
+    (rp + rs) / 2.0
+}
+
+pub fn fresnel_conductor(cos_theta_i: f64, eta: &Color, k: &Color) -> Color {
+    Color::new(
+        fresnel_complex(cos_theta_i, eta.r(), k.r()),
+        fresnel_complex(cos_theta_i, eta.g(), k.g()),
+        fresnel_complex(cos_theta_i, eta.b(), k.b()),
+    )
+}
+
+/// A rough metal: GGX microfacets with Fresnel reflectance from a complex
+/// index of refraction. Unlike `Metal`, the lobe width follows from the
+/// surface statistics and no energy is gained at grazing angles.
+pub struct Conductor {
+    pub eta: Color,
+    pub k: Color,
+    pub distribution: TrowbridgeReitz,
+    /// An oxide or coating layer, as on heat-tinted steel or anodised
+    /// titanium.
+    pub thin_film: Option<ThinFilm>,
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, roughness: f64) -> Self {
+        Self::anisotropic(eta, k, roughness, roughness)
+    }
+
+    pub fn anisotropic(eta: Color, k: Color, roughness_u: f64, roughness_v: f64) -> Self {
+        Self {
+            eta,
+            k,
+            distribution: TrowbridgeReitz::from_roughness(roughness_u, roughness_v),
+            thin_film: None,
+        }
+    }
+
+    pub fn thin_film(mut self, thin_film: ThinFilm) -> Self {
+        self.thin_film = Some(thin_film);
+        self
+    }
+
+    fn fresnel(&self, record: &HitRecord, cos_theta: f64) -> Color {
+        match &self.thin_film {
+            Some(thin_film) => thin_film.reflectance(record, cos_theta, 1.0, &self.eta, &self.k),
+            None => fresnel_conductor(cos_theta, &self.eta, &self.k),
+        }
+    }
+
+    pub fn gold(roughness: f64) -> Self {
+        Self::new(
+            Color::new(0.143, 0.374, 1.442),
+            Color::new(3.983, 2.385, 1.603),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: f64) -> Self {
+        Self::new(
+            Color::new(0.200, 0.924, 1.102),
+            Color::new(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    pub fn aluminium(roughness: f64) -> Self {
+        Self::new(
+            Color::new(1.657, 0.880, 0.521),
+            Color::new(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+
+    pub fn silver(roughness: f64) -> Self {
+        Self::new(
+            Color::new(0.155, 0.117, 0.138),
+            Color::new(4.828, 3.122, 2.147),
+            roughness,
+        )
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        let frame = Onb::new(&record.normal);
+        let wo = frame.world_to_local(&-unit_vector(&ray.direction));
+        if wo.z() <= 0.0 {
+            return false;
+        }
+
+        if self.distribution.is_smooth() {
+            let wi = Vector3::new(-wo.x(), -wo.y(), wo.z());
+            *scattered = Ray::new(record.point, frame.local_vector(&wi), ray.time);
+            *attenuation = self.fresnel(record, wo.z());
+            return true;
+        }
+
+        let mut rng = random::rng();
+        let wm = self.distribution.sample_wm(
+            &wo,
+            rng.random_range(0.0..1.0),
+            rng.random_range(0.0..1.0),
+        );
+        let wi = reflect(&-wo, &wm);
+        if wi.z() <= 0.0 {
+            return false;
+        }
+
+        // With visible-normal sampling, f * cos / pdf reduces to F * G2 / G1.
+        let weight = self.distribution.g(&wo, &wi) / self.distribution.g1(&wo);
+        *scattered = Ray::new(record.point, frame.local_vector(&wi), ray.time);
+        *attenuation = weight * self.fresnel(record, dot(&wo, &wm));
+
+        true
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        if self.distribution.is_smooth() {
+            return Color::black();
+        }
+
+        let frame = Onb::new(&record.normal);
+        let wo = frame.world_to_local(&-unit_vector(&ray.direction));
+        let wi = frame.world_to_local(&unit_vector(direction));
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Color::black();
+        }
+
+        let wm = unit_vector(&(wo + wi));
+        let fresnel = self.fresnel(record, dot(&wo, &wm));
+        let brdf_cos = self.distribution.d(&wm) * self.distribution.g(&wo, &wi) / (4.0 * wo.z());
+
+        brdf_cos * fresnel
+    }
+
+    fn pdf(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        if self.distribution.is_smooth() {
+            return 0.0;
+        }
+
+        let frame = Onb::new(&record.normal);
+        let wo = frame.world_to_local(&-unit_vector(&ray.direction));
+        let wi = frame.world_to_local(&unit_vector(direction));
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+
+        // Visible normals, mapped through the reflection's Jacobian.
+        let wm = unit_vector(&(wo + wi));
+        self.distribution.visible_d(&wo, &wm) / (4.0 * dot(&wo, &wm))
+    }
+}
+
+/// Rough glass: GGX microfacets that both reflect and transmit, choosing
+/// between the two by the Fresnel term of the sampled facet. Absorption and
+/// the thin-walled option work as they do for `Dielectric`; a thin rough
+/// sheet transmits the mirror image of its glossy reflection, like frosted
+/// window glass.
+pub struct RoughDielectric {
+    pub index_of_refraction: f64,
+    pub distribution: TrowbridgeReitz,
+    pub absorption: Color,
+    pub thin_walled: bool,
+}
+
+impl RoughDielectric {
+    pub fn new(index_of_refraction: f64, roughness: f64) -> Self {
+        Self::anisotropic(index_of_refraction, roughness, roughness)
+    }
+
+    pub fn anisotropic(index_of_refraction: f64, roughness_u: f64, roughness_v: f64) -> Self {
+        Self {
+            index_of_refraction,
+            distribution: TrowbridgeReitz::from_roughness(roughness_u, roughness_v),
+            absorption: Color::black(),
+            thin_walled: false,
+        }
+    }
+
+    pub fn absorption(mut self, color: Color, distance: f64) -> Self {
+        self.absorption = absorption_from_color(&color, distance);
+        self
+    }
+
+    pub fn thin_walled(mut self, thin_walled: bool) -> Self {
+        self.thin_walled = thin_walled;
+        self
+    }
+
+    /// Samples an outgoing local direction for local incident direction `wo`
+    /// (with `wo.z > 0`), returning it with its throughput weight.
+    pub(crate) fn sample_local(&self, wo: &Vector3, eta: f64) -> Option<(Vector3, f64)> {
+        let mut rng = random::rng();
+        let wm = if self.distribution.is_smooth() {
+            Vector3::new(0.0, 0.0, 1.0)
+        } else {
+            self.distribution
+                .sample_wm(wo, rng.random_range(0.0..1.0), rng.random_range(0.0..1.0))
+        };
+
+        let cos_o = dot(wo, &wm);
+        let reflectance = fresnel_dielectric(cos_o, eta);
+        let wi = if self.thin_walled {
+            let wi = reflect(&-*wo, &wm);
+            if wi.z() <= 0.0 {
+                return None;
+            }
+            if rng.random_range(0.0..1.0) < thin_sheet_reflectance(reflectance) {
+                wi
+            } else {
+                Vector3::new(wi.x(), wi.y(), -wi.z())
+            }
+        } else if rng.random_range(0.0..1.0) < reflectance {
+            let wi = reflect(&-*wo, &wm);
+            if wi.z() <= 0.0 {
+                return None;
+            }
+            wi
+        } else {
+            let wi = refract(&-*wo, &wm, 1.0 / eta);
+            if wi.z() >= 0.0 {
+                return None;
+            }
+            wi
+        };
+
+        if self.distribution.is_smooth() {
+            return Some((wi, 1.0));
+        }
+
+        // The Fresnel factor cancels against the reflect/refract choice.
+        let weight = self.distribution.g(wo, &wi) / self.distribution.g1(wo);
+        Some((wi, weight))
+    }
+
+    /// Density with which `sample_local` picks `wi`, zero for a smooth
+    /// surface, whose lobes can't be evaluated.
+    pub(crate) fn pdf_local(&self, wo: &Vector3, wi: &Vector3, eta: f64) -> f64 {
+        if self.distribution.is_smooth() || wo.z() <= 0.0 || wi.z() == 0.0 {
+            return 0.0;
+        }
+
+        let reflection = |wi: &Vector3| {
+            let wm = unit_vector(&(*wo + *wi));
+            let pdf = self.distribution.visible_d(wo, &wm) / (4.0 * dot(wo, &wm));
+            (fresnel_dielectric(dot(wo, &wm), eta), pdf)
+        };
+
+        if self.thin_walled {
+            // Transmission through a sheet mirrors its reflection.
+            let (reflectance, pdf) = reflection(&Vector3::new(wi.x(), wi.y(), wi.z().abs()));
+            let reflectance = thin_sheet_reflectance(reflectance);
+            return if wi.z() > 0.0 {
+                reflectance * pdf
+            } else {
+                (1.0 - reflectance) * pdf
+            };
+        }
+
+        if wi.z() > 0.0 {
+            let (reflectance, pdf) = reflection(wi);
+            return reflectance * pdf;
+        }
+
+        // The facet that refracts `wo` into `wi` is along the generalized
+        // half vector, turned to face the outside.
+        let half = eta * *wi + *wo;
+        if half.length_squared() == 0.0 {
+            return 0.0;
+        }
+        let wm = unit_vector(&half);
+        let wm = if wm.z() < 0.0 { -wm } else { wm };
+        let (cos_o, cos_i) = (dot(wo, &wm), dot(wi, &wm));
+        if cos_o <= 0.0 || cos_i >= 0.0 {
+            return 0.0;
+        }
+
+        let transmittance = 1.0 - fresnel_dielectric(cos_o, eta);
+        let dwm_dwi = -cos_i / (cos_i + cos_o / eta).powi(2);
+        transmittance * self.distribution.visible_d(wo, &wm) * dwm_dwi
+    }
+
+    /// `sample_local`'s weight times its density, which with the Fresnel
+    /// term cancelled is the BSDF times the cosine.
+    pub(crate) fn eval_local(&self, wo: &Vector3, wi: &Vector3, eta: f64) -> f64 {
+        let pdf = self.pdf_local(wo, wi, eta);
+        if pdf == 0.0 {
+            return 0.0;
+        }
+
+        pdf * self.distribution.g(wo, wi) / self.distribution.g1(wo)
+    }
+
+    fn eta(&self, record: &HitRecord) -> f64 {
+        if record.front_face || self.thin_walled {
+            self.index_of_refraction
+        } else {
+            1.0 / self.index_of_refraction
+        }
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        let frame = Onb::new(&record.normal);
+        let wo = frame.world_to_local(&-unit_vector(&ray.direction));
+        let Some((wi, weight)) = self.sample_local(&wo, self.eta(record)) else {
+            return false;
+        };
+
+        let transmittance = if self.thin_walled {
+            Color::white()
+        } else {
+            interior_transmittance(&self.absorption, ray, record)
+        };
+        *scattered = Ray::new(record.point, frame.local_vector(&wi), ray.time);
+        *attenuation = weight * transmittance;
+
+        true
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        let frame = Onb::new(&record.normal);
+        let wo = frame.world_to_local(&-unit_vector(&ray.direction));
+        let wi = frame.world_to_local(&unit_vector(direction));
+        let value = self.eval_local(&wo, &wi, self.eta(record));
+        if value == 0.0 {
+            return Color::black();
+        }
+
+        let transmittance = if self.thin_walled {
+            Color::white()
+        } else {
+            interior_transmittance(&self.absorption, ray, record)
+        };
+        value * transmittance
+    }
+
+    fn pdf(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        let frame = Onb::new(&record.normal);
+        let wo = frame.world_to_local(&-unit_vector(&ray.direction));
+        let wi = frame.world_to_local(&unit_vector(direction));
+
+        self.pdf_local(&wo, &wi, self.eta(record))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::material::assert_eval_matches_scatter;
+
+    #[test]
+    fn rough_dielectric_eval_matches_scatter() {
+        for thin_walled in [false, true] {
+            let glass = RoughDielectric::new(1.5, 0.4).thin_walled(thin_walled);
+            for cos_theta in [0.9, 0.4] {
+                assert_eval_matches_scatter(&glass, cos_theta);
+            }
+        }
+    }
+}
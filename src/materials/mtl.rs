@@ -0,0 +1,207 @@
+//! Wavefront MTL import with the common PBR extension statements (`Pr`,
+//! `Pm`, `Ps`, `Pc`, `Pcr` and their `map_` forms). Classic statements are
+//! mapped onto `Principled` where there is an obvious counterpart; the rest
+//! (`Ka`, `Ks`, `illum`, ...) are ignored.
+
+use crate::images::error::ImageError;
+use crate::materials::principled::{Parameter, Principled};
+use crate::math::color::Color;
+use crate::textures::image_texture::TextureCache;
+use crate::textures::texture::{SolidColor, Texture};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub enum MtlError {
+    Io(io::Error),
+    /// A texture map named by the library could not be loaded.
+    Image(ImageError),
+    /// A statement had missing or unreadable arguments.
+    Parse {
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for MtlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MtlError::Io(error) => write!(f, "could not read material library: {}", error),
+            MtlError::Image(error) => write!(f, "could not load texture map: {}", error),
+            MtlError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for MtlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MtlError::Io(error) => Some(error),
+            MtlError::Image(error) => Some(error),
+            MtlError::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for MtlError {
+    fn from(error: io::Error) -> Self {
+        MtlError::Io(error)
+    }
+}
+
+impl From<ImageError> for MtlError {
+    fn from(error: ImageError) -> Self {
+        MtlError::Image(error)
+    }
+}
+
+#[derive(Default)]
+struct Entry {
+    base_color: Option<Arc<dyn Texture>>,
+    diffuse: Option<Color>,
+    metallic: Option<Parameter>,
+    roughness: Option<Parameter>,
+    shininess: Option<f64>,
+    sheen: Option<Parameter>,
+    clearcoat: Option<f64>,
+    clearcoat_roughness: Option<f64>,
+    dissolve: Option<f64>,
+    ior: Option<f64>,
+}
+
+impl Entry {
+    fn build(self) -> Principled {
+        let base_color = self.base_color.unwrap_or_else(|| {
+            Arc::new(SolidColor::new(
+                self.diffuse.unwrap_or(Color::new(0.8, 0.8, 0.8)),
+            ))
+        });
+
+        // Without an explicit `Pr`, derive roughness from the Phong exponent
+        // through the usual Beckmann correspondence alpha^2 = 2 / (Ns + 2).
+        let roughness = self.roughness.unwrap_or_else(|| match self.shininess {
+            Some(ns) => (2.0 / (ns.max(0.0) + 2.0)).sqrt().sqrt().into(),
+            None => 0.5.into(),
+        });
+
+        let mut principled = Principled::from_texture(base_color)
+            .roughness(roughness)
+            .transmission(1.0 - self.dissolve.unwrap_or(1.0).clamp(0.0, 1.0))
+            .ior(self.ior.unwrap_or(1.5));
+        if let Some(metallic) = self.metallic {
+            principled = principled.metallic(metallic);
+        }
+        if let Some(sheen) = self.sheen {
+            principled = principled.sheen(sheen);
+        }
+        if let Some(clearcoat) = self.clearcoat {
+            principled = principled.clearcoat(clearcoat);
+        }
+        if let Some(clearcoat_roughness) = self.clearcoat_roughness {
+            principled = principled.clearcoat_gloss(1.0 - clearcoat_roughness);
+        }
+
+        principled
+    }
+}
+
+/// Reads every material in an MTL file. Texture paths are resolved relative
+/// to the file and loaded through `textures`, so libraries sharing maps
+/// share the decoded images.
+pub fn load_mtl(
+    path: &Path,
+    textures: &mut TextureCache,
+) -> Result<HashMap<String, Arc<Principled>>, MtlError> {
+    let source = fs::read_to_string(path)?;
+    let directory = path.parent().unwrap_or(Path::new(""));
+
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, Entry)> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let error = |message: &str| MtlError::Parse {
+            line: line_number,
+            message: message.to_string(),
+        };
+
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let arguments: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            let name = arguments.join(" ");
+            if name.is_empty() {
+                return Err(error("newmtl needs a name"));
+            }
+            if let Some((name, entry)) = current.replace((name, Entry::default())) {
+                materials.insert(name, Arc::new(entry.build()));
+            }
+            continue;
+        }
+
+        let Some((_, entry)) = current.as_mut() else {
+            return Err(error("statement before the first newmtl"));
+        };
+
+        let number = |i: usize| -> Result<f64, MtlError> {
+            arguments
+                .get(i)
+                .and_then(|text| text.parse().ok())
+                .ok_or_else(|| error(&format!("{} needs a number", keyword)))
+        };
+        // Map statements may carry options before the file name; only the
+        // file name itself is used.
+        let mut texture = |data: bool| -> Result<Arc<dyn Texture>, MtlError> {
+            let file = arguments
+                .last()
+                .ok_or_else(|| error(&format!("{} needs a file name", keyword)))?;
+            let path = directory.join(file);
+            Ok(if data {
+                textures.load_data(path)?
+            } else {
+                textures.load(path)?
+            })
+        };
+
+        match keyword {
+            "Kd" => {
+                // A single value means a grey of that intensity.
+                let r = number(0)?;
+                let color = if arguments.len() >= 3 {
+                    Color::new(r, number(1)?, number(2)?)
+                } else {
+                    Color::new(r, r, r)
+                };
+                entry.diffuse = Some(color);
+            }
+            "map_Kd" => entry.base_color = Some(texture(false)?),
+            "Pm" => entry.metallic = Some(number(0)?.into()),
+            "map_Pm" => entry.metallic = Some(texture(true)?.into()),
+            "Pr" => entry.roughness = Some(number(0)?.into()),
+            "map_Pr" => entry.roughness = Some(texture(true)?.into()),
+            "Ps" => entry.sheen = Some(number(0)?.into()),
+            "map_Ps" => entry.sheen = Some(texture(true)?.into()),
+            "Pc" => entry.clearcoat = Some(number(0)?),
+            "Pcr" => entry.clearcoat_roughness = Some(number(0)?),
+            "Ns" => entry.shininess = Some(number(0)?),
+            "Ni" => entry.ior = Some(number(0)?),
+            "d" => entry.dissolve = Some(number(0)?),
+            "Tr" => entry.dissolve = Some(1.0 - number(0)?),
+            _ => {}
+        }
+    }
+
+    if let Some((name, entry)) = current {
+        materials.insert(name, Arc::new(entry.build()));
+    }
+
+    Ok(materials)
+}
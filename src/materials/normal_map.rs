@@ -0,0 +1,283 @@
+//! Shading-normal perturbation for any material. Both wrappers replace
+//! `HitRecord::normal` before handing the hit to the wrapped material and
+//! leave `geometric_normal` untouched, which is what the safeguards against
+//! shading normals turned below the surface are measured against.
+
+use crate::materials::material::Material;
+use crate::math::color::Color;
+use crate::math::onb::Onb;
+use crate::math::ray::Ray;
+use crate::math::vector3::{cross, dot, unit_vector, Vector3};
+use crate::objects::hittable::HitRecord;
+use crate::textures::texture::Texture;
+use std::sync::Arc;
+
+/// Smallest cosine allowed between the geometric normal and the mirror
+/// direction about the shading normal.
+const MIN_GRAZING_COS: f64 = 0.01;
+
+/// Applies a tangent-space normal map: red, green and blue encode the
+/// tangent, bitangent and normal components mapped from [-1, 1] to [0, 1].
+/// Image maps should be loaded with `TextureCache::load_data` so they aren't
+/// read as sRGB.
+pub struct NormalMap {
+    material: Arc<dyn Material>,
+    map: Arc<dyn Texture>,
+    strength: f64,
+}
+
+impl NormalMap {
+    pub fn new(material: Arc<dyn Material>, map: Arc<dyn Texture>) -> Self {
+        Self {
+            material,
+            map,
+            strength: 1.0,
+        }
+    }
+
+    /// Scales the tangential part of the mapped normals; zero flattens the
+    /// map away entirely.
+    pub fn strength(mut self, strength: f64) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    fn shading_normal(&self, record: &HitRecord) -> Vector3 {
+        let (tangent, bitangent) = tangent_frame(record);
+        let texel = self
+            .map
+            .value(record.u, record.v, &record.point, &record.geometric_normal);
+        let x = self.strength * (2.0 * texel.r() - 1.0);
+        let y = self.strength * (2.0 * texel.g() - 1.0);
+        let z = (2.0 * texel.b() - 1.0).max(0.0);
+
+        let normal = x * tangent + y * bitangent + z * record.normal;
+        if normal.length_squared() == 0.0 {
+            return record.normal;
+        }
+        unit_vector(&normal)
+    }
+}
+
+impl Material for NormalMap {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        let normal = self.shading_normal(record);
+        scatter_shaded(&*self.material, normal, ray, record, attenuation, scattered)
+    }
+
+    fn emitted(&self, ray: &Ray, record: &HitRecord) -> Color {
+        self.material.emitted(ray, record)
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        let normal = self.shading_normal(record);
+        eval_shaded(&*self.material, normal, ray, record, direction)
+    }
+
+    fn pdf(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        let normal = self.shading_normal(record);
+        pdf_shaded(&*self.material, normal, ray, record, direction)
+    }
+}
+
+/// Bump mapping from a scalar height texture (its red channel), offset along
+/// the normal by `scale` world units per unit of height. Works with solid
+/// textures as well as UV-mapped ones.
+pub struct BumpMap {
+    material: Arc<dyn Material>,
+    height: Arc<dyn Texture>,
+    scale: f64,
+}
+
+impl BumpMap {
+    pub fn new(material: Arc<dyn Material>, height: Arc<dyn Texture>, scale: f64) -> Self {
+        Self {
+            material,
+            height,
+            scale,
+        }
+    }
+
+    fn shading_normal(&self, record: &HitRecord) -> Vector3 {
+        // Without surface derivatives, step along an arbitrary tangent frame
+        // and leave the texture coordinates alone.
+        let has_uv = record.dpdu.length_squared() > 0.0 && record.dpdv.length_squared() > 0.0;
+        let (dpdu, dpdv) = if has_uv {
+            (record.dpdu, record.dpdv)
+        } else {
+            tangent_frame(record)
+        };
+        let uv_step = if has_uv { 1.0 } else { 0.0 };
+
+        let n = record.normal;
+        let height = |u: f64, v: f64, p: Vector3| self.scale * self.height.value(u, v, &p, &n).r();
+
+        let delta = 0.0005;
+        let base = height(record.u, record.v, record.point);
+        let du = height(
+            record.u + uv_step * delta,
+            record.v,
+            record.point + delta * dpdu,
+        );
+        let dv = height(
+            record.u,
+            record.v + uv_step * delta,
+            record.point + delta * dpdv,
+        );
+
+        // Derivatives of the displaced surface p + h n, neglecting the
+        // change in n itself.
+        let displaced_u = dpdu + ((du - base) / delta) * n;
+        let displaced_v = dpdv + ((dv - base) / delta) * n;
+        let normal = cross(&displaced_u, &displaced_v);
+        if normal.length_squared() == 0.0 {
+            return n;
+        }
+
+        let normal = unit_vector(&normal);
+        if dot(&normal, &n) < 0.0 {
+            -normal
+        } else {
+            normal
+        }
+    }
+}
+
+impl Material for BumpMap {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        let normal = self.shading_normal(record);
+        scatter_shaded(&*self.material, normal, ray, record, attenuation, scattered)
+    }
+
+    fn emitted(&self, ray: &Ray, record: &HitRecord) -> Color {
+        self.material.emitted(ray, record)
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        let normal = self.shading_normal(record);
+        eval_shaded(&*self.material, normal, ray, record, direction)
+    }
+
+    fn pdf(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        let normal = self.shading_normal(record);
+        pdf_shaded(&*self.material, normal, ray, record, direction)
+    }
+}
+
+/// Unit tangent and bitangent around `record.normal`: the tangent follows
+/// dp/du where the surface has one, otherwise a stable arbitrary basis, and
+/// the bitangent is flipped to agree with dp/dv so mirrored UVs and back
+/// faces keep the map's handedness.
+fn tangent_frame(record: &HitRecord) -> (Vector3, Vector3) {
+    let n = record.normal;
+    let projected = record.dpdu - dot(&record.dpdu, &n) * n;
+    let tangent = if projected.length_squared() > 1e-12 {
+        unit_vector(&projected)
+    } else {
+        Onb::new(&n).u
+    };
+
+    let bitangent = cross(&n, &tangent);
+    if dot(&bitangent, &record.dpdv) < 0.0 {
+        (tangent, -bitangent)
+    } else {
+        (tangent, bitangent)
+    }
+}
+
+/// Bends a shading normal until it is usable from the viewing direction
+/// `wo`: first back into the geometric hemisphere, then far enough towards
+/// `wo` that the mirror direction stays just above the surface.
+fn adapt_shading_normal(shading: &Vector3, geometric: &Vector3, wo: &Vector3) -> Vector3 {
+    let mut normal = *shading;
+    let below = dot(&normal, geometric);
+    if below < 0.0 {
+        normal = unit_vector(&(normal - 2.0 * below * *geometric));
+    }
+
+    let mirror = 2.0 * dot(wo, &normal) * normal - *wo;
+    if dot(&mirror, geometric) >= MIN_GRAZING_COS {
+        return normal;
+    }
+
+    let tangential = mirror - dot(&mirror, geometric) * *geometric;
+    if tangential.length_squared() == 0.0 {
+        return *geometric;
+    }
+    let sin = (1.0 - MIN_GRAZING_COS * MIN_GRAZING_COS).sqrt();
+    let mirror = sin * unit_vector(&tangential) + MIN_GRAZING_COS * *geometric;
+
+    unit_vector(&(*wo + mirror))
+}
+
+/// `record` with its shading normal replaced by `normal`, adapted to the
+/// viewing direction.
+fn shaded_record(normal: Vector3, ray: &Ray, record: &HitRecord) -> HitRecord {
+    let wo = -unit_vector(&ray.direction);
+
+    let mut shaded = record.clone();
+    shaded.normal = adapt_shading_normal(&normal, &record.geometric_normal, &wo);
+    shaded
+}
+
+/// A direction on different sides of the shading and geometric surfaces
+/// would leak light through the object (or darken it from within), so
+/// such directions are dropped.
+fn same_side(direction: &Vector3, shaded: &HitRecord) -> bool {
+    (dot(direction, &shaded.normal) > 0.0) == (dot(direction, &shaded.geometric_normal) > 0.0)
+}
+
+fn scatter_shaded(
+    material: &dyn Material,
+    normal: Vector3,
+    ray: &Ray,
+    record: &HitRecord,
+    attenuation: &mut Color,
+    scattered: &mut Ray,
+) -> bool {
+    let shaded = shaded_record(normal, ray, record);
+    material.scatter(ray, &shaded, attenuation, scattered)
+        && same_side(&scattered.direction, &shaded)
+}
+
+fn eval_shaded(
+    material: &dyn Material,
+    normal: Vector3,
+    ray: &Ray,
+    record: &HitRecord,
+    direction: &Vector3,
+) -> Color {
+    let shaded = shaded_record(normal, ray, record);
+    if !same_side(direction, &shaded) {
+        return Color::black();
+    }
+
+    material.eval(ray, &shaded, direction)
+}
+
+fn pdf_shaded(
+    material: &dyn Material,
+    normal: Vector3,
+    ray: &Ray,
+    record: &HitRecord,
+    direction: &Vector3,
+) -> f64 {
+    let shaded = shaded_record(normal, ray, record);
+    if !same_side(direction, &shaded) {
+        return 0.0;
+    }
+
+    material.pdf(ray, &shaded, direction)
+}
@@ -0,0 +1,133 @@
+use crate::materials::material::Material;
+use crate::math::color::Color;
+use crate::math::onb::Onb;
+use crate::math::random;
+use crate::math::ray::{random_unit_vector, Ray};
+use crate::math::vector3::{dot, unit_vector, Vector3};
+use crate::objects::hittable::HitRecord;
+use crate::textures::texture::{SolidColor, Texture};
+use rand::Rng;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// Scatters uniformly in all directions. Used inside participating media,
+/// where the hit normal has no meaning.
+pub struct Isotropic {
+    pub albedo: Arc<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture>) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        *scattered = Ray::new(record.point, random_unit_vector(), ray.time);
+        *attenuation = self
+            .albedo
+            .value(record.u, record.v, &record.point, &record.normal);
+
+        true
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        let albedo = self
+            .albedo
+            .value(record.u, record.v, &record.point, &record.normal);
+
+        self.pdf(ray, record, direction) * albedo
+    }
+
+    fn pdf(&self, _ray: &Ray, _record: &HitRecord, _direction: &Vector3) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+}
+
+/// Henyey-Greenstein phase function. `g` in (-1, 1) is the mean cosine of
+/// the scattering angle: positive values scatter forward (haze, clouds),
+/// negative values backward, and zero is isotropic.
+#[derive(Clone)]
+pub struct HenyeyGreenstein {
+    pub albedo: Arc<dyn Texture>,
+    pub g: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Color, g: f64) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)), g)
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture>, g: f64) -> Self {
+        Self {
+            albedo,
+            g: g.clamp(-0.999, 0.999),
+        }
+    }
+
+    /// Density over the sphere of scattering at an angle with cosine `cos_theta`
+    /// to the direction of travel.
+    pub fn phase(&self, cos_theta: f64) -> f64 {
+        let denominator = 1.0 + self.g * self.g - 2.0 * self.g * cos_theta;
+
+        (1.0 - self.g * self.g) / (4.0 * PI * denominator * denominator.sqrt())
+    }
+
+    fn sample_cos_theta(&self, xi: f64) -> f64 {
+        let g = self.g;
+        if g.abs() < 1e-3 {
+            return 1.0 - 2.0 * xi;
+        }
+
+        let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+        ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        let mut rng = random::rng();
+        let cos_theta = self.sample_cos_theta(rng.random_range(0.0..1.0));
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * rng.random_range(0.0..1.0);
+
+        let frame = Onb::new(&ray.direction);
+        let direction = frame.local(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+
+        *scattered = Ray::new(record.point, direction, ray.time);
+        *attenuation = self
+            .albedo
+            .value(record.u, record.v, &record.point, &record.normal);
+
+        true
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        let albedo = self
+            .albedo
+            .value(record.u, record.v, &record.point, &record.normal);
+
+        self.pdf(ray, record, direction) * albedo
+    }
+
+    fn pdf(&self, ray: &Ray, _record: &HitRecord, direction: &Vector3) -> f64 {
+        self.phase(dot(&unit_vector(&ray.direction), &unit_vector(direction)))
+    }
+}
@@ -0,0 +1,506 @@
+use crate::materials::material::Material;
+use crate::materials::microfacet::{
+    facet_reflection, reflect_off_facet, sample_facet, RoughDielectric, TrowbridgeReitz,
+};
+use crate::math::color::Color;
+use crate::math::onb::Onb;
+use crate::math::random;
+use crate::math::ray::Ray;
+use crate::math::vector3::{dot, unit_vector, Vector3};
+use crate::objects::hittable::HitRecord;
+use crate::textures::texture::{SolidColor, Texture};
+use rand::Rng;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// A scalar material parameter, either constant or read from the red
+/// channel of a texture.
+#[derive(Clone)]
+pub enum Parameter {
+    Constant(f64),
+    Texture(Arc<dyn Texture>),
+}
+
+impl Parameter {
+    pub(crate) fn value(&self, record: &HitRecord) -> f64 {
+        match self {
+            Parameter::Constant(value) => *value,
+            Parameter::Texture(texture) => texture
+                .value(record.u, record.v, &record.point, &record.normal)
+                .r(),
+        }
+    }
+}
+
+impl From<f64> for Parameter {
+    fn from(value: f64) -> Self {
+        Parameter::Constant(value)
+    }
+}
+
+impl From<Arc<dyn Texture>> for Parameter {
+    fn from(texture: Arc<dyn Texture>) -> Self {
+        Parameter::Texture(texture)
+    }
+}
+
+/// All parameters evaluated at one hit point.
+struct Evaluated {
+    base_color: Color,
+    metallic: f64,
+    roughness: f64,
+    specular: f64,
+    specular_tint: f64,
+    sheen: f64,
+    sheen_tint: f64,
+    clearcoat: f64,
+    clearcoat_gloss: f64,
+    transmission: f64,
+}
+
+/// An uber material following the Disney principled BSDF conventions
+/// (Burley 2012, 2015). Every parameter lies in [0, 1] except `ior`.
+///
+/// Lobes are chosen stochastically so that energy is conserved by
+/// construction: a clearcoat on top, then a metallic, a glass-like
+/// transmissive or an opaque dielectric base. The opaque base splits
+/// between a GGX specular lobe and a Burley diffuse lobe with sheen. The
+/// clearcoat uses GGX rather than GTR1 for its lobe shape.
+pub struct Principled {
+    base_color: Arc<dyn Texture>,
+    metallic: Parameter,
+    roughness: Parameter,
+    specular: Parameter,
+    specular_tint: Parameter,
+    sheen: Parameter,
+    sheen_tint: Parameter,
+    clearcoat: Parameter,
+    clearcoat_gloss: Parameter,
+    transmission: Parameter,
+    ior: f64,
+}
+
+impl Principled {
+    pub fn new(base_color: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(base_color)))
+    }
+
+    pub fn from_texture(base_color: Arc<dyn Texture>) -> Self {
+        Self {
+            base_color,
+            metallic: 0.0.into(),
+            roughness: 0.5.into(),
+            specular: 0.5.into(),
+            specular_tint: 0.0.into(),
+            sheen: 0.0.into(),
+            sheen_tint: 0.5.into(),
+            clearcoat: 0.0.into(),
+            clearcoat_gloss: 1.0.into(),
+            transmission: 0.0.into(),
+            ior: 1.5,
+        }
+    }
+
+    pub fn metallic(mut self, metallic: impl Into<Parameter>) -> Self {
+        self.metallic = metallic.into();
+        self
+    }
+
+    pub fn roughness(mut self, roughness: impl Into<Parameter>) -> Self {
+        self.roughness = roughness.into();
+        self
+    }
+
+    pub fn specular(mut self, specular: impl Into<Parameter>) -> Self {
+        self.specular = specular.into();
+        self
+    }
+
+    pub fn specular_tint(mut self, specular_tint: impl Into<Parameter>) -> Self {
+        self.specular_tint = specular_tint.into();
+        self
+    }
+
+    pub fn sheen(mut self, sheen: impl Into<Parameter>) -> Self {
+        self.sheen = sheen.into();
+        self
+    }
+
+    pub fn sheen_tint(mut self, sheen_tint: impl Into<Parameter>) -> Self {
+        self.sheen_tint = sheen_tint.into();
+        self
+    }
+
+    pub fn clearcoat(mut self, clearcoat: impl Into<Parameter>) -> Self {
+        self.clearcoat = clearcoat.into();
+        self
+    }
+
+    pub fn clearcoat_gloss(mut self, clearcoat_gloss: impl Into<Parameter>) -> Self {
+        self.clearcoat_gloss = clearcoat_gloss.into();
+        self
+    }
+
+    pub fn transmission(mut self, transmission: impl Into<Parameter>) -> Self {
+        self.transmission = transmission.into();
+        self
+    }
+
+    pub fn ior(mut self, ior: f64) -> Self {
+        self.ior = ior;
+        self
+    }
+
+    /// Builds the material from glTF 2.0 metallic-roughness factors, with
+    /// `KHR_materials_transmission` and `KHR_materials_ior` where present.
+    pub fn from_gltf(
+        base_color: Arc<dyn Texture>,
+        metallic: impl Into<Parameter>,
+        roughness: impl Into<Parameter>,
+        transmission: Option<f64>,
+        ior: Option<f64>,
+    ) -> Self {
+        // glTF's dielectric F0 of 0.04 is Disney's specular 0.5.
+        Self::from_texture(base_color)
+            .metallic(metallic)
+            .roughness(roughness)
+            .transmission(transmission.unwrap_or(0.0))
+            .ior(ior.unwrap_or(1.5))
+    }
+
+    fn evaluate(&self, record: &HitRecord) -> Evaluated {
+        let unit = |parameter: &Parameter| parameter.value(record).clamp(0.0, 1.0);
+
+        Evaluated {
+            base_color: self
+                .base_color
+                .value(record.u, record.v, &record.point, &record.normal),
+            metallic: unit(&self.metallic),
+            roughness: unit(&self.roughness),
+            specular: unit(&self.specular),
+            specular_tint: unit(&self.specular_tint),
+            sheen: unit(&self.sheen),
+            sheen_tint: unit(&self.sheen_tint),
+            clearcoat: unit(&self.clearcoat),
+            clearcoat_gloss: unit(&self.clearcoat_gloss),
+            transmission: unit(&self.transmission),
+        }
+    }
+
+    /// The transmissive lobe, as rough glass.
+    fn glass(&self, distribution: TrowbridgeReitz) -> RoughDielectric {
+        RoughDielectric {
+            index_of_refraction: self.ior,
+            distribution,
+            absorption: Color::black(),
+            thin_walled: false,
+        }
+    }
+
+    fn eta(&self, record: &HitRecord) -> f64 {
+        if record.front_face {
+            self.ior
+        } else {
+            1.0 / self.ior
+        }
+    }
+}
+
+fn schlick_weight(cos_theta: f64) -> f64 {
+    (1.0 - cos_theta).clamp(0.0, 1.0).powi(5)
+}
+
+fn schlick_color(f0: &Color, cos_theta: f64) -> Color {
+    f0.lerp(&Color::white(), schlick_weight(cos_theta))
+}
+
+fn sample_cosine_hemisphere() -> Vector3 {
+    let mut rng = random::rng();
+    let r = rng.random_range(0.0f64..1.0).sqrt();
+    let phi = 2.0 * PI * rng.random_range(0.0..1.0);
+
+    Vector3::new(r * phi.cos(), r * phi.sin(), (1.0 - r * r).max(0.0).sqrt())
+}
+
+/// How `scatter` divides between the lobes at one hit, seen from `wo`.
+/// Each probability applies once the lobes before it weren't picked.
+struct Lobes {
+    clearcoat: f64,
+    metallic: f64,
+    transmission: f64,
+    /// The opaque base's specular lobe, against its diffuse one.
+    specular: f64,
+    coat: TrowbridgeReitz,
+    distribution: TrowbridgeReitz,
+    /// Normal-incidence reflectance of the opaque base's specular lobe.
+    f0: Color,
+    /// The base color normalised to unit luminance.
+    tint: Color,
+}
+
+impl Lobes {
+    fn new(p: &Evaluated, record: &HitRecord, wo: &Vector3) -> Self {
+        // Clearcoat: a clear, achromatic layer with a fixed IOR of 1.5 (F0 = 0.04).
+        let clearcoat = if record.front_face {
+            p.clearcoat * (0.04 + 0.96 * schlick_weight(wo.z()))
+        } else {
+            0.0
+        };
+        let coat_alpha = 0.1 + (0.001 - 0.1) * p.clearcoat_gloss;
+        let alpha = (p.roughness * p.roughness).max(1e-4);
+
+        let tint = if p.base_color.luminance() > 0.0 {
+            p.base_color / p.base_color.luminance()
+        } else {
+            Color::white()
+        };
+        let specular_color = Color::white().lerp(&tint, p.specular_tint);
+        let f0 = 0.08 * p.specular * specular_color;
+
+        Self {
+            clearcoat,
+            metallic: p.metallic,
+            transmission: p.transmission,
+            // Picked by the reflectance towards the viewer, which `eval` can
+            // reproduce; the sampled facet's own Fresnel term is a weight.
+            specular: schlick_color(&f0, wo.z()).luminance().clamp(0.0, 1.0),
+            coat: TrowbridgeReitz::new(coat_alpha, coat_alpha),
+            distribution: TrowbridgeReitz::new(alpha, alpha),
+            f0,
+            tint,
+        }
+    }
+}
+
+/// Burley diffuse with the renormalisation from Frostbite (Lagarde and de
+/// Rousiers 2014), which keeps the retro-reflection from adding energy at
+/// grazing angles, blended with sheen: `scatter`'s weight for a
+/// cosine-sampled `wi`.
+fn diffuse_weight(p: &Evaluated, tint: &Color, wo: &Vector3, wi: &Vector3) -> Color {
+    let half = unit_vector(&(*wi + *wo));
+    let cos_d = dot(wi, &half);
+    let energy_bias = 0.5 * p.roughness;
+    let energy_factor = 1.0 + (1.0 / 1.51 - 1.0) * p.roughness;
+    let fd90 = energy_bias + 2.0 * p.roughness * cos_d * cos_d;
+    let retro = energy_factor
+        * (1.0 + (fd90 - 1.0) * schlick_weight(wi.z()))
+        * (1.0 + (fd90 - 1.0) * schlick_weight(wo.z()));
+
+    // Sheen blends towards its own color at grazing half angles rather
+    // than adding on top, so it can't push the total past one either.
+    let sheen_color = Color::white().lerp(tint, p.sheen_tint);
+    let sheen = p.sheen * schlick_weight(cos_d);
+
+    (1.0 - sheen) * retro * p.base_color + sheen * sheen_color
+}
+
+impl Material for Principled {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        let p = self.evaluate(record);
+        let frame = Onb::new(&record.normal);
+        let wo = frame.world_to_local(&-unit_vector(&ray.direction));
+        if wo.z() <= 0.0 {
+            return false;
+        }
+
+        let lobes = Lobes::new(&p, record, &wo);
+        let mut rng = random::rng();
+
+        let mut finish = |wi: Vector3, weight: Color, smooth: bool| {
+            *scattered = Ray::new(record.point, frame.local_vector(&wi), ray.time).specular(smooth);
+            *attenuation = weight;
+            true
+        };
+
+        if rng.random_range(0.0..1.0) < lobes.clearcoat {
+            let wm = sample_facet(&lobes.coat, &wo);
+            let Some((wi, weight)) = reflect_off_facet(&lobes.coat, &wo, &wm) else {
+                return false;
+            };
+            return finish(wi, weight * Color::white(), lobes.coat.is_smooth());
+        }
+
+        let distribution = &lobes.distribution;
+        let smooth = distribution.is_smooth();
+        if rng.random_range(0.0..1.0) < lobes.metallic {
+            let wm = sample_facet(distribution, &wo);
+            let Some((wi, weight)) = reflect_off_facet(distribution, &wo, &wm) else {
+                return false;
+            };
+            return finish(
+                wi,
+                weight * schlick_color(&p.base_color, dot(&wo, &wm)),
+                smooth,
+            );
+        }
+
+        if rng.random_range(0.0..1.0) < lobes.transmission {
+            let glass = self.glass(*distribution);
+            let Some((wi, weight)) = glass.sample_local(&wo, self.eta(record)) else {
+                return false;
+            };
+            // Only the transmitted part is tinted; reflections stay white.
+            let color = if wi.z() < 0.0 {
+                p.base_color
+            } else {
+                Color::white()
+            };
+            return finish(wi, weight * color, smooth);
+        }
+
+        // Opaque dielectric base: specular reflection with the Disney F0,
+        // everything that isn't reflected goes into the diffuse lobe.
+        if rng.random_range(0.0..1.0) < lobes.specular {
+            let wm = sample_facet(distribution, &wo);
+            let Some((wi, weight)) = reflect_off_facet(distribution, &wo, &wm) else {
+                return false;
+            };
+            let reflectance = schlick_color(&lobes.f0, dot(&wo, &wm));
+            return finish(wi, (weight / lobes.specular) * reflectance, smooth);
+        }
+
+        let wi = sample_cosine_hemisphere();
+        finish(wi, diffuse_weight(&p, &lobes.tint, &wo, &wi), false)
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        let p = self.evaluate(record);
+        let frame = Onb::new(&record.normal);
+        let wo = frame.world_to_local(&-unit_vector(&ray.direction));
+        let wi = frame.world_to_local(&unit_vector(direction));
+        if wo.z() <= 0.0 {
+            return Color::black();
+        }
+        let lobes = Lobes::new(&p, record, &wo);
+        let reflection = facet_reflection(&lobes.distribution, &wo, &wi);
+
+        let mut base = Color::black();
+        if let Some(reflection) = &reflection {
+            base += reflection.value * schlick_color(&lobes.f0, dot(&wo, &reflection.wm));
+        }
+        if wi.z() > 0.0 {
+            base +=
+                ((1.0 - lobes.specular) * wi.z() / PI) * diffuse_weight(&p, &lobes.tint, &wo, &wi);
+        }
+
+        let glass = self
+            .glass(lobes.distribution)
+            .eval_local(&wo, &wi, self.eta(record));
+        let transmission = if wi.z() < 0.0 {
+            glass * p.base_color
+        } else {
+            glass * Color::white()
+        };
+
+        let metal = match &reflection {
+            Some(reflection) => {
+                reflection.value * schlick_color(&p.base_color, dot(&wo, &reflection.wm))
+            }
+            None => Color::black(),
+        };
+
+        let coat = facet_reflection(&lobes.coat, &wo, &wi).map_or(0.0, |coat| coat.value);
+
+        let under_coat = lobes.metallic * metal
+            + (1.0 - lobes.metallic)
+                * (lobes.transmission * transmission + (1.0 - lobes.transmission) * base);
+        (lobes.clearcoat * coat) * Color::white() + (1.0 - lobes.clearcoat) * under_coat
+    }
+
+    fn pdf(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        let p = self.evaluate(record);
+        let frame = Onb::new(&record.normal);
+        let wo = frame.world_to_local(&-unit_vector(&ray.direction));
+        let wi = frame.world_to_local(&unit_vector(direction));
+        if wo.z() <= 0.0 {
+            return 0.0;
+        }
+        let lobes = Lobes::new(&p, record, &wo);
+
+        let reflection = facet_reflection(&lobes.distribution, &wo, &wi)
+            .map_or(0.0, |reflection| reflection.pdf);
+        let diffuse = wi.z().max(0.0) / PI;
+        let base = lobes.specular * reflection + (1.0 - lobes.specular) * diffuse;
+        let glass = self
+            .glass(lobes.distribution)
+            .pdf_local(&wo, &wi, self.eta(record));
+        let coat = facet_reflection(&lobes.coat, &wo, &wi).map_or(0.0, |coat| coat.pdf);
+
+        let under_coat = lobes.metallic * reflection
+            + (1.0 - lobes.metallic)
+                * (lobes.transmission * glass + (1.0 - lobes.transmission) * base);
+        lobes.clearcoat * coat + (1.0 - lobes.clearcoat) * under_coat
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::material::{assert_eval_matches_scatter, directional_albedo};
+
+    /// A white principled surface in a uniformly white environment can't
+    /// reflect more than comes in, at any roughness or metalness.
+    #[test]
+    fn white_furnace() {
+        const SAMPLES: u32 = 20_000;
+        // Allowance for Monte Carlo noise at this sample count.
+        const TOLERANCE: f64 = 0.02;
+
+        random::seed(33);
+        let white = Color::new(1.0, 1.0, 1.0);
+        for roughness in [0.0, 0.25, 0.5, 0.75, 1.0] {
+            for metallic in [0.0, 0.5, 1.0] {
+                for (sheen, clearcoat) in [(0.0, 0.0), (1.0, 1.0)] {
+                    let material = Principled::new(white)
+                        .roughness(roughness)
+                        .metallic(metallic)
+                        .sheen(sheen)
+                        .clearcoat(clearcoat);
+
+                    for cos_theta in [1.0, 0.5, 0.1] {
+                        let albedo = directional_albedo(&material, cos_theta, SAMPLES);
+                        let largest = albedo.r().max(albedo.g()).max(albedo.b());
+                        assert!(
+                            largest <= 1.0 + TOLERANCE,
+                            "roughness {} metallic {} sheen {} clearcoat {} at cos {}: {}",
+                            roughness,
+                            metallic,
+                            sheen,
+                            clearcoat,
+                            cos_theta,
+                            largest
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn eval_matches_scatter() {
+        let color = Color::new(0.8, 0.4, 0.2);
+        let materials = [
+            Principled::new(color).roughness(0.5),
+            Principled::new(color).roughness(0.3).metallic(0.5),
+            // A glossier coat is too narrow for the comparison to resolve.
+            Principled::new(color)
+                .roughness(0.6)
+                .sheen(1.0)
+                .clearcoat(1.0)
+                .clearcoat_gloss(0.5),
+            Principled::new(color).roughness(0.4).transmission(0.7),
+        ];
+
+        for material in &materials {
+            for cos_theta in [0.9, 0.4] {
+                assert_eval_matches_scatter(material, cos_theta);
+            }
+        }
+    }
+}
@@ -0,0 +1,305 @@
+//! Thin-film interference, as seen on soap bubbles, oil slicks, coated
+//! lenses and heat-tinted metal. Light reflected off the top and bottom of a
+//! film a few hundred nanometres thick interferes with itself, so the
+//! reflectance varies with wavelength, film thickness and viewing angle.
+
+use crate::materials::principled::Parameter;
+use crate::math::color::Color;
+use crate::math::spectrum::reflectance_to_rgb;
+use crate::objects::hittable::HitRecord;
+use crate::textures::texture::Texture;
+use std::f64::consts::PI;
+use std::ops::{Add, Div, Mul, Sub};
+use std::sync::Arc;
+
+/// Wavelengths, in nanometres, that the red, green and blue components of
+/// RGB optical constants are taken to be measured at.
+const CHANNEL_WAVELENGTHS: [f64; 3] = [650.0, 550.0, 450.0];
+
+/// A transparent film coating a surface.
+pub struct ThinFilm {
+    index_of_refraction: f64,
+    /// Scales between `min_thickness` and `max_thickness`.
+    thickness: Parameter,
+    min_thickness: f64,
+    max_thickness: f64,
+}
+
+impl ThinFilm {
+    /// A film of uniform `thickness` in nanometres.
+    pub fn new(thickness: f64, index_of_refraction: f64) -> Self {
+        Self {
+            index_of_refraction,
+            thickness: Parameter::Constant(0.0),
+            min_thickness: thickness,
+            max_thickness: thickness,
+        }
+    }
+
+    /// A film whose thickness runs from `min_thickness` to `max_thickness`
+    /// nanometres as the red channel of `thickness` goes from zero to one,
+    /// for swirls on a bubble or a gradient of temper colors.
+    pub fn from_texture(
+        thickness: Arc<dyn Texture>,
+        min_thickness: f64,
+        max_thickness: f64,
+        index_of_refraction: f64,
+    ) -> Self {
+        Self {
+            index_of_refraction,
+            thickness: Parameter::Texture(thickness),
+            min_thickness,
+            max_thickness,
+        }
+    }
+
+    /// Reflectance of the film on a substrate with complex index
+    /// `eta + i k`, lit at `cos_theta` from a medium of index `outside`.
+    /// A dielectric substrate has `k` zero.
+    pub(crate) fn reflectance(
+        &self,
+        record: &HitRecord,
+        cos_theta: f64,
+        outside: f64,
+        eta: &Color,
+        k: &Color,
+    ) -> Color {
+        let t = self.thickness.value(record).clamp(0.0, 1.0);
+        let thickness = self.min_thickness + t * (self.max_thickness - self.min_thickness);
+
+        reflectance_to_rgb(|lambda| {
+            let substrate = Complex::new(channel_at(eta, lambda), channel_at(k, lambda));
+            airy_reflectance(
+                cos_theta,
+                outside,
+                self.index_of_refraction,
+                substrate,
+                thickness,
+                lambda,
+            )
+        })
+    }
+}
+
+/// Interpolates an RGB optical constant to `lambda`, holding the end
+/// values beyond the channel wavelengths.
+fn channel_at(color: &Color, lambda: f64) -> f64 {
+    let [red, green, blue] = CHANNEL_WAVELENGTHS;
+    if lambda >= red {
+        color.r()
+    } else if lambda >= green {
+        color.g() + (lambda - green) / (red - green) * (color.r() - color.g())
+    } else if lambda >= blue {
+        color.b() + (lambda - blue) / (green - blue) * (color.g() - color.b())
+    } else {
+        color.b()
+    }
+}
+
+/// Unpolarised reflectance of a single film, summing every internal
+/// reflection coherently (the Airy formula).
+fn airy_reflectance(
+    cos_theta: f64,
+    outside: f64,
+    film: f64,
+    substrate: Complex,
+    thickness: f64,
+    lambda: f64,
+) -> f64 {
+    let cos_theta = cos_theta.clamp(0.0, 1.0);
+    let sin2 = 1.0 - cos_theta * cos_theta;
+
+    let n1 = Complex::real(outside);
+    let n2 = Complex::real(film);
+    let n3 = substrate;
+    let cos1 = Complex::real(cos_theta);
+    // Snell's law, n sin θ constant across the layers; complex cosines cover
+    // total internal reflection and absorbing substrates.
+    let invariant = Complex::real(outside * outside * sin2);
+    let cos2 = (Complex::real(1.0) - invariant / (n2 * n2)).sqrt();
+    let cos3 = (Complex::real(1.0) - invariant / (n3 * n3)).sqrt();
+
+    let phase = Complex::real(4.0 * PI * thickness / lambda) * n2 * cos2;
+    let delay = (Complex::new(0.0, 1.0) * phase).exp();
+
+    let total = |r12: Complex, r23: Complex| {
+        let r = (r12 + r23 * delay) / (Complex::real(1.0) + r12 * r23 * delay);
+        r.norm_squared()
+    };
+
+    let rs = total(
+        amplitude_s(n1, cos1, n2, cos2),
+        amplitude_s(n2, cos2, n3, cos3),
+    );
+    let rp = total(
+        amplitude_p(n1, cos1, n2, cos2),
+        amplitude_p(n2, cos2, n3, cos3),
+    );
+
+    (0.5 * (rs + rp)).clamp(0.0, 1.0)
+}
+
+/// Fresnel amplitude reflection coefficients for light polarised
+/// perpendicular (s) and parallel (p) to the plane of incidence, going from
+/// index `ni` at cosine `ci` into index `nj` at cosine `cj`.
+fn amplitude_s(ni: Complex, ci: Complex, nj: Complex, cj: Complex) -> Complex {
+    (ni * ci - nj * cj) / (ni * ci + nj * cj)
+}
+
+fn amplitude_p(ni: Complex, ci: Complex, nj: Complex, cj: Complex) -> Complex {
+    (nj * ci - ni * cj) / (nj * ci + ni * cj)
+}
+
+#[derive(Copy, Clone)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    fn real(re: f64) -> Self {
+        Self::new(re, 0.0)
+    }
+
+    fn norm_squared(&self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+
+    /// Principal square root, with a non-negative real part.
+    fn sqrt(self) -> Self {
+        let modulus = self.norm_squared().sqrt();
+        let re = (0.5 * (modulus + self.re)).max(0.0).sqrt();
+        let im = (0.5 * (modulus - self.re)).max(0.0).sqrt();
+        Self::new(re, if self.im < 0.0 { -im } else { im })
+    }
+
+    fn exp(self) -> Self {
+        let magnitude = self.re.exp();
+        Self::new(magnitude * self.im.cos(), magnitude * self.im.sin())
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        let denominator = other.norm_squared();
+        Self::new(
+            (self.re * other.re + self.im * other.im) / denominator,
+            (self.im * other.re - self.re * other.im) / denominator,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::microfacet::fresnel_dielectric;
+
+    /// A film too thin to add a path difference leaves the bare interface
+    /// between the outside and the substrate.
+    #[test]
+    fn zero_thickness_is_the_bare_substrate() {
+        for substrate in [1.33, 1.5, 2.4] {
+            for cos_theta in [1.0, 0.7, 0.3, 0.05] {
+                let expected = fresnel_dielectric(cos_theta, substrate);
+                for lambda in [450.0, 550.0, 650.0] {
+                    let film = airy_reflectance(
+                        cos_theta,
+                        1.0,
+                        1.8,
+                        Complex::real(substrate),
+                        0.0,
+                        lambda,
+                    );
+                    assert!(
+                        (film - expected).abs() < 1e-9,
+                        "substrate {} at cos {}: {} against {}",
+                        substrate,
+                        cos_theta,
+                        film,
+                        expected
+                    );
+                }
+
+                let film = ThinFilm::new(0.0, 1.8);
+                let eta = Color::new(substrate, substrate, substrate);
+                let rgb =
+                    film.reflectance(&HitRecord::new(), cos_theta, 1.0, &eta, &Color::black());
+                for channel in [rgb.r(), rgb.g(), rgb.b()] {
+                    assert!(
+                        (channel - expected).abs() < 1e-3,
+                        "{} against {}",
+                        channel,
+                        expected
+                    );
+                }
+            }
+        }
+    }
+
+    /// Water on glass at normal incidence: the reflectance swings with
+    /// thickness as the two reflections fall in and out of phase.
+    #[test]
+    fn normal_incidence_varies_with_thickness() {
+        let glass = Color::new(1.5, 1.5, 1.5);
+        let reflectance = |thickness: f64| {
+            ThinFilm::new(thickness, 1.33).reflectance(
+                &HitRecord::new(),
+                1.0,
+                1.0,
+                &glass,
+                &Color::black(),
+            )
+        };
+
+        let bare = reflectance(0.0);
+        // A quarter wave at 550 nm: the reflections off air-water and
+        // water-glass both flip phase, so they cancel in the green.
+        let quarter = reflectance(550.0 / (4.0 * 1.33));
+        assert!(
+            quarter.g() < bare.g(),
+            "{} against {}",
+            quarter.g(),
+            bare.g()
+        );
+        // A half wave brings them back in phase, as if the film weren't there.
+        let half = reflectance(550.0 / (2.0 * 1.33));
+        assert!((half.g() - bare.g()).abs() < 0.1 * (bare.g() - quarter.g()));
+        // And the color shifts across the spectrum with thickness.
+        let mut channels = [quarter.r(), quarter.g(), quarter.b()];
+        channels.sort_by(f64::total_cmp);
+        assert!(channels[2] - channels[0] > 1e-3);
+    }
+}
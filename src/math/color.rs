@@ -1,5 +1,8 @@
+use crate::math::random;
 use crate::math::vector3::Vector3;
 use rand::Rng;
+use std::io;
+use std::io::Write;
 use std::ops;
 use std::ops::Range;
 
@@ -11,18 +14,31 @@ impl Color {
         Color(Vector3::new(r, g, b))
     }
 
-    fn r(&self) -> f64 {
+    pub fn r(&self) -> f64 {
         self.0.x()
     }
 
-    fn g(&self) -> f64 {
+    pub fn g(&self) -> f64 {
         self.0.y()
     }
 
-    fn b(&self) -> f64 {
+    pub fn b(&self) -> f64 {
         self.0.z()
     }
 
+    pub fn lerp(&self, other: &Color, t: f64) -> Self {
+        (1.0 - t) * *self + t * *other
+    }
+
+    /// Relative luminance of a linear sRGB color (Rec. 709 weights).
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.r() + 0.7152 * self.g() + 0.0722 * self.b()
+    }
+
+    pub fn is_black(&self) -> bool {
+        self.r() == 0.0 && self.g() == 0.0 && self.b() == 0.0
+    }
+
     pub fn black() -> Self {
         Color::new(0.0, 0.0, 0.0)
     }
@@ -32,7 +48,7 @@ impl Color {
     }
 
     pub fn random() -> Self {
-        let mut rng = rand::rng();
+        let mut rng = random::rng();
 
         Self::new(
             rng.random_range(0.0..1.0),
@@ -42,7 +58,7 @@ impl Color {
     }
 
     pub fn random_range(range: Range<f64>) -> Self {
-        let mut rng = rand::rng();
+        let mut rng = random::rng();
 
         Self::new(
             rng.random_range(range.clone()),
@@ -60,6 +76,30 @@ impl ops::Add<Color> for Color {
     }
 }
 
+impl ops::Sub<Color> for Color {
+    type Output = Color;
+
+    fn sub(self, rhs: Color) -> Self::Output {
+        Color::new(self.r() - rhs.r(), self.g() - rhs.g(), self.b() - rhs.b())
+    }
+}
+
+impl ops::Mul<f64> for Color {
+    type Output = Color;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl ops::Div<f64> for Color {
+    type Output = Color;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        (1.0 / rhs) * self
+    }
+}
+
 impl ops::Mul<Color> for f64 {
     type Output = Color;
 
@@ -90,7 +130,7 @@ impl ops::AddAssign<Color> for Color {
     }
 }
 
-pub fn write_color(color: &Color, samples_per_pixel: u32) {
+pub fn write_color(out: &mut dyn Write, color: &Color, samples_per_pixel: u32) -> io::Result<()> {
     let mut r = color.r();
     let mut g = color.g();
     let mut b = color.b();
@@ -104,5 +144,5 @@ pub fn write_color(color: &Color, samples_per_pixel: u32) {
     let gg = (256.0 * g.clamp(0.0, 0.999)) as u32;
     let bb = (256.0 * b.clamp(0.0, 0.999)) as u32;
 
-    println!("{} {} {}", rr, gg, bb);
+    writeln!(out, "{} {} {}", rr, gg, bb)
 }
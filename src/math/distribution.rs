@@ -0,0 +1,133 @@
+//! Piecewise-constant distributions for importance sampling tabulated
+//! functions, such as the brightness of an environment map.
+
+/// A distribution over [0, 1) proportional to a step function with equally
+/// wide steps.
+pub struct Distribution1D {
+    function: Vec<f64>,
+    /// Running integral at the start of each step, normalised to end at one.
+    cdf: Vec<f64>,
+    integral: f64,
+}
+
+impl Distribution1D {
+    /// Negative values count as zero. A function that is zero everywhere
+    /// samples uniformly.
+    pub fn new(function: Vec<f64>) -> Self {
+        assert!(!function.is_empty(), "distribution needs at least one step");
+        let function: Vec<f64> = function.into_iter().map(|value| value.max(0.0)).collect();
+        let n = function.len() as f64;
+
+        let mut cdf = Vec::with_capacity(function.len() + 1);
+        cdf.push(0.0);
+        for (i, value) in function.iter().enumerate() {
+            cdf.push(cdf[i] + value / n);
+        }
+
+        let integral = cdf[function.len()];
+        for (i, entry) in cdf.iter_mut().enumerate() {
+            *entry = if integral > 0.0 {
+                *entry / integral
+            } else {
+                i as f64 / n
+            };
+        }
+
+        Self {
+            function,
+            cdf,
+            integral,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.function.len()
+    }
+
+    /// Integral of the function over [0, 1).
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+
+    /// Maps `u` in [0, 1) to a point in [0, 1), returning it with its
+    /// density and the index of the step it falls in.
+    pub fn sample(&self, u: f64) -> (f64, f64, usize) {
+        // The last step whose start is at or below u.
+        let index = self
+            .cdf
+            .partition_point(|&start| start <= u)
+            .saturating_sub(1)
+            .min(self.len() - 1);
+
+        let width = self.cdf[index + 1] - self.cdf[index];
+        let offset = if width > 0.0 {
+            (u - self.cdf[index]) / width
+        } else {
+            0.0
+        };
+
+        let x = (index as f64 + offset) / self.len() as f64;
+        (x.min(1.0 - f64::EPSILON), self.pdf_at(index), index)
+    }
+
+    /// Density at any point within step `index`.
+    pub fn pdf_at(&self, index: usize) -> f64 {
+        if self.integral > 0.0 {
+            self.function[index] / self.integral
+        } else {
+            1.0
+        }
+    }
+
+    pub fn pdf(&self, x: f64) -> f64 {
+        self.pdf_at(self.index_of(x))
+    }
+
+    fn index_of(&self, x: f64) -> usize {
+        ((x * self.len() as f64) as usize).min(self.len() - 1)
+    }
+}
+
+/// A distribution over the unit square proportional to a grid of values,
+/// sampled as a marginal distribution over rows and a conditional one
+/// within the chosen row.
+pub struct Distribution2D {
+    rows: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    /// `values` holds `height` rows of `width` values each.
+    pub fn new(values: &[f64], width: usize, height: usize) -> Self {
+        assert_eq!(values.len(), width * height);
+
+        let rows: Vec<Distribution1D> = values
+            .chunks(width)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(rows.iter().map(|row| row.integral()).collect());
+
+        Self { rows, marginal }
+    }
+
+    /// Maps `u` and `v` in [0, 1) to a point (x, y) in the unit square, with
+    /// y running along the rows, returning it with its density.
+    pub fn sample(&self, u: f64, v: f64) -> ((f64, f64), f64) {
+        let (y, pdf_y, row) = self.marginal.sample(v);
+        let (x, pdf_x, _) = self.rows[row].sample(u);
+
+        ((x, y), pdf_x * pdf_y)
+    }
+
+    /// Integral of the values over the unit square, each covering an equal
+    /// share of it.
+    pub fn integral(&self) -> f64 {
+        self.marginal.integral()
+    }
+
+    pub fn pdf(&self, x: f64, y: f64) -> f64 {
+        let row = self.marginal.index_of(y);
+
+        self.marginal.pdf_at(row) * self.rows[row].pdf(x)
+    }
+}
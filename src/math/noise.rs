@@ -0,0 +1,300 @@
+use crate::math::point3::Point3;
+use crate::math::vector3::{dot, unit_vector, Vector3};
+use rand::Rng;
+
+const POINT_COUNT: usize = 256;
+
+/// Scalar noise defined over 3D space.
+pub trait Noise: Send + Sync {
+    fn noise(&self, p: &Point3) -> f64;
+}
+
+fn permutation(rng: &mut impl Rng) -> [usize; POINT_COUNT] {
+    let mut perm = [0; POINT_COUNT];
+    for (i, p) in perm.iter_mut().enumerate() {
+        *p = i;
+    }
+
+    for i in (1..POINT_COUNT).rev() {
+        let target = rng.random_range(0..=i);
+        perm.swap(i, target);
+    }
+
+    perm
+}
+
+/// Gradient noise with a random unit vector at every lattice point, blended
+/// with trilinear Hermite interpolation. Values lie roughly in [-1, 1].
+pub struct Perlin {
+    gradients: [Vector3; POINT_COUNT],
+    perm_x: [usize; POINT_COUNT],
+    perm_y: [usize; POINT_COUNT],
+    perm_z: [usize; POINT_COUNT],
+}
+
+impl Perlin {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        let mut gradients = [Vector3::zero(); POINT_COUNT];
+        for gradient in gradients.iter_mut() {
+            let v = Vector3::new(
+                rng.random_range(-1.0..1.0),
+                rng.random_range(-1.0..1.0),
+                rng.random_range(-1.0..1.0),
+            );
+            *gradient = unit_vector(&v);
+        }
+
+        Self {
+            gradients,
+            perm_x: permutation(rng),
+            perm_y: permutation(rng),
+            perm_z: permutation(rng),
+        }
+    }
+}
+
+impl Noise for Perlin {
+    fn noise(&self, p: &Point3) -> f64 {
+        let u = p.x() - p.x().floor();
+        let v = p.y() - p.y().floor();
+        let w = p.z() - p.z().floor();
+
+        let i = p.x().floor() as i64;
+        let j = p.y().floor() as i64;
+        let k = p.z().floor() as i64;
+
+        let mut c = [[[Vector3::zero(); 2]; 2]; 2];
+        for (di, plane) in c.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, corner) in row.iter_mut().enumerate() {
+                    *corner = self.gradients[self.perm_x[((i + di as i64) & 255) as usize]
+                        ^ self.perm_y[((j + dj as i64) & 255) as usize]
+                        ^ self.perm_z[((k + dk as i64) & 255) as usize]];
+                }
+            }
+        }
+
+        perlin_interp(&c, u, v, w)
+    }
+}
+
+fn perlin_interp(c: &[[[Vector3; 2]; 2]; 2], u: f64, v: f64, w: f64) -> f64 {
+    let uu = u * u * (3.0 - 2.0 * u);
+    let vv = v * v * (3.0 - 2.0 * v);
+    let ww = w * w * (3.0 - 2.0 * w);
+
+    let mut accum = 0.0;
+    for (i, plane) in c.iter().enumerate() {
+        for (j, row) in plane.iter().enumerate() {
+            for (k, gradient) in row.iter().enumerate() {
+                let (fi, fj, fk) = (i as f64, j as f64, k as f64);
+                let weight = Vector3::new(u - fi, v - fj, w - fk);
+                accum += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                    * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                    * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                    * dot(gradient, &weight);
+            }
+        }
+    }
+
+    accum
+}
+
+const SIMPLEX_GRADIENTS: [[f64; 3]; 12] = [
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [1.0, -1.0, 0.0],
+    [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [-1.0, 0.0, 1.0],
+    [1.0, 0.0, -1.0],
+    [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, -1.0, 1.0],
+    [0.0, 1.0, -1.0],
+    [0.0, -1.0, -1.0],
+];
+
+/// 3D simplex noise (Perlin 2001, after Gustavson's reference
+/// implementation). Cheaper than `Perlin` and free of axis-aligned artifacts.
+/// Values lie roughly in [-1, 1].
+pub struct Simplex {
+    perm: [usize; 2 * POINT_COUNT],
+}
+
+impl Simplex {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        let base = permutation(rng);
+        let mut perm = [0; 2 * POINT_COUNT];
+        for (i, p) in perm.iter_mut().enumerate() {
+            *p = base[i & 255];
+        }
+
+        Self { perm }
+    }
+
+    fn corner(&self, gi: usize, x: f64, y: f64, z: f64) -> f64 {
+        let t = 0.6 - x * x - y * y - z * z;
+        if t < 0.0 {
+            return 0.0;
+        }
+
+        let g = SIMPLEX_GRADIENTS[gi % 12];
+        let t = t * t;
+        t * t * (g[0] * x + g[1] * y + g[2] * z)
+    }
+}
+
+impl Noise for Simplex {
+    fn noise(&self, p: &Point3) -> f64 {
+        const F3: f64 = 1.0 / 3.0;
+        const G3: f64 = 1.0 / 6.0;
+
+        // Skew into the simplicial grid to find the containing cell.
+        let s = (p.x() + p.y() + p.z()) * F3;
+        let i = (p.x() + s).floor();
+        let j = (p.y() + s).floor();
+        let k = (p.z() + s).floor();
+        let t = (i + j + k) * G3;
+        let x0 = p.x() - (i - t);
+        let y0 = p.y() - (j - t);
+        let z0 = p.z() - (k - t);
+
+        // Pick the simplex (one of six tetrahedra) by ordering the offsets.
+        let (i1, j1, k1, i2, j2, k2) = if x0 >= y0 {
+            if y0 >= z0 {
+                (1, 0, 0, 1, 1, 0)
+            } else if x0 >= z0 {
+                (1, 0, 0, 1, 0, 1)
+            } else {
+                (0, 0, 1, 1, 0, 1)
+            }
+        } else if y0 < z0 {
+            (0, 0, 1, 0, 1, 1)
+        } else if x0 < z0 {
+            (0, 1, 0, 0, 1, 1)
+        } else {
+            (0, 1, 0, 1, 1, 0)
+        };
+
+        let x1 = x0 - i1 as f64 + G3;
+        let y1 = y0 - j1 as f64 + G3;
+        let z1 = z0 - k1 as f64 + G3;
+        let x2 = x0 - i2 as f64 + 2.0 * G3;
+        let y2 = y0 - j2 as f64 + 2.0 * G3;
+        let z2 = z0 - k2 as f64 + 2.0 * G3;
+        let x3 = x0 - 1.0 + 3.0 * G3;
+        let y3 = y0 - 1.0 + 3.0 * G3;
+        let z3 = z0 - 1.0 + 3.0 * G3;
+
+        let ii = (i as i64 & 255) as usize;
+        let jj = (j as i64 & 255) as usize;
+        let kk = (k as i64 & 255) as usize;
+        let perm = &self.perm;
+        let gi0 = perm[ii + perm[jj + perm[kk]]];
+        let gi1 = perm[ii + i1 + perm[jj + j1 + perm[kk + k1]]];
+        let gi2 = perm[ii + i2 + perm[jj + j2 + perm[kk + k2]]];
+        let gi3 = perm[ii + 1 + perm[jj + 1 + perm[kk + 1]]];
+
+        32.0 * (self.corner(gi0, x0, y0, z0)
+            + self.corner(gi1, x1, y1, z1)
+            + self.corner(gi2, x2, y2, z2)
+            + self.corner(gi3, x3, y3, z3))
+    }
+}
+
+/// Fractional Brownian motion: `octaves` layers of noise, each at
+/// `lacunarity` times the frequency and `gain` times the amplitude of the
+/// previous one.
+pub fn fbm(noise: &dyn Noise, p: &Point3, octaves: u32, lacunarity: f64, gain: f64) -> f64 {
+    let mut accum = 0.0;
+    let mut temp_p = *p;
+    let mut weight = 1.0;
+
+    for _ in 0..octaves {
+        accum += weight * noise.noise(&temp_p);
+        weight *= gain;
+        temp_p = lacunarity * temp_p;
+    }
+
+    accum
+}
+
+/// Sum of absolute noise octaves, which gives the creased look of turbulence.
+pub fn turbulence(noise: &dyn Noise, p: &Point3, depth: u32) -> f64 {
+    let mut accum = 0.0;
+    let mut temp_p = *p;
+    let mut weight = 1.0;
+
+    for _ in 0..depth {
+        accum += weight * noise.noise(&temp_p).abs();
+        weight *= 0.5;
+        temp_p = 2.0 * temp_p;
+    }
+
+    accum
+}
+
+/// Cellular noise: one jittered feature point per lattice cell. `distances`
+/// returns the distance to the nearest (F1) and second nearest (F2) points.
+pub struct Worley {
+    perm: [usize; POINT_COUNT],
+    offsets: [Vector3; POINT_COUNT],
+}
+
+impl Worley {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        let mut offsets = [Vector3::zero(); POINT_COUNT];
+        for offset in offsets.iter_mut() {
+            *offset = Vector3::new(
+                rng.random_range(0.0..1.0),
+                rng.random_range(0.0..1.0),
+                rng.random_range(0.0..1.0),
+            );
+        }
+
+        Self {
+            perm: permutation(rng),
+            offsets,
+        }
+    }
+
+    fn feature_point(&self, i: i64, j: i64, k: i64) -> Point3 {
+        let hash = self.perm[(self.perm
+            [(self.perm[(i & 255) as usize] + (j & 255) as usize) & 255]
+            + (k & 255) as usize)
+            & 255];
+
+        Point3::new(i as f64, j as f64, k as f64) + self.offsets[hash]
+    }
+
+    pub fn distances(&self, p: &Point3) -> (f64, f64) {
+        let i = p.x().floor() as i64;
+        let j = p.y().floor() as i64;
+        let k = p.z().floor() as i64;
+
+        let mut f1 = f64::INFINITY;
+        let mut f2 = f64::INFINITY;
+        for di in -1..=1 {
+            for dj in -1..=1 {
+                for dk in -1..=1 {
+                    let d = (self.feature_point(i + di, j + dj, k + dk) - *p).length();
+                    if d < f1 {
+                        f2 = f1;
+                        f1 = d;
+                    } else if d < f2 {
+                        f2 = d;
+                    }
+                }
+            }
+        }
+
+        (f1, f2)
+    }
+}
+
+impl Noise for Worley {
+    fn noise(&self, p: &Point3) -> f64 {
+        self.distances(p).0
+    }
+}
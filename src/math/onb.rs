@@ -0,0 +1,38 @@
+use crate::math::vector3::{dot, unit_vector, Vector3};
+
+/// Orthonormal basis with `w` along a given direction. Built with the
+/// branchless construction of Duff et al. (2017), which is continuous
+/// everywhere except where `w.z` changes sign.
+#[derive(Copy, Clone)]
+pub struct Onb {
+    pub u: Vector3,
+    pub v: Vector3,
+    pub w: Vector3,
+}
+
+impl Onb {
+    pub fn new(n: &Vector3) -> Self {
+        let w = unit_vector(n);
+        let sign = 1.0f64.copysign(w.z());
+        let a = -1.0 / (sign + w.z());
+        let b = w.x() * w.y() * a;
+
+        Self {
+            u: Vector3::new(1.0 + sign * w.x() * w.x() * a, sign * b, -sign * w.x()),
+            v: Vector3::new(b, sign + w.y() * w.y() * a, -w.y()),
+            w,
+        }
+    }
+
+    pub fn local(&self, a: f64, b: f64, c: f64) -> Vector3 {
+        a * self.u + b * self.v + c * self.w
+    }
+
+    pub fn local_vector(&self, a: &Vector3) -> Vector3 {
+        self.local(a.x(), a.y(), a.z())
+    }
+
+    pub fn world_to_local(&self, a: &Vector3) -> Vector3 {
+        Vector3::new(dot(a, &self.u), dot(a, &self.v), dot(a, &self.w))
+    }
+}
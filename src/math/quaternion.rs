@@ -0,0 +1,94 @@
+use crate::math::vector3::{cross, dot, unit_vector, Vector3};
+use std::ops::Mul;
+
+#[derive(Copy, Clone)]
+pub struct Quaternion {
+    w: f64,
+    v: Vector3,
+}
+
+impl Quaternion {
+    pub fn identity() -> Self {
+        Self {
+            w: 1.0,
+            v: Vector3::zero(),
+        }
+    }
+
+    /// Rotation of `degrees` around `axis`, counter-clockwise when looking down the axis.
+    pub fn from_axis_angle(axis: &Vector3, degrees: f64) -> Self {
+        let half = degrees.to_radians() / 2.0;
+
+        Self {
+            w: half.cos(),
+            v: half.sin() * unit_vector(axis),
+        }
+    }
+
+    pub fn conjugate(&self) -> Self {
+        Self {
+            w: self.w,
+            v: -self.v,
+        }
+    }
+
+    pub fn normalized(&self) -> Self {
+        let length = (self.w * self.w + self.v.length_squared()).sqrt();
+
+        Self {
+            w: self.w / length,
+            v: self.v / length,
+        }
+    }
+
+    pub fn rotate(&self, p: &Vector3) -> Vector3 {
+        // p' = p + 2w(v x p) + 2v x (v x p)
+        let t = 2.0 * cross(&self.v, p);
+
+        *p + self.w * t + cross(&self.v, &t)
+    }
+
+    /// Spherical linear interpolation along the shortest arc.
+    pub fn slerp(&self, other: &Quaternion, t: f64) -> Self {
+        let mut cos_theta = self.w * other.w + dot(&self.v, &other.v);
+        let mut other = *other;
+        if cos_theta < 0.0 {
+            other = Self {
+                w: -other.w,
+                v: -other.v,
+            };
+            cos_theta = -cos_theta;
+        }
+
+        // Nearly parallel quaternions fall back to a normalized lerp to avoid
+        // dividing by a vanishing sine.
+        if cos_theta > 0.9995 {
+            return Self {
+                w: self.w + t * (other.w - self.w),
+                v: self.v + t * (other.v - self.v),
+            }
+            .normalized();
+        }
+
+        let theta = cos_theta.acos();
+        let sin_theta = theta.sin();
+        let a = ((1.0 - t) * theta).sin() / sin_theta;
+        let b = (t * theta).sin() / sin_theta;
+
+        Self {
+            w: a * self.w + b * other.w,
+            v: a * self.v + b * other.v,
+        }
+    }
+}
+
+impl Mul<Quaternion> for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, rhs: Quaternion) -> Self::Output {
+        Quaternion {
+            w: self.w * rhs.w - dot(&self.v, &rhs.v),
+            v: self.w * rhs.v + rhs.w * self.v + cross(&self.v, &rhs.v),
+        }
+    }
+}
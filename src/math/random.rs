@@ -0,0 +1,37 @@
+use rand::rngs::SmallRng;
+use rand::{RngCore, SeedableRng};
+use std::cell::RefCell;
+
+const DEFAULT_SEED: u64 = 0x5eed;
+
+thread_local! {
+    static RNG: RefCell<SmallRng> = RefCell::new(SmallRng::seed_from_u64(DEFAULT_SEED));
+}
+
+/// Handle to the renderer's thread-local generator. Unlike `rand::rng()` it
+/// can be reseeded, so scene construction and procedural textures are
+/// reproducible from run to run.
+#[derive(Copy, Clone)]
+pub struct RendererRng;
+
+impl RngCore for RendererRng {
+    fn next_u32(&mut self) -> u32 {
+        RNG.with(|rng| rng.borrow_mut().next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        RNG.with(|rng| rng.borrow_mut().next_u64())
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        RNG.with(|rng| rng.borrow_mut().fill_bytes(dst))
+    }
+}
+
+pub fn rng() -> RendererRng {
+    RendererRng
+}
+
+pub fn seed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = SmallRng::seed_from_u64(seed));
+}
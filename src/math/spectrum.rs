@@ -0,0 +1,253 @@
+//! Spectral rendering support. A spectral ray carries three wavelengths
+//! (a hero wavelength and two rotations of it across the visible range, after
+//! Wilkie et al. 2014) and reuses the three channels of `Color` for the
+//! radiance at each of them.
+
+use crate::math::color::Color;
+
+pub const MIN_WAVELENGTH: f64 = 360.0;
+pub const MAX_WAVELENGTH: f64 = 830.0;
+
+/// Integral of the CIE y-bar fit below over the sampled range, which
+/// normalises an equal-energy spectrum of one to a luminance of one.
+const CIE_Y_INTEGRAL: f64 = 106.922_074_5;
+
+/// CIE XYZ to linear sRGB, Bradford-adapted from the equal-energy white
+/// (illuminant E) to D65 so that a flat spectrum comes out as RGB white.
+const XYZ_TO_SRGB: [[f64; 3]; 3] = [
+    [3.147_809_3, -1.662_846_2, -0.480_574_4],
+    [-0.994_747_4, 1.953_571_0, 0.039_740_2],
+    [0.063_515_5, -0.214_510_9, 1.151_595_2],
+];
+
+#[derive(Copy, Clone)]
+pub struct Wavelengths {
+    lambdas: [f64; 3],
+    secondary_terminated: bool,
+}
+
+impl Wavelengths {
+    /// Picks the hero wavelength from `u` in [0, 1) and spaces the other two
+    /// evenly after it, wrapping around the range.
+    pub fn sample(u: f64) -> Self {
+        let range = MAX_WAVELENGTH - MIN_WAVELENGTH;
+        let hero = u * range;
+        let lambdas = [0.0, 1.0, 2.0].map(|i| MIN_WAVELENGTH + (hero + i * range / 3.0) % range);
+
+        Self {
+            lambdas,
+            secondary_terminated: false,
+        }
+    }
+
+    pub fn hero(&self) -> f64 {
+        self.lambdas[0]
+    }
+
+    /// Drops the secondary wavelengths, for events such as dispersion whose
+    /// direction only suits the hero wavelength.
+    pub fn terminate_secondary(self) -> Self {
+        Self {
+            secondary_terminated: true,
+            ..self
+        }
+    }
+
+    /// Throughput factor for a scattering event that turned `self` into
+    /// `next`: terminating the secondary wavelengths zeroes their channels
+    /// and triples the hero's, which keeps the estimate unbiased.
+    pub fn termination_weight(&self, next: &Wavelengths) -> Color {
+        if next.secondary_terminated && !self.secondary_terminated {
+            Color::new(3.0, 0.0, 0.0)
+        } else {
+            Color::white()
+        }
+    }
+
+    /// Evaluates the spectrum of an RGB color at each wavelength.
+    pub fn uplift(&self, rgb: &Color) -> Color {
+        let [a, b, c] = self.lambdas.map(|lambda| rgb_to_spectrum(rgb, lambda));
+        Color::new(a, b, c)
+    }
+
+    /// Converts radiance at these wavelengths into a linear sRGB estimate.
+    pub fn radiance_to_rgb(&self, radiance: &Color) -> Color {
+        let samples = [radiance.r(), radiance.g(), radiance.b()];
+        let mut xyz = [0.0; 3];
+        for (lambda, sample) in self.lambdas.iter().zip(samples) {
+            let cmf = cie_xyz(*lambda);
+            for (total, weight) in xyz.iter_mut().zip(cmf) {
+                *total += sample * weight;
+            }
+        }
+
+        // Each wavelength has density 1 / range, and there are three of them.
+        let scale = (MAX_WAVELENGTH - MIN_WAVELENGTH) / (3.0 * CIE_Y_INTEGRAL);
+        let [r, g, b] =
+            XYZ_TO_SRGB.map(|row| scale * (row[0] * xyz[0] + row[1] * xyz[1] + row[2] * xyz[2]));
+
+        Color::new(r, g, b)
+    }
+}
+
+/// Linear sRGB color of a reflectance spectrum under equal-energy light,
+/// integrated in 10 nm steps over the visible range. A reflectance of one
+/// everywhere comes out as exactly white; saturated spectra outside the sRGB
+/// gamut are clamped into it, so the result stays a valid reflectance.
+pub fn reflectance_to_rgb(reflectance: impl Fn(f64) -> f64) -> Color {
+    let mut xyz = [0.0; 3];
+    let mut white = [0.0; 3];
+    for step in 0..=40 {
+        let lambda = 380.0 + 10.0 * step as f64;
+        let value = reflectance(lambda);
+        for (i, weight) in cie_xyz(lambda).into_iter().enumerate() {
+            xyz[i] += value * weight;
+            white[i] += weight;
+        }
+    }
+
+    let to_rgb =
+        |xyz: [f64; 3]| XYZ_TO_SRGB.map(|row| row[0] * xyz[0] + row[1] * xyz[1] + row[2] * xyz[2]);
+    let [r, g, b] = to_rgb(xyz);
+    let [white_r, white_g, white_b] = to_rgb(white);
+
+    Color::new(
+        (r / white_r).clamp(0.0, 1.0),
+        (g / white_g).clamp(0.0, 1.0),
+        (b / white_b).clamp(0.0, 1.0),
+    )
+}
+
+/// The CIE 1931 2° color matching functions at `lambda` nanometres, from the
+/// multi-lobe Gaussian fit by Wyman, Sloan and Shirley (2013).
+pub fn cie_xyz(lambda: f64) -> [f64; 3] {
+    let lobe = |mean: f64, below: f64, above: f64| {
+        let sigma = if lambda < mean { below } else { above };
+        let t = (lambda - mean) / sigma;
+        (-0.5 * t * t).exp()
+    };
+
+    [
+        1.056 * lobe(599.8, 37.9, 31.0) + 0.362 * lobe(442.0, 16.0, 26.7)
+            - 0.065 * lobe(501.1, 20.4, 26.2),
+        0.821 * lobe(568.8, 46.9, 40.5) + 0.286 * lobe(530.9, 16.3, 31.1),
+        1.217 * lobe(437.0, 11.8, 36.0) + 0.681 * lobe(459.0, 26.0, 13.8),
+    ]
+}
+
+// Smits (1999) basis spectra, sampled at ten evenly spaced bins over
+// 380-720 nm.
+const SMITS_WHITE: [f64; 10] = [
+    1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+];
+const SMITS_CYAN: [f64; 10] = [
+    0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+];
+const SMITS_MAGENTA: [f64; 10] = [
+    1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+];
+const SMITS_YELLOW: [f64; 10] = [
+    0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+];
+const SMITS_RED: [f64; 10] = [
+    0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const SMITS_GREEN: [f64; 10] = [
+    0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+];
+const SMITS_BLUE: [f64; 10] = [
+    1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+
+/// Linearly interpolates a Smits table between bin centers, holding the
+/// end values outside 380-720 nm.
+fn smits(table: &[f64; 10], lambda: f64) -> f64 {
+    let bin_width = (720.0 - 380.0) / 10.0;
+    let x = ((lambda - 380.0) / bin_width - 0.5).clamp(0.0, 9.0);
+    let i = (x.floor() as usize).min(8);
+    let t = x - i as f64;
+
+    table[i] + t * (table[i + 1] - table[i])
+}
+
+/// Smits' RGB to spectrum conversion: the smallest component as white, the
+/// next as the matching secondary (cyan, magenta or yellow) and the rest as
+/// a primary. Scaling the color scales the spectrum, so it also applies to
+/// radiance and weights above one.
+pub fn rgb_to_spectrum(rgb: &Color, lambda: f64) -> f64 {
+    let (r, g, b) = (rgb.r(), rgb.g(), rgb.b());
+    let at = |table: &[f64; 10]| smits(table, lambda);
+
+    if r <= g && r <= b {
+        if g <= b {
+            r * at(&SMITS_WHITE) + (g - r) * at(&SMITS_CYAN) + (b - g) * at(&SMITS_BLUE)
+        } else {
+            r * at(&SMITS_WHITE) + (b - r) * at(&SMITS_CYAN) + (g - b) * at(&SMITS_GREEN)
+        }
+    } else if g <= r && g <= b {
+        if r <= b {
+            g * at(&SMITS_WHITE) + (r - g) * at(&SMITS_MAGENTA) + (b - r) * at(&SMITS_BLUE)
+        } else {
+            g * at(&SMITS_WHITE) + (b - g) * at(&SMITS_MAGENTA) + (r - b) * at(&SMITS_RED)
+        }
+    } else if r <= g {
+        b * at(&SMITS_WHITE) + (r - b) * at(&SMITS_YELLOW) + (g - r) * at(&SMITS_GREEN)
+    } else {
+        b * at(&SMITS_WHITE) + (g - b) * at(&SMITS_YELLOW) + (r - g) * at(&SMITS_RED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(color: &Color, expected: &Color, tolerance: f64) {
+        for (value, target) in [
+            (color.r(), expected.r()),
+            (color.g(), expected.g()),
+            (color.b(), expected.b()),
+        ] {
+            assert!(
+                (value - target).abs() <= tolerance,
+                "({}, {}, {}) is not ({}, {}, {})",
+                color.r(),
+                color.g(),
+                color.b(),
+                expected.r(),
+                expected.g(),
+                expected.b()
+            );
+        }
+    }
+
+    #[test]
+    fn white_reflectance_stays_white() {
+        for level in [1.0, 0.5, 0.1] {
+            let grey = Color::new(level, level, level);
+            let rgb = reflectance_to_rgb(|lambda| rgb_to_spectrum(&grey, lambda));
+            assert_close(&rgb, &grey, 0.002);
+        }
+    }
+
+    /// Uplifting white radiance and converting it back, averaged over the
+    /// hero wavelength, gives white again, as spectral renders rely on.
+    #[test]
+    fn white_radiance_round_trips() {
+        const STEPS: u32 = 10_000;
+
+        let white = Color::white();
+        let mut total = Color::black();
+        for step in 0..STEPS {
+            let wavelengths = Wavelengths::sample((step as f64 + 0.5) / STEPS as f64);
+            total += wavelengths.radiance_to_rgb(&wavelengths.uplift(&white));
+        }
+
+        assert_close(&(total / STEPS as f64), &white, 0.01);
+    }
+
+    #[test]
+    fn primaries_keep_their_hue() {
+        let red = reflectance_to_rgb(|lambda| rgb_to_spectrum(&Color::new(1.0, 0.0, 0.0), lambda));
+        assert!(red.r() > 0.8 && red.g() < 0.2 && red.b() < 0.2);
+    }
+}
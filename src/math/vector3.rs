@@ -1,5 +1,6 @@
+use crate::math::random;
 use rand::Rng;
-use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::ops::{Add, Div, Index, Mul, Neg, Sub};
 
 #[derive(Copy, Clone)]
 pub struct Vector3 {
@@ -7,7 +8,7 @@ pub struct Vector3 {
 }
 
 impl Vector3 {
-    pub fn new(x: f64, y: f64, z: f64) -> Self {
+    pub const fn new(x: f64, y: f64, z: f64) -> Self {
         Vector3 { e: [x, y, z] }
     }
 
@@ -39,8 +40,24 @@ impl Vector3 {
         self.x() * self.x() + self.y() * self.y() + self.z() * self.z()
     }
 
+    pub fn min(&self, other: &Vector3) -> Self {
+        Self::new(
+            self.x().min(other.x()),
+            self.y().min(other.y()),
+            self.z().min(other.z()),
+        )
+    }
+
+    pub fn max(&self, other: &Vector3) -> Self {
+        Self::new(
+            self.x().max(other.x()),
+            self.y().max(other.y()),
+            self.z().max(other.z()),
+        )
+    }
+
     pub fn random(min: f64, max: f64) -> Self {
-        let mut rng = rand::rng();
+        let mut rng = random::rng();
 
         Self::new(
             rng.random_range(min..max),
@@ -94,6 +111,14 @@ impl Div<f64> for Vector3 {
     }
 }
 
+impl Index<usize> for Vector3 {
+    type Output = f64;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.e[index]
+    }
+}
+
 impl Neg for Vector3 {
     type Output = Vector3;
 
@@ -119,7 +144,7 @@ pub fn unit_vector(v: &Vector3) -> Vector3 {
 }
 
 pub fn random_in_unit_disk() -> Vector3 {
-    let mut rng = rand::rng();
+    let mut rng = random::rng();
     loop {
         let p = Vector3::new(
             rng.random_range(-1.0..1.0),
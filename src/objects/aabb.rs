@@ -0,0 +1,109 @@
+use crate::math::point3::Point3;
+use crate::math::ray::Ray;
+use crate::math::vector3::Vector3;
+
+#[derive(Copy, Clone)]
+pub struct Aabb {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl Aabb {
+    pub fn new(a: Point3, b: Point3) -> Self {
+        Self {
+            min: a.min(&b),
+            max: a.max(&b),
+        }
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            min: Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: Point3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        }
+    }
+
+    pub fn surrounding(a: &Aabb, b: &Aabb) -> Self {
+        Self {
+            min: a.min.min(&b.min),
+            max: a.max.max(&b.max),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x() > self.max.x() || self.min.y() > self.max.y() || self.min.z() > self.max.z()
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn extent(&self) -> Vector3 {
+        self.max - self.min
+    }
+
+    pub fn corner(&self, index: usize) -> Point3 {
+        Point3::new(
+            if index & 1 == 0 {
+                self.min.x()
+            } else {
+                self.max.x()
+            },
+            if index & 2 == 0 {
+                self.min.y()
+            } else {
+                self.max.y()
+            },
+            if index & 4 == 0 {
+                self.min.z()
+            } else {
+                self.max.z()
+            },
+        )
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        let extent = self.extent();
+        if extent.x() > extent.y() && extent.x() > extent.z() {
+            0
+        } else if extent.y() > extent.z() {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Grows degenerate axes so flat primitives still produce a hittable slab.
+    pub fn pad(&self, delta: f64) -> Self {
+        let extent = self.extent();
+        let padding = Vector3::new(
+            if extent.x() < delta { delta / 2.0 } else { 0.0 },
+            if extent.y() < delta { delta / 2.0 } else { 0.0 },
+            if extent.z() < delta { delta / 2.0 } else { 0.0 },
+        );
+
+        Self {
+            min: self.min - padding,
+            max: self.max + padding,
+        }
+    }
+
+    pub fn hit(&self, ray: &Ray, mut t_min: f64, mut t_max: f64) -> bool {
+        for axis in 0..3 {
+            let inv_d = 1.0 / ray.direction[axis];
+            let mut t0 = (self.min[axis] - ray.origin[axis]) * inv_d;
+            let mut t1 = (self.max[axis] - ray.origin[axis]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max <= t_min {
+                return false;
+            }
+        }
+
+        true
+    }
+}
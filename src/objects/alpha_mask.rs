@@ -0,0 +1,144 @@
+use crate::math::color::Color;
+use crate::math::random;
+use crate::math::ray::Ray;
+use crate::objects::aabb::Aabb;
+use crate::objects::hittable::{HitRecord, Hittable};
+use crate::textures::texture::Texture;
+use rand::Rng;
+use std::sync::Arc;
+
+/// Offset used to step past a rejected hit before looking for the next.
+const SKIP_EPSILON: f64 = 1e-4;
+
+/// Rejected hits after which a ray is taken to have passed through, so a
+/// surface that keeps reporting the same hit can't stall the search.
+const MAX_SKIPPED: u32 = 64;
+
+#[derive(Copy, Clone)]
+pub enum AlphaTest {
+    /// Keeps a hit with probability alpha, so partial alpha reads as
+    /// partial coverage once averaged over samples.
+    Stochastic,
+    /// Keeps a hit where alpha reaches the cutoff, for hard-edged cut-outs.
+    Threshold(f64),
+}
+
+/// Cuts holes in a surface where an opacity texture (its red channel) says
+/// there is nothing there, such as the gaps between the leaves painted on a
+/// card or between the wires of a fence. Rejected hits are skipped and the
+/// search carries on behind them, for camera and shadow rays alike.
+pub struct AlphaMask {
+    object: Box<dyn Hittable>,
+    alpha: Arc<dyn Texture>,
+    test: AlphaTest,
+}
+
+impl AlphaMask {
+    pub fn new(object: Box<dyn Hittable>, alpha: Arc<dyn Texture>) -> Self {
+        Self {
+            object,
+            alpha,
+            test: AlphaTest::Stochastic,
+        }
+    }
+
+    pub fn test(mut self, test: AlphaTest) -> Self {
+        self.test = test;
+        self
+    }
+
+    fn alpha_at(&self, record: &HitRecord) -> f64 {
+        self.alpha
+            .value(record.u, record.v, &record.point, &record.geometric_normal)
+            .r()
+            .clamp(0.0, 1.0)
+    }
+}
+
+impl Hittable for AlphaMask {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, hit_record: &mut HitRecord) -> bool {
+        // Rejected hits go to a scratch record, since callers such as
+        // `BvhNode` keep a closer hit in `hit_record` across a miss.
+        let mut record = HitRecord::new();
+        let mut t_min = t_min;
+        for _ in 0..MAX_SKIPPED {
+            if !self.object.hit(ray, t_min, t_max, &mut record) {
+                break;
+            }
+            let alpha = self.alpha_at(&record);
+            let keep = match self.test {
+                AlphaTest::Stochastic => {
+                    alpha >= 1.0 || random::rng().random_range(0.0..1.0) < alpha
+                }
+                AlphaTest::Threshold(cutoff) => alpha >= cutoff,
+            };
+            if keep {
+                *hit_record = record;
+                return true;
+            }
+            t_min = record.t + SKIP_EPSILON;
+        }
+
+        false
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.object.bounding_box()
+    }
+
+    /// Every surface crossing lets through the uncovered fraction, which
+    /// for the stochastic test is the expected value of the hit test rather
+    /// than one noisy draw of it.
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64) -> Color {
+        let mut transmittance = 1.0;
+        let mut record = HitRecord::new();
+        let mut t_min = t_min;
+        for _ in 0..MAX_SKIPPED {
+            if transmittance <= 0.0 || !self.object.hit(r, t_min, t_max, &mut record) {
+                break;
+            }
+            let alpha = self.alpha_at(&record);
+            transmittance *= match self.test {
+                AlphaTest::Stochastic => 1.0 - alpha,
+                AlphaTest::Threshold(cutoff) if alpha >= cutoff => 0.0,
+                AlphaTest::Threshold(_) => 1.0,
+            };
+            t_min = record.t + SKIP_EPSILON;
+        }
+
+        Color::new(transmittance, transmittance, transmittance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::point3::Point3;
+    use crate::math::vector3::Vector3;
+    use crate::textures::texture::SolidColor;
+
+    /// A surface hit wherever the search starts, as one that reports hits at
+    /// `t_min` inclusively would be.
+    struct Sticky;
+
+    impl Hittable for Sticky {
+        fn hit(&self, _r: &Ray, t_min: f64, t_max: f64, hit_record: &mut HitRecord) -> bool {
+            hit_record.t = t_min.max(1.0);
+            hit_record.t <= t_max
+        }
+
+        fn bounding_box(&self) -> Aabb {
+            Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0))
+        }
+    }
+
+    #[test]
+    fn rejected_hits_are_stepped_past() {
+        let clear = Arc::new(SolidColor::new(Color::black()));
+        let mask = AlphaMask::new(Box::new(Sticky), clear).test(AlphaTest::Threshold(0.5));
+        let ray = Ray::new(Point3::zero(), Vector3::new(0.0, 0.0, 1.0), 0.0);
+
+        assert!(!mask.hit(&ray, 0.001, f64::INFINITY, &mut HitRecord::new()));
+        assert_eq!(mask.transmittance(&ray, 0.001, f64::INFINITY).r(), 1.0);
+    }
+}
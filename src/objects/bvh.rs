@@ -0,0 +1,98 @@
+use crate::math::color::Color;
+use crate::math::ray::Ray;
+use crate::objects::aabb::Aabb;
+use crate::objects::hittable::{HitRecord, Hittable};
+use crate::objects::hittable_list::HittableList;
+
+pub struct BvhNode {
+    left: Box<dyn Hittable>,
+    right: Option<Box<dyn Hittable>>,
+    bbox: Aabb,
+}
+
+impl BvhNode {
+    pub fn new(list: HittableList) -> Self {
+        let objects = list.into_objects();
+        assert!(!objects.is_empty(), "cannot build a BVH from an empty list");
+
+        Self::from_objects(objects)
+    }
+
+    fn from_objects(mut objects: Vec<Box<dyn Hittable>>) -> Self {
+        if objects.len() == 1 {
+            let left = objects.pop().unwrap();
+            let bbox = left.bounding_box();
+            return Self {
+                left,
+                right: None,
+                bbox,
+            };
+        }
+
+        // Split on the centroid bounds rather than the object bounds, so a
+        // single large object (such as the ground) doesn't dictate the axis.
+        let centroid_bounds = objects.iter().fold(Aabb::empty(), |bbox, object| {
+            let centroid = object.bounding_box().centroid();
+            Aabb::surrounding(&bbox, &Aabb::new(centroid, centroid))
+        });
+        let axis = centroid_bounds.longest_axis();
+
+        objects.sort_by(|a, b| {
+            let a = a.bounding_box().centroid()[axis];
+            let b = b.bounding_box().centroid()[axis];
+            a.total_cmp(&b)
+        });
+
+        let right_objects = objects.split_off(objects.len() / 2);
+        let left = Self::into_child(objects);
+        let right = Self::into_child(right_objects);
+        let bbox = Aabb::surrounding(&left.bounding_box(), &right.bounding_box());
+
+        Self {
+            left,
+            right: Some(right),
+            bbox,
+        }
+    }
+
+    fn into_child(mut objects: Vec<Box<dyn Hittable>>) -> Box<dyn Hittable> {
+        if objects.len() == 1 {
+            objects.pop().unwrap()
+        } else {
+            Box::new(Self::from_objects(objects))
+        }
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, hit_record: &mut HitRecord) -> bool {
+        if !self.bbox.hit(ray, t_min, t_max) {
+            return false;
+        }
+
+        let hit_left = self.left.hit(ray, t_min, t_max, hit_record);
+        let closest_so_far = if hit_left { hit_record.t } else { t_max };
+        let hit_right = match &self.right {
+            Some(right) => right.hit(ray, t_min, closest_so_far, hit_record),
+            None => false,
+        };
+
+        hit_left || hit_right
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> Color {
+        if !self.bbox.hit(ray, t_min, t_max) {
+            return Color::white();
+        }
+
+        let left = self.left.transmittance(ray, t_min, t_max);
+        match &self.right {
+            Some(right) if !left.is_black() => left * right.transmittance(ray, t_min, t_max),
+            _ => left,
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use crate::materials::material::Material;
+use crate::materials::phase_function::Isotropic;
+use crate::math::color::Color;
+use crate::math::random;
+use crate::math::ray::Ray;
+use crate::math::vector3::Vector3;
+use crate::objects::aabb::Aabb;
+use crate::objects::hittable::{HitRecord, Hittable};
+use rand::Rng;
+use std::sync::Arc;
+
+/// Offset used to step past a boundary crossing before looking for the next.
+const CROSSING_EPSILON: f64 = 1e-4;
+
+/// Finds the next stretch of the ray, starting at `cursor`, that lies inside
+/// `boundary`. Handles rays that start inside (such as camera rays in fog)
+/// and, by being called repeatedly, non-convex boundaries. An exit that is
+/// never found means the medium extends to infinity.
+pub(crate) fn boundary_segment(
+    boundary: &dyn Hittable,
+    ray: &Ray,
+    cursor: f64,
+) -> Option<(f64, f64)> {
+    let mut crossing = HitRecord::new();
+    if !boundary.hit(ray, cursor, f64::INFINITY, &mut crossing) {
+        return None;
+    }
+
+    if !crossing.front_face {
+        return Some((cursor, crossing.t));
+    }
+
+    let enter = crossing.t;
+    let mut leaving = HitRecord::new();
+    let exit = if boundary.hit(ray, enter + CROSSING_EPSILON, f64::INFINITY, &mut leaving) {
+        leaving.t
+    } else {
+        f64::INFINITY
+    };
+
+    Some((enter, exit))
+}
+
+/// Calls `visit` with each inside stretch of the ray clipped to
+/// `[t_min, t_max]`, in order, until it returns `false`.
+pub(crate) fn for_each_segment(
+    boundary: &dyn Hittable,
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+    mut visit: impl FnMut(f64, f64) -> bool,
+) {
+    let mut cursor = t_min;
+    while let Some((enter, exit)) = boundary_segment(boundary, ray, cursor) {
+        if enter >= t_max || !visit(enter, exit.min(t_max)) || exit >= t_max {
+            return;
+        }
+        cursor = exit + CROSSING_EPSILON;
+    }
+}
+
+/// Fills in a hit record for a scattering or absorption event inside a medium,
+/// where there is no surface and so no meaningful normal.
+pub(crate) fn medium_hit(
+    ray: &Ray,
+    t: f64,
+    material: Arc<dyn Material>,
+    hit_record: &mut HitRecord,
+) {
+    hit_record.t = t;
+    hit_record.point = ray.at(t);
+    hit_record.normal = Vector3::new(1.0, 0.0, 0.0);
+    hit_record.geometric_normal = hit_record.normal;
+    hit_record.dpdu = Vector3::zero();
+    hit_record.dpdv = Vector3::zero();
+    hit_record.front_face = true;
+    hit_record.u = 0.0;
+    hit_record.v = 0.0;
+    hit_record.material = Some(material);
+}
+
+/// A homogeneous participating medium (fog, smoke) filling a closed boundary.
+/// Rays travel an exponentially distributed distance before scattering off
+/// the phase function, or pass through if they leave the boundary first.
+/// Overlapping or nested media add their densities.
+pub struct ConstantMedium {
+    boundary: Box<dyn Hittable>,
+    density: f64,
+    phase_function: Arc<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(boundary: Box<dyn Hittable>, density: f64, albedo: Color) -> Self {
+        Self::with_phase_function(boundary, density, Arc::new(Isotropic::new(albedo)))
+    }
+
+    pub fn with_phase_function(
+        boundary: Box<dyn Hittable>,
+        density: f64,
+        phase_function: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            boundary,
+            density,
+            phase_function,
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, hit_record: &mut HitRecord) -> bool {
+        let ray_length = ray.direction.length();
+        let mut rng = random::rng();
+        let mut remaining = -rng.random_range(f64::EPSILON..1.0).ln() / self.density;
+
+        let mut hit = false;
+        for_each_segment(&*self.boundary, ray, t_min, t_max, |enter, exit| {
+            let inside = (exit - enter) * ray_length;
+            if remaining < inside {
+                let t = enter + remaining / ray_length;
+                medium_hit(ray, t, self.phase_function.clone(), hit_record);
+                hit = true;
+                return false;
+            }
+
+            remaining -= inside;
+            true
+        });
+
+        hit
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> Color {
+        let ray_length = ray.direction.length();
+        let mut optical_depth = 0.0;
+        for_each_segment(&*self.boundary, ray, t_min, t_max, |enter, exit| {
+            optical_depth += self.density * (exit - enter) * ray_length;
+            true
+        });
+
+        (-optical_depth).exp() * Color::white()
+    }
+}
@@ -0,0 +1,279 @@
+use crate::math::noise::{fbm, Noise};
+use crate::math::point3::Point3;
+use crate::objects::aabb::Aabb;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Spatially varying density for a heterogeneous medium.
+pub trait DensityField: Send + Sync {
+    fn density(&self, p: &Point3) -> f64;
+
+    /// Upper bound of `density` anywhere, used as the tracking majorant.
+    fn max_density(&self) -> f64;
+}
+
+#[derive(Copy, Clone)]
+pub enum VoxelFormat {
+    U8,
+    F32,
+}
+
+/// Dense voxel grid stretched over `bounds`, sampled with trilinear
+/// interpolation. Voxels are stored with x varying fastest, then y, then z.
+pub struct VoxelGrid {
+    nx: usize,
+    ny: usize,
+    nz: usize,
+    values: Vec<f64>,
+    bounds: Aabb,
+    max_value: f64,
+}
+
+impl VoxelGrid {
+    pub fn new(nx: usize, ny: usize, nz: usize, values: Vec<f64>, bounds: Aabb) -> Self {
+        assert_eq!(values.len(), nx * ny * nz);
+        let max_value = values.iter().fold(0.0f64, |max, &v| max.max(v));
+
+        Self {
+            nx,
+            ny,
+            nz,
+            values,
+            bounds,
+            max_value,
+        }
+    }
+
+    /// Loads a dense grid with a one-line text header giving its size and
+    /// sample format, followed by the raw voxels:
+    ///
+    /// ```text
+    /// dense <nx> <ny> <nz> <u8|f32>
+    /// ```
+    pub fn load_dense(path: &Path, bounds: Aabb) -> io::Result<Self> {
+        let data = fs::read(path)?;
+        let newline = data
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .ok_or_else(|| invalid_data("missing dense grid header"))?;
+        let header = String::from_utf8_lossy(&data[..newline]);
+
+        let fields: Vec<&str> = header.split_whitespace().collect();
+        let [kind, nx, ny, nz, format] = fields.as_slice() else {
+            return Err(invalid_data("dense grid header needs five fields"));
+        };
+        if *kind != "dense" {
+            return Err(invalid_data("not a dense grid file"));
+        }
+
+        let size = |text: &str| -> io::Result<usize> {
+            match text.parse() {
+                Ok(0) | Err(_) => Err(invalid_data("bad dense grid dimension")),
+                Ok(n) => Ok(n),
+            }
+        };
+        let (nx, ny, nz) = (size(nx)?, size(ny)?, size(nz)?);
+        let format = match *format {
+            "u8" => VoxelFormat::U8,
+            "f32" => VoxelFormat::F32,
+            _ => return Err(invalid_data("dense grid format must be u8 or f32")),
+        };
+
+        let values = decode_voxels(&data[newline + 1..], voxel_count(nx, ny, nz)?, format)?;
+
+        Ok(Self::new(nx, ny, nz, values, bounds))
+    }
+
+    fn voxel(&self, x: usize, y: usize, z: usize) -> f64 {
+        self.values[(z * self.ny + y) * self.nx + x]
+    }
+}
+
+impl DensityField for VoxelGrid {
+    fn density(&self, p: &Point3) -> f64 {
+        let extent = self.bounds.extent();
+        let local = *p - self.bounds.min;
+        let dims = [self.nx, self.ny, self.nz];
+
+        // Voxel centers sit at half-integer grid coordinates.
+        let mut base = [0usize; 3];
+        let mut fraction = [0.0; 3];
+        for axis in 0..3 {
+            let g = local[axis] / extent[axis] * dims[axis] as f64 - 0.5;
+            if g < -0.5 || g > dims[axis] as f64 - 0.5 {
+                return 0.0;
+            }
+            let g = g.clamp(0.0, (dims[axis] - 1) as f64);
+            let floor = g.floor().min((dims[axis].max(2) - 2) as f64);
+            base[axis] = floor as usize;
+            fraction[axis] = g - floor;
+        }
+
+        let mut density = 0.0;
+        for corner in 0..8 {
+            let mut weight = 1.0;
+            let mut index = [0usize; 3];
+            for axis in 0..3 {
+                let offset = (corner >> axis) & 1;
+                index[axis] = (base[axis] + offset).min(dims[axis] - 1);
+                weight *= if offset == 1 {
+                    fraction[axis]
+                } else {
+                    1.0 - fraction[axis]
+                };
+            }
+            density += weight * self.voxel(index[0], index[1], index[2]);
+        }
+
+        density
+    }
+
+    fn max_density(&self) -> f64 {
+        self.max_value
+    }
+}
+
+fn voxel_count(nx: usize, ny: usize, nz: usize) -> io::Result<usize> {
+    nx.checked_mul(ny)
+        .and_then(|count| count.checked_mul(nz))
+        .ok_or_else(|| invalid_data("voxel grid is too large"))
+}
+
+fn decode_voxels(data: &[u8], count: usize, format: VoxelFormat) -> io::Result<Vec<f64>> {
+    let size = match format {
+        VoxelFormat::U8 => 1,
+        VoxelFormat::F32 => 4,
+    };
+    let length = count
+        .checked_mul(size)
+        .ok_or_else(|| invalid_data("voxel grid is too large"))?;
+    if data.len() < length {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "voxel data is truncated",
+        ));
+    }
+
+    let values: Vec<f64> = match format {
+        VoxelFormat::U8 => data[..count].iter().map(|&v| v as f64 / 255.0).collect(),
+        VoxelFormat::F32 => data[..length]
+            .chunks(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64)
+            .collect(),
+    };
+    // An infinite density would give tracking an infinite majorant, which
+    // never lets a ray advance.
+    if values.iter().any(|v| !v.is_finite()) {
+        return Err(invalid_data("voxel values must be finite"));
+    }
+
+    Ok(values.into_iter().map(|v| v.max(0.0)).collect())
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Billowy density from fBm noise inside a sphere. A radial falloff is added
+/// to the noise before thresholding, so the cloud thins out towards the
+/// surface instead of ending in a hard edge.
+pub struct NoiseDensity {
+    noise: Box<dyn Noise>,
+    scale: f64,
+    octaves: u32,
+    threshold: f64,
+    gain: f64,
+    center: Point3,
+    radius: f64,
+}
+
+impl NoiseDensity {
+    pub fn new(
+        noise: Box<dyn Noise>,
+        scale: f64,
+        octaves: u32,
+        threshold: f64,
+        gain: f64,
+        center: Point3,
+        radius: f64,
+    ) -> Self {
+        Self {
+            noise,
+            scale,
+            octaves,
+            threshold,
+            gain,
+            center,
+            radius,
+        }
+    }
+}
+
+impl DensityField for NoiseDensity {
+    fn density(&self, p: &Point3) -> f64 {
+        let falloff = 1.0 - (*p - self.center).length() / self.radius;
+        if falloff <= 0.0 {
+            return 0.0;
+        }
+
+        let n = fbm(&*self.noise, &(self.scale * *p), self.octaves, 2.0, 0.5);
+        let density = self.gain * (n + falloff - self.threshold).max(0.0);
+
+        // Noise can overshoot its nominal range slightly; the majorant must hold.
+        density.min(self.max_density())
+    }
+
+    fn max_density(&self) -> f64 {
+        // Each octave of noise stays within [-1, 1] and the falloff within [0, 1].
+        let fbm_bound = 2.0 * (1.0 - 0.5f64.powi(self.octaves as i32));
+        self.gain * (fbm_bound + 1.0 - self.threshold).max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oversized_grids_are_invalid() {
+        let error = voxel_count(1 << 30, 1 << 30, 1 << 30).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let error = decode_voxels(&[0; 4], usize::MAX / 2, VoxelFormat::F32).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn short_data_is_truncated() {
+        let error = decode_voxels(&[0; 7], 2, VoxelFormat::F32).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+
+        let count = voxel_count(1000, 1000, 1000).unwrap();
+        let error = decode_voxels(&[0; 4], count, VoxelFormat::U8).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn decodes_voxels() {
+        assert_eq!(
+            decode_voxels(&[0, 255], 2, VoxelFormat::U8).unwrap(),
+            [0.0, 1.0]
+        );
+
+        let mut data = 0.5f32.to_le_bytes().to_vec();
+        data.extend_from_slice(&(-2.0f32).to_le_bytes());
+        assert_eq!(
+            decode_voxels(&data, 2, VoxelFormat::F32).unwrap(),
+            [0.5, 0.0]
+        );
+    }
+
+    #[test]
+    fn infinite_voxels_are_invalid() {
+        let mut data = 0.5f32.to_le_bytes().to_vec();
+        data.extend_from_slice(&f32::INFINITY.to_le_bytes());
+        let error = decode_voxels(&data, 2, VoxelFormat::F32).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
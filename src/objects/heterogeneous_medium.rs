@@ -0,0 +1,181 @@
+use crate::materials::material::Material;
+use crate::materials::phase_function::Isotropic;
+use crate::math::color::Color;
+use crate::math::point3::Point3;
+use crate::math::random;
+use crate::math::ray::Ray;
+use crate::objects::aabb::Aabb;
+use crate::objects::constant_medium::{for_each_segment, medium_hit};
+use crate::objects::density_field::DensityField;
+use crate::objects::hittable::{HitRecord, Hittable};
+use rand::Rng;
+use std::sync::Arc;
+
+/// A participating medium whose density varies through space, such as a
+/// cloud from a voxel grid or procedural smoke.
+///
+/// At density `d` the medium absorbs with coefficient `d * absorption` and
+/// scatters with `d * scattering`. Absorbing collisions end the path and
+/// pick up the emitted radiance, `emission * emission_field(p)`, which is how
+/// fire is rendered. Camera and scattered rays are sampled with delta
+/// tracking; shadow rays estimate transmittance with ratio tracking.
+pub struct HeterogeneousMedium {
+    boundary: Box<dyn Hittable>,
+    density: Box<dyn DensityField>,
+    absorption: f64,
+    scattering: f64,
+    phase_function: Arc<dyn Material>,
+    emission: Color,
+    emission_field: Option<Box<dyn DensityField>>,
+}
+
+impl HeterogeneousMedium {
+    pub fn new(
+        boundary: Box<dyn Hittable>,
+        density: Box<dyn DensityField>,
+        absorption: f64,
+        scattering: f64,
+        albedo: Color,
+    ) -> Self {
+        Self {
+            boundary,
+            density,
+            absorption,
+            scattering,
+            phase_function: Arc::new(Isotropic::new(albedo)),
+            emission: Color::black(),
+            emission_field: None,
+        }
+    }
+
+    pub fn phase_function(mut self, phase_function: Arc<dyn Material>) -> Self {
+        self.phase_function = phase_function;
+        self
+    }
+
+    /// Emission follows the density unless a separate field (for example a
+    /// temperature grid) is given.
+    pub fn emission(mut self, emission: Color, field: Option<Box<dyn DensityField>>) -> Self {
+        self.emission = emission;
+        self.emission_field = field;
+        self
+    }
+
+    fn majorant(&self) -> f64 {
+        self.density.max_density() * (self.absorption + self.scattering)
+    }
+
+    fn emitted_radiance(&self, p: &Point3) -> Color {
+        let strength = match &self.emission_field {
+            Some(field) => field.density(p),
+            None => self.density.density(p),
+        };
+
+        strength * self.emission
+    }
+}
+
+impl Hittable for HeterogeneousMedium {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, hit_record: &mut HitRecord) -> bool {
+        let majorant = self.majorant();
+        if majorant <= 0.0 || !majorant.is_finite() {
+            return false;
+        }
+
+        let ray_length = ray.direction.length();
+        let mut rng = random::rng();
+        let mut hit = false;
+
+        for_each_segment(&*self.boundary, ray, t_min, t_max, |enter, exit| {
+            let mut t = enter;
+            loop {
+                t -= rng.random_range(f64::EPSILON..1.0).ln() / (majorant * ray_length);
+                if t >= exit {
+                    return true;
+                }
+
+                let p = ray.at(t);
+                let density = self.density.density(&p);
+                let xi = rng.random_range(0.0..1.0) * majorant;
+
+                if xi < density * self.absorption {
+                    let radiance = self.emitted_radiance(&p);
+                    medium_hit(ray, t, Arc::new(VolumeEmission { radiance }), hit_record);
+                    hit = true;
+                    return false;
+                }
+                if xi < density * (self.absorption + self.scattering) {
+                    medium_hit(ray, t, self.phase_function.clone(), hit_record);
+                    hit = true;
+                    return false;
+                }
+                // Null collision: the fictitious medium lets the ray carry on.
+            }
+        });
+
+        hit
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> Color {
+        let majorant = self.majorant();
+        if majorant <= 0.0 || !majorant.is_finite() {
+            return Color::white();
+        }
+
+        let ray_length = ray.direction.length();
+        let mut rng = random::rng();
+        let mut transmittance = 1.0;
+
+        for_each_segment(&*self.boundary, ray, t_min, t_max, |enter, exit| {
+            let mut t = enter;
+            loop {
+                t -= rng.random_range(f64::EPSILON..1.0).ln() / (majorant * ray_length);
+                if t >= exit {
+                    return true;
+                }
+
+                let extinction =
+                    self.density.density(&ray.at(t)) * (self.absorption + self.scattering);
+                transmittance *= 1.0 - extinction / majorant;
+
+                // Russian roulette once the estimate is negligible keeps long
+                // shadow rays through thick media cheap without adding bias.
+                if transmittance < 0.1 {
+                    if rng.random_range(0.0..1.0) < 0.5 {
+                        transmittance = 0.0;
+                        return false;
+                    }
+                    transmittance *= 2.0;
+                }
+            }
+        });
+
+        transmittance * Color::white()
+    }
+}
+
+/// Terminates the path at an absorbing collision and returns what the medium
+/// emits there.
+struct VolumeEmission {
+    radiance: Color,
+}
+
+impl Material for VolumeEmission {
+    fn scatter(
+        &self,
+        _ray: &Ray,
+        _record: &HitRecord,
+        _attenuation: &mut Color,
+        _scattered: &mut Ray,
+    ) -> bool {
+        false
+    }
+
+    fn emitted(&self, _ray: &Ray, _record: &HitRecord) -> Color {
+        self.radiance
+    }
+}
@@ -1,19 +1,78 @@
 use crate::materials::material::Material;
+use crate::math::color::Color;
 use crate::math::point3::Point3;
 use crate::math::ray::Ray;
 use crate::math::vector3::{dot, Vector3};
+use crate::objects::aabb::Aabb;
 use std::sync::Arc;
 
-pub trait Hittable {
+pub trait Hittable: Send + Sync {
     fn hit(&self, r: &Ray, t_min: f64, t_max: f64, hit_record: &mut HitRecord) -> bool;
+
+    /// Bounds the object over the whole shutter interval, not just one instant.
+    fn bounding_box(&self) -> Aabb;
+
+    /// Fraction of light that survives travelling along `r` between `t_min`
+    /// and `t_max`, for shadow rays. Solid objects block it completely;
+    /// participating media override this to attenuate instead.
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64) -> Color {
+        let mut record = HitRecord::new();
+        if self.hit(r, t_min, t_max, &mut record) {
+            Color::black()
+        } else {
+            Color::white()
+        }
+    }
+}
+
+/// Lets one object be shared between the world and a light list.
+impl<T: Hittable + ?Sized> Hittable for Arc<T> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, hit_record: &mut HitRecord) -> bool {
+        self.as_ref().hit(r, t_min, t_max, hit_record)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.as_ref().bounding_box()
+    }
+
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64) -> Color {
+        self.as_ref().transmittance(r, t_min, t_max)
+    }
+}
+
+/// A surface that can be sampled uniformly by area, which is what an area
+/// light needs of its shape.
+pub trait Shape: Hittable {
+    fn area(&self) -> f64;
+
+    /// A point spread uniformly over the surface, from `u` and `v` in
+    /// [0, 1), with its outward normal and texture coordinates. The record
+    /// has no material.
+    fn sample_surface(&self, u: f64, v: f64) -> HitRecord;
+
+    /// A direction and the cosine of the widest angle from it to any of the
+    /// surface's outward normals. The default allows every direction.
+    fn normal_bounds(&self) -> (Vector3, f64) {
+        (Vector3::new(0.0, 0.0, 1.0), -1.0)
+    }
 }
 
 #[derive(Clone)]
 pub struct HitRecord {
     pub point: Point3,
+    /// Shading normal, facing against the ray. Normal and bump maps perturb
+    /// this one.
     pub normal: Vector3,
+    /// True surface normal, also facing against the ray.
+    pub geometric_normal: Vector3,
+    /// Partial derivatives of the surface point with respect to u and v, or
+    /// zero where the surface has no parameterisation.
+    pub dpdu: Vector3,
+    pub dpdv: Vector3,
     pub material: Option<Arc<dyn Material>>,
     pub t: f64,
+    pub u: f64,
+    pub v: f64,
     pub front_face: bool,
 }
 
@@ -22,8 +81,13 @@ impl HitRecord {
         Self {
             point: Point3::zero(),
             normal: Vector3::zero(),
+            geometric_normal: Vector3::zero(),
+            dpdu: Vector3::zero(),
+            dpdv: Vector3::zero(),
             material: None,
             t: 0.0,
+            u: 0.0,
+            v: 0.0,
             front_face: false,
         }
     }
@@ -35,5 +99,6 @@ impl HitRecord {
         } else {
             -*outward_normal
         };
+        self.geometric_normal = self.normal;
     }
 }
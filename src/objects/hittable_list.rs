@@ -1,21 +1,28 @@
+use crate::math::color::Color;
 use crate::math::ray::Ray;
+use crate::objects::aabb::Aabb;
 use crate::objects::hittable::{HitRecord, Hittable};
 
 pub struct HittableList {
     objects: Vec<Box<dyn Hittable>>,
+    bbox: Aabb,
 }
 
 impl HittableList {
     pub fn new() -> Self {
-        Self { objects: vec![] }
-    }
-    pub fn clear(&mut self) {
-        self.objects.clear();
+        Self {
+            objects: vec![],
+            bbox: Aabb::empty(),
+        }
     }
-
     pub fn add(&mut self, object: Box<dyn Hittable>) {
+        self.bbox = Aabb::surrounding(&self.bbox, &object.bounding_box());
         self.objects.push(object);
     }
+
+    pub fn into_objects(self) -> Vec<Box<dyn Hittable>> {
+        self.objects
+    }
 }
 
 impl Hittable for HittableList {
@@ -34,4 +41,20 @@ impl Hittable for HittableList {
 
         hit_anything
     }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> Color {
+        let mut transmittance = Color::white();
+        for object in &self.objects {
+            transmittance = transmittance * object.transmittance(ray, t_min, t_max);
+            if transmittance.is_black() {
+                break;
+            }
+        }
+
+        transmittance
+    }
 }
@@ -0,0 +1,108 @@
+use crate::materials::material::Material;
+use crate::math::point3::Point3;
+use crate::math::ray::Ray;
+use crate::math::vector3::{cross, dot, unit_vector, Vector3};
+use crate::objects::aabb::Aabb;
+use crate::objects::hittable::{HitRecord, Hittable, Shape};
+use std::sync::Arc;
+
+/// A parallelogram with one corner at `corner` and sides `u` and `v`. The
+/// outward normal is along u × v, and texture coordinates run from zero to
+/// one along each side.
+pub struct Quad {
+    corner: Point3,
+    u: Vector3,
+    v: Vector3,
+    /// u × v, whose length is the area.
+    n: Vector3,
+    normal: Vector3,
+    material: Arc<dyn Material>,
+}
+
+impl Quad {
+    pub fn new(corner: Point3, u: Vector3, v: Vector3, material: Arc<dyn Material>) -> Self {
+        let n = cross(&u, &v);
+
+        Self {
+            corner,
+            u,
+            v,
+            n,
+            normal: unit_vector(&n),
+            material,
+        }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, hit_record: &mut HitRecord) -> bool {
+        let denominator = dot(&self.normal, &ray.direction);
+        if denominator.abs() < 1e-12 {
+            return false;
+        }
+
+        let t = dot(&self.normal, &(self.corner - ray.origin)) / denominator;
+        if t <= t_min || t >= t_max {
+            return false;
+        }
+
+        // Coordinates of the hit along the sides, from the plane point's
+        // offset from the corner.
+        let point = ray.at(t);
+        let offset = point - self.corner;
+        let n_squared = self.n.length_squared();
+        let alpha = dot(&self.n, &cross(&offset, &self.v)) / n_squared;
+        let beta = dot(&self.n, &cross(&self.u, &offset)) / n_squared;
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return false;
+        }
+
+        hit_record.t = t;
+        hit_record.point = point;
+        hit_record.set_face_normal(ray, &self.normal);
+        (hit_record.u, hit_record.v) = (alpha, beta);
+        (hit_record.dpdu, hit_record.dpdv) = (self.u, self.v);
+        hit_record.material = Some(self.material.clone());
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let corners = [
+            self.corner,
+            self.corner + self.u,
+            self.corner + self.v,
+            self.corner + self.u + self.v,
+        ];
+        let min = corners
+            .iter()
+            .fold(corners[0], |min, corner| min.min(corner));
+        let max = corners
+            .iter()
+            .fold(corners[0], |max, corner| max.max(corner));
+
+        Aabb::new(min, max).pad(1e-4)
+    }
+}
+
+impl Shape for Quad {
+    fn area(&self) -> f64 {
+        self.n.length()
+    }
+
+    fn sample_surface(&self, u: f64, v: f64) -> HitRecord {
+        let mut record = HitRecord::new();
+        record.point = self.corner + u * self.u + v * self.v;
+        record.normal = self.normal;
+        record.geometric_normal = self.normal;
+        (record.dpdu, record.dpdv) = (self.u, self.v);
+        (record.u, record.v) = (u, v);
+        record.front_face = true;
+
+        record
+    }
+
+    fn normal_bounds(&self) -> (Vector3, f64) {
+        (self.normal, 1.0)
+    }
+}
@@ -1,11 +1,15 @@
 use crate::materials::material::Material;
+use crate::math::point3::Point3;
 use crate::math::ray::Ray;
 use crate::math::vector3::{dot, Vector3};
-use crate::objects::hittable::{HitRecord, Hittable};
+use crate::objects::aabb::Aabb;
+use crate::objects::hittable::{HitRecord, Hittable, Shape};
+use std::f64::consts::PI;
 use std::sync::Arc;
 
 pub(crate) struct Sphere {
     pub center: Vector3,
+    pub motion: Vector3,
     pub radius: f64,
     pub material: Arc<dyn Material>,
 }
@@ -14,15 +18,36 @@ impl Sphere {
     pub fn new(center: Vector3, radius: f64, material: Arc<dyn Material>) -> Self {
         Self {
             center,
+            motion: Vector3::zero(),
             radius,
             material,
         }
     }
+
+    /// A sphere that moves linearly from `center0` at time 0 to `center1` at time 1.
+    pub fn new_moving(
+        center0: Vector3,
+        center1: Vector3,
+        radius: f64,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            center: center0,
+            motion: center1 - center0,
+            radius,
+            material,
+        }
+    }
+
+    pub fn center(&self, time: f64) -> Point3 {
+        self.center + time.clamp(0.0, 1.0) * self.motion
+    }
 }
 
 impl Hittable for Sphere {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, hit_record: &mut HitRecord) -> bool {
-        let oc = ray.origin - self.center;
+        let center = self.center(ray.time);
+        let oc = ray.origin - center;
         let a = ray.direction.length_squared();
         let half_b = dot(&oc, &ray.direction);
         let c = oc.length_squared() - self.radius * self.radius;
@@ -34,8 +59,11 @@ impl Hittable for Sphere {
             if temp < t_max && temp > t_min {
                 hit_record.t = temp;
                 hit_record.point = ray.at(hit_record.t);
-                let outward_normal = (hit_record.point - self.center) / self.radius;
+                let outward_normal = (hit_record.point - center) / self.radius;
                 hit_record.set_face_normal(ray, &outward_normal);
+                (hit_record.u, hit_record.v) = sphere_uv(&outward_normal);
+                (hit_record.dpdu, hit_record.dpdv) =
+                    sphere_derivatives(&(self.radius * outward_normal));
                 hit_record.material = Some(self.material.clone());
 
                 return true;
@@ -45,8 +73,11 @@ impl Hittable for Sphere {
             if temp < t_max && temp > t_min {
                 hit_record.t = temp;
                 hit_record.point = ray.at(hit_record.t);
-                let outward_normal = (hit_record.point - self.center) / self.radius;
+                let outward_normal = (hit_record.point - center) / self.radius;
                 hit_record.set_face_normal(ray, &outward_normal);
+                (hit_record.u, hit_record.v) = sphere_uv(&outward_normal);
+                (hit_record.dpdu, hit_record.dpdv) =
+                    sphere_derivatives(&(self.radius * outward_normal));
                 hit_record.material = Some(self.material.clone());
 
                 return true;
@@ -55,4 +86,59 @@ impl Hittable for Sphere {
 
         false
     }
+
+    fn bounding_box(&self) -> Aabb {
+        let radius = Vector3::new(self.radius, self.radius, self.radius);
+        let start = Aabb::new(self.center(0.0) - radius, self.center(0.0) + radius);
+        let end = Aabb::new(self.center(1.0) - radius, self.center(1.0) + radius);
+
+        Aabb::surrounding(&start, &end)
+    }
+}
+
+/// Moving spheres are sampled where they are at time zero.
+impl Shape for Sphere {
+    fn area(&self) -> f64 {
+        4.0 * PI * self.radius * self.radius
+    }
+
+    fn sample_surface(&self, u: f64, v: f64) -> HitRecord {
+        let y = 1.0 - 2.0 * u;
+        let r = (1.0 - y * y).max(0.0).sqrt();
+        let phi = 2.0 * PI * v;
+        let outward_normal = Vector3::new(r * phi.cos(), y, r * phi.sin());
+
+        let mut record = HitRecord::new();
+        record.point = self.center(0.0) + self.radius * outward_normal;
+        record.normal = outward_normal;
+        record.geometric_normal = outward_normal;
+        (record.u, record.v) = sphere_uv(&outward_normal);
+        (record.dpdu, record.dpdv) = sphere_derivatives(&(self.radius * outward_normal));
+        record.front_face = true;
+
+        record
+    }
+}
+
+/// Maps a point on the unit sphere to (u, v), with u running around the Y
+/// axis starting at -X and v running from the bottom pole to the top pole.
+fn sphere_uv(p: &Point3) -> (f64, f64) {
+    let theta = (-p.y()).acos();
+    let phi = (-p.z()).atan2(p.x()) + PI;
+
+    (phi / (2.0 * PI), theta / PI)
+}
+
+/// Derivatives of a point `p` relative to the center with respect to the
+/// (u, v) of `sphere_uv`. Both vanish at the poles, where u is undefined.
+fn sphere_derivatives(p: &Vector3) -> (Vector3, Vector3) {
+    let rho = (p.x() * p.x() + p.z() * p.z()).sqrt();
+    if rho == 0.0 {
+        return (Vector3::zero(), Vector3::zero());
+    }
+
+    let dpdu = 2.0 * PI * Vector3::new(p.z(), 0.0, -p.x());
+    let dpdv = PI * Vector3::new(-p.x() * p.y() / rho, rho, -p.y() * p.z() / rho);
+
+    (dpdu, dpdv)
 }
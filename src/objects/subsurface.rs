@@ -0,0 +1,361 @@
+use crate::materials::material::{reflect, Material};
+use crate::materials::microfacet::fresnel_dielectric;
+use crate::materials::phase_function::HenyeyGreenstein;
+use crate::math::color::Color;
+use crate::math::point3::Point3;
+use crate::math::random;
+use crate::math::ray::{random_unit_vector, Ray};
+use crate::math::vector3::{dot, unit_vector, Vector3};
+use crate::objects::aabb::Aabb;
+use crate::objects::hittable::{HitRecord, Hittable};
+use rand::Rng;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// Offset keeping a walk segment from finding the surface it starts on.
+const WALK_EPSILON: f64 = 1e-4;
+
+/// Scattering events after which a walk is given up as absorbed.
+const MAX_WALK_STEPS: u32 = 256;
+
+/// How far back inside the surface a walk's way out starts. It is further
+/// than the tracer's smallest hit distance, so the surface is found again
+/// and light leaves from a hit of its own, where lights are sampled.
+const EXIT_OFFSET: f64 = 2e-3;
+
+/// Subsurface scattering (skin, wax, marble, milk) inside a closed boundary.
+/// Light entering the surface takes a random walk through a dense medium
+/// filling it and leaves wherever the walk reaches the boundary again, so
+/// thin parts glow and hard lighting softens.
+///
+/// The walk traces against the boundary itself, so transforms belong inside
+/// it rather than around the `Subsurface`. Where the boundary turns out not
+/// to be closed (a walk finds no way out, or meets an outside face), the
+/// surface falls back to diffuse reflection.
+pub struct Subsurface {
+    boundary: Arc<dyn Hittable>,
+    walk: Arc<RandomWalk>,
+}
+
+impl Subsurface {
+    /// `albedo` is the color of the surface as a whole, after any amount of
+    /// scattering inside, and `mean_free_path` the average distance between
+    /// events per channel in world units: larger values let that channel
+    /// travel further, like red in skin.
+    pub fn new(boundary: Box<dyn Hittable>, albedo: Color, mean_free_path: Color) -> Self {
+        let boundary: Arc<dyn Hittable> = Arc::from(boundary);
+        let walk = RandomWalk {
+            boundary: boundary.clone(),
+            albedo,
+            single_scattering_albedo: single_scattering_albedo(&albedo),
+            extinction: Color::new(
+                1.0 / mean_free_path.r().max(1e-6),
+                1.0 / mean_free_path.g().max(1e-6),
+                1.0 / mean_free_path.b().max(1e-6),
+            ),
+            phase_function: HenyeyGreenstein::new(Color::white(), 0.0),
+            index_of_refraction: 1.4,
+        };
+
+        Self {
+            boundary,
+            walk: Arc::new(walk),
+        }
+    }
+
+    /// Mean cosine of the scattering angle inside, as for
+    /// `HenyeyGreenstein`. Skin scatters strongly forward, around 0.8.
+    pub fn anisotropy(self, g: f64) -> Self {
+        self.with_walk(|walk| RandomWalk {
+            phase_function: HenyeyGreenstein::new(Color::white(), g),
+            ..walk
+        })
+    }
+
+    /// Index of refraction of the surface, which sets the strength of its
+    /// specular reflection.
+    pub fn ior(self, index_of_refraction: f64) -> Self {
+        self.with_walk(|walk| RandomWalk {
+            index_of_refraction,
+            ..walk
+        })
+    }
+
+    /// Builds the walk afresh from a changed copy of the current one.
+    fn with_walk(self, change: impl FnOnce(RandomWalk) -> RandomWalk) -> Self {
+        let walk = change(RandomWalk::clone(&self.walk));
+
+        Self {
+            walk: Arc::new(walk),
+            ..self
+        }
+    }
+}
+
+impl Hittable for Subsurface {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, hit_record: &mut HitRecord) -> bool {
+        if !self.boundary.hit(ray, t_min, t_max, hit_record) {
+            return false;
+        }
+
+        hit_record.material = Some(self.walk.clone());
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+}
+
+/// Where a walk through the interior ended up.
+enum WalkEnd {
+    /// Left through the boundary at this point and outward normal.
+    Exit(Point3, Vector3),
+    Absorbed,
+    /// The boundary isn't closed around the walk.
+    Open,
+}
+
+/// The material handed out for hits on a `Subsurface` boundary. It runs the
+/// whole walk, from entry to exit, within one call to `scatter`.
+#[derive(Clone)]
+struct RandomWalk {
+    boundary: Arc<dyn Hittable>,
+    albedo: Color,
+    single_scattering_albedo: Color,
+    extinction: Color,
+    phase_function: HenyeyGreenstein,
+    index_of_refraction: f64,
+}
+
+impl RandomWalk {
+    /// Follows light from `entry` along `direction` through the medium,
+    /// accumulating its throughput, until it reaches the boundary again.
+    fn walk(
+        &self,
+        entry: Point3,
+        direction: Vector3,
+        time: f64,
+        throughput: &mut Color,
+    ) -> WalkEnd {
+        let mut rng = random::rng();
+        let mut segment = Ray::new(entry, direction, time);
+
+        for _ in 0..MAX_WALK_STEPS {
+            let mut exit = HitRecord::new();
+            if !self
+                .boundary
+                .hit(&segment, WALK_EPSILON, f64::INFINITY, &mut exit)
+                || exit.front_face
+            {
+                return WalkEnd::Open;
+            }
+
+            // Sample the free flight with one channel's density, picked in
+            // proportion to its throughput so far, and weight by the mixture
+            // of all three so every channel stays unbiased whichever was
+            // picked.
+            let weights = [throughput.r(), throughput.g(), throughput.b()];
+            let total: f64 = weights.iter().sum();
+            if total <= 0.0 {
+                return WalkEnd::Absorbed;
+            }
+            let probabilities = weights.map(|weight| weight / total);
+            let mut u = rng.random_range(0.0..1.0);
+            let mut channel = 2;
+            for (i, probability) in probabilities.iter().enumerate() {
+                if u < *probability {
+                    channel = i;
+                    break;
+                }
+                u -= probability;
+            }
+            let sigma_t = [
+                self.extinction.r(),
+                self.extinction.g(),
+                self.extinction.b(),
+            ][channel];
+            let distance = -rng.random_range(f64::EPSILON..1.0).ln() / sigma_t;
+
+            let scatters = distance < exit.t;
+            let travelled = if scatters { distance } else { exit.t };
+            let transmittance = Color::new(
+                (-self.extinction.r() * travelled).exp(),
+                (-self.extinction.g() * travelled).exp(),
+                (-self.extinction.b() * travelled).exp(),
+            );
+            let mixture = |per_channel: &Color| {
+                probabilities[0] * per_channel.r()
+                    + probabilities[1] * per_channel.g()
+                    + probabilities[2] * per_channel.b()
+            };
+
+            if !scatters {
+                // Probability of the flight passing the boundary.
+                *throughput = *throughput * transmittance / mixture(&transmittance);
+                return WalkEnd::Exit(exit.point, -exit.geometric_normal);
+            }
+
+            let density = self.extinction * transmittance;
+            *throughput = *throughput * self.single_scattering_albedo * density / mixture(&density);
+
+            let mut event = HitRecord::new();
+            event.point = segment.at(distance);
+            let mut attenuation = Color::white();
+            let mut next = Ray::new(event.point, Vector3::zero(), time);
+            self.phase_function
+                .scatter(&segment, &event, &mut attenuation, &mut next);
+            segment = Ray::new(event.point, unit_vector(&next.direction), time);
+        }
+
+        WalkEnd::Absorbed
+    }
+}
+
+impl Material for RandomWalk {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        // Hit from inside, which is how walks come out (and how a camera in
+        // the wax sees the surface), light leaves diffusely.
+        if !record.front_face {
+            *scattered = Ray::new(record.point, diffuse_direction(&-record.normal), ray.time);
+            *attenuation = Color::white();
+            return true;
+        }
+
+        // Neither the reflection nor the walk can be evaluated here, so
+        // both are marked specular.
+        let mut rng = random::rng();
+        let unit_direction = unit_vector(&ray.direction);
+        let cos_theta = dot(&-unit_direction, &record.normal).min(1.0);
+        if rng.random_range(0.0..1.0) < fresnel_dielectric(cos_theta, self.index_of_refraction) {
+            let reflected = reflect(&unit_direction, &record.normal);
+            *scattered = Ray::new(record.point, reflected, ray.time).specular(true);
+            *attenuation = Color::white();
+            return true;
+        }
+
+        let mut throughput = Color::white();
+        let inward = diffuse_direction(&-record.geometric_normal);
+        let (point, normal) = match self.walk(record.point, inward, ray.time, &mut throughput) {
+            WalkEnd::Exit(point, normal) => (point, normal),
+            WalkEnd::Absorbed => return false,
+            WalkEnd::Open => {
+                throughput = self.albedo;
+                (record.point, record.normal)
+            }
+        };
+        *scattered = Ray::new(point - EXIT_OFFSET * normal, normal, ray.time).specular(true);
+        *attenuation = throughput;
+
+        true
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: &Vector3) -> Color {
+        self.pdf(ray, record, direction) * Color::white()
+    }
+
+    fn pdf(&self, _ray: &Ray, record: &HitRecord, direction: &Vector3) -> f64 {
+        if record.front_face {
+            return 0.0;
+        }
+
+        dot(&unit_vector(direction), &-record.normal).max(0.0) / PI
+    }
+}
+
+/// Cosine-distributed unit direction about `normal`.
+fn diffuse_direction(normal: &Vector3) -> Vector3 {
+    let direction = *normal + random_unit_vector();
+    if direction.length_squared() < 1e-12 {
+        return *normal;
+    }
+
+    unit_vector(&direction)
+}
+
+/// Inverts the surface albedo of a semi-infinite slab for the medium's
+/// single-scattering albedo, using the fit from Chiang et al., "Practical
+/// and Controllable Subsurface Scattering for Production Path Tracing"
+/// (2016).
+fn single_scattering_albedo(albedo: &Color) -> Color {
+    let invert = |a: f64| {
+        let a = a.clamp(0.0, 0.999);
+        let s = 4.09712 + 4.20863 * a - (9.59217 + 41.6808 * a + 17.7126 * a * a).sqrt();
+        (1.0 - s * s).clamp(0.0, 1.0)
+    };
+
+    Color::new(invert(albedo.r()), invert(albedo.g()), invert(albedo.b()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::material::Lambertian;
+    use crate::objects::quad::Quad;
+    use crate::objects::sphere::Sphere;
+
+    fn sphere() -> Box<dyn Hittable> {
+        let material = Arc::new(Lambertian::new(Color::white()));
+        Box::new(Sphere::new(Point3::zero(), 1.0, material))
+    }
+
+    /// Changing one surface mustn't reach another that shares its walk.
+    #[test]
+    fn builders_leave_shared_walks_alone() {
+        let original = Subsurface::new(sphere(), Color::new(0.8, 0.6, 0.5), Color::white());
+        let changed = Subsurface {
+            boundary: original.boundary.clone(),
+            walk: original.walk.clone(),
+        }
+        .ior(2.0)
+        .anisotropy(0.8);
+
+        assert_eq!(original.walk.index_of_refraction, 1.4);
+        assert_eq!(original.walk.phase_function.g, 0.0);
+        assert_eq!(changed.walk.index_of_refraction, 2.0);
+        assert_eq!(changed.walk.phase_function.g, 0.8);
+    }
+
+    /// A walk into a lone quad finds no way out, so the light comes back
+    /// with the surface's albedo.
+    #[test]
+    fn open_boundaries_fall_back_to_the_albedo() {
+        let material = Arc::new(Lambertian::new(Color::white()));
+        let quad = Quad::new(
+            Point3::new(-1.0, -1.0, 0.0),
+            Vector3::new(2.0, 0.0, 0.0),
+            Vector3::new(0.0, 2.0, 0.0),
+            material,
+        );
+        let albedo = Color::new(0.8, 0.6, 0.5);
+        // Matched indices, so nothing is reflected at the surface.
+        let surface = Subsurface::new(Box::new(quad), albedo, Color::white()).ior(1.0);
+
+        random::seed(39);
+        let ray = Ray::new(
+            Point3::new(0.2, 0.1, 1.0),
+            Vector3::new(0.0, 0.0, -1.0),
+            0.0,
+        );
+        let mut record = HitRecord::new();
+        assert!(surface.hit(&ray, 0.001, f64::INFINITY, &mut record));
+        let material = record.material.clone().unwrap();
+        for _ in 0..100 {
+            let mut attenuation = Color::black();
+            let mut scattered = Ray::new(Point3::zero(), Vector3::zero(), 0.0);
+            assert!(material.scatter(&ray, &record, &mut attenuation, &mut scattered));
+
+            assert_eq!(
+                [attenuation.r(), attenuation.g(), attenuation.b()],
+                [albedo.r(), albedo.g(), albedo.b()]
+            );
+            assert!(dot(&scattered.direction, &record.normal) > 0.0);
+        }
+    }
+}
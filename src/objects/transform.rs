@@ -0,0 +1,151 @@
+use crate::math::color::Color;
+use crate::math::quaternion::Quaternion;
+use crate::math::ray::Ray;
+use crate::math::vector3::{unit_vector, Vector3};
+use crate::objects::aabb::Aabb;
+use crate::objects::hittable::{HitRecord, Hittable};
+
+#[derive(Copy, Clone)]
+pub struct Keyframe {
+    pub time: f64,
+    pub translation: Vector3,
+    pub rotation: Quaternion,
+    pub scale: f64,
+}
+
+impl Keyframe {
+    pub fn new(time: f64, translation: Vector3, rotation: Quaternion, scale: f64) -> Self {
+        Self {
+            time,
+            translation,
+            rotation: rotation.normalized(),
+            scale,
+        }
+    }
+
+    fn lerp(&self, other: &Keyframe, time: f64) -> Keyframe {
+        let t = (time - self.time) / (other.time - self.time);
+
+        Keyframe {
+            time,
+            translation: self.translation + t * (other.translation - self.translation),
+            rotation: self.rotation.slerp(&other.rotation, t),
+            scale: self.scale + t * (other.scale - self.scale),
+        }
+    }
+
+    fn apply(&self, p: &Vector3) -> Vector3 {
+        self.scale * self.rotation.rotate(p) + self.translation
+    }
+}
+
+/// Places an object with a translation, rotation and uniform scale that are
+/// interpolated between keyframes over the shutter interval. Rotations are
+/// slerped, translations and scales are interpolated linearly.
+pub struct KeyframedTransform {
+    object: Box<dyn Hittable>,
+    keyframes: Vec<Keyframe>,
+    bbox: Aabb,
+}
+
+impl KeyframedTransform {
+    pub fn new(object: Box<dyn Hittable>, mut keyframes: Vec<Keyframe>) -> Self {
+        assert!(
+            !keyframes.is_empty(),
+            "a transform needs at least one keyframe"
+        );
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        let bbox = motion_bounds(&object.bounding_box(), &keyframes);
+
+        Self {
+            object,
+            keyframes,
+            bbox,
+        }
+    }
+
+    fn keyframe_at(&self, time: f64) -> Keyframe {
+        let first = &self.keyframes[0];
+        if time <= first.time {
+            return *first;
+        }
+
+        for pair in self.keyframes.windows(2) {
+            if time <= pair[1].time {
+                return pair[0].lerp(&pair[1], time);
+            }
+        }
+
+        *self.keyframes.last().unwrap()
+    }
+
+    /// The object-space ray keeps the same parametrization, so t carries over.
+    fn object_ray(&self, keyframe: &Keyframe, ray: &Ray) -> Ray {
+        let inverse = keyframe.rotation.conjugate();
+        let origin = inverse.rotate(&(ray.origin - keyframe.translation)) / keyframe.scale;
+        let direction = inverse.rotate(&ray.direction) / keyframe.scale;
+
+        Ray::new(origin, direction, ray.time)
+    }
+}
+
+impl Hittable for KeyframedTransform {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, hit_record: &mut HitRecord) -> bool {
+        let keyframe = self.keyframe_at(ray.time);
+        let object_ray = self.object_ray(&keyframe, ray);
+
+        if !self.object.hit(&object_ray, t_min, t_max, hit_record) {
+            return false;
+        }
+
+        hit_record.point = keyframe.apply(&hit_record.point);
+        hit_record.normal = unit_vector(&keyframe.rotation.rotate(&hit_record.normal));
+        hit_record.geometric_normal =
+            unit_vector(&keyframe.rotation.rotate(&hit_record.geometric_normal));
+        hit_record.dpdu = keyframe.scale * keyframe.rotation.rotate(&hit_record.dpdu);
+        hit_record.dpdv = keyframe.scale * keyframe.rotation.rotate(&hit_record.dpdv);
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> Color {
+        let keyframe = self.keyframe_at(ray.time);
+
+        self.object
+            .transmittance(&self.object_ray(&keyframe, ray), t_min, t_max)
+    }
+}
+
+fn motion_bounds(object_bbox: &Aabb, keyframes: &[Keyframe]) -> Aabb {
+    if object_bbox.is_empty() {
+        return *object_bbox;
+    }
+
+    if keyframes.len() == 1 {
+        return (0..8).fold(Aabb::empty(), |bbox, i| {
+            let corner = keyframes[0].apply(&object_bbox.corner(i));
+            Aabb::surrounding(&bbox, &Aabb::new(corner, corner))
+        });
+    }
+
+    // While rotating, every point of the object stays within
+    // `scale * (|centroid| + half diagonal)` of the translation, and the
+    // translation moves along straight segments between keyframes, so boxes
+    // around the keyframe translations bound the whole motion.
+    let reach = object_bbox.centroid().length() + object_bbox.extent().length() / 2.0;
+    let max_scale = keyframes
+        .iter()
+        .fold(0.0f64, |scale, keyframe| scale.max(keyframe.scale.abs()));
+    let radius = max_scale * reach;
+    let radius = Vector3::new(radius, radius, radius);
+
+    keyframes.iter().fold(Aabb::empty(), |bbox, keyframe| {
+        let around = Aabb::new(keyframe.translation - radius, keyframe.translation + radius);
+        Aabb::surrounding(&bbox, &around)
+    })
+}
@@ -0,0 +1,135 @@
+use crate::materials::material::Material;
+use crate::math::point3::Point3;
+use crate::math::ray::Ray;
+use crate::math::vector3::{cross, dot, unit_vector, Vector3};
+use crate::objects::aabb::Aabb;
+use crate::objects::hittable::{HitRecord, Hittable, Shape};
+use std::sync::Arc;
+
+/// A single triangle with per-vertex texture coordinates. The outward
+/// normal follows the counter-clockwise winding of `vertices`.
+pub struct Triangle {
+    vertices: [Point3; 3],
+    uvs: [(f64, f64); 3],
+    material: Arc<dyn Material>,
+}
+
+impl Triangle {
+    pub fn new(p0: Point3, p1: Point3, p2: Point3, material: Arc<dyn Material>) -> Self {
+        Self {
+            vertices: [p0, p1, p2],
+            uvs: [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)],
+            material,
+        }
+    }
+
+    pub fn uvs(mut self, uv0: (f64, f64), uv1: (f64, f64), uv2: (f64, f64)) -> Self {
+        self.uvs = [uv0, uv1, uv2];
+        self
+    }
+
+    /// Solves for the surface derivatives from the edges and their UV
+    /// differences. Degenerate UV layouts have none.
+    fn derivatives(&self) -> (Vector3, Vector3) {
+        let [p0, p1, p2] = self.vertices;
+        let [(u0, v0), (u1, v1), (u2, v2)] = self.uvs;
+        let (du1, dv1) = (u1 - u0, v1 - v0);
+        let (du2, dv2) = (u2 - u0, v2 - v0);
+
+        let determinant = du1 * dv2 - dv1 * du2;
+        if determinant.abs() < 1e-12 {
+            return (Vector3::zero(), Vector3::zero());
+        }
+
+        let e1 = p1 - p0;
+        let e2 = p2 - p0;
+        let dpdu = (dv2 * e1 - dv1 * e2) / determinant;
+        let dpdv = (du1 * e2 - du2 * e1) / determinant;
+
+        (dpdu, dpdv)
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, hit_record: &mut HitRecord) -> bool {
+        // Möller-Trumbore.
+        let [p0, p1, p2] = self.vertices;
+        let e1 = p1 - p0;
+        let e2 = p2 - p0;
+        let p = cross(&ray.direction, &e2);
+        let determinant = dot(&e1, &p);
+        if determinant.abs() < 1e-12 {
+            return false;
+        }
+
+        let inverse = 1.0 / determinant;
+        let s = ray.origin - p0;
+        let b1 = dot(&s, &p) * inverse;
+        if !(0.0..=1.0).contains(&b1) {
+            return false;
+        }
+
+        let q = cross(&s, &e1);
+        let b2 = dot(&ray.direction, &q) * inverse;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return false;
+        }
+
+        let t = dot(&e2, &q) * inverse;
+        if t <= t_min || t >= t_max {
+            return false;
+        }
+
+        let b0 = 1.0 - b1 - b2;
+        let [(u0, v0), (u1, v1), (u2, v2)] = self.uvs;
+
+        hit_record.t = t;
+        hit_record.point = ray.at(t);
+        hit_record.set_face_normal(ray, &unit_vector(&cross(&e1, &e2)));
+        hit_record.u = b0 * u0 + b1 * u1 + b2 * u2;
+        hit_record.v = b0 * v0 + b1 * v1 + b2 * v2;
+        (hit_record.dpdu, hit_record.dpdv) = self.derivatives();
+        hit_record.material = Some(self.material.clone());
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let [p0, p1, p2] = self.vertices;
+        Aabb::new(p0.min(&p1).min(&p2), p0.max(&p1).max(&p2)).pad(1e-4)
+    }
+}
+
+impl Shape for Triangle {
+    fn area(&self) -> f64 {
+        let [p0, p1, p2] = self.vertices;
+        0.5 * cross(&(p1 - p0), &(p2 - p0)).length()
+    }
+
+    fn sample_surface(&self, u: f64, v: f64) -> HitRecord {
+        // Folding the unit square onto the triangle with a square root keeps
+        // the density uniform.
+        let root = u.sqrt();
+        let (b1, b2) = (root * (1.0 - v), root * v);
+        let b0 = 1.0 - b1 - b2;
+        let [p0, p1, p2] = self.vertices;
+        let [(u0, v0), (u1, v1), (u2, v2)] = self.uvs;
+        let normal = unit_vector(&cross(&(p1 - p0), &(p2 - p0)));
+
+        let mut record = HitRecord::new();
+        record.point = b0 * p0 + b1 * p1 + b2 * p2;
+        record.normal = normal;
+        record.geometric_normal = normal;
+        record.u = b0 * u0 + b1 * u1 + b2 * u2;
+        record.v = b0 * v0 + b1 * v1 + b2 * v2;
+        (record.dpdu, record.dpdv) = self.derivatives();
+        record.front_face = true;
+
+        record
+    }
+
+    fn normal_bounds(&self) -> (Vector3, f64) {
+        let [p0, p1, p2] = self.vertices;
+        (unit_vector(&cross(&(p1 - p0), &(p2 - p0))), 1.0)
+    }
+}
@@ -0,0 +1,163 @@
+use crate::images::error::ImageError;
+use crate::images::image::{ColorSpace, Image};
+use crate::images::loader::load_image;
+use crate::math::color::Color;
+use crate::math::point3::Point3;
+use crate::math::vector3::Vector3;
+use crate::textures::texture::Texture;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(Copy, Clone)]
+pub enum WrapMode {
+    Repeat,
+    ClampToEdge,
+}
+
+impl WrapMode {
+    fn apply(&self, index: i64, size: u32) -> u32 {
+        let size = size as i64;
+        let wrapped = match self {
+            WrapMode::Repeat => index.rem_euclid(size),
+            WrapMode::ClampToEdge => index.clamp(0, size - 1),
+        };
+
+        wrapped as u32
+    }
+}
+
+#[derive(Copy, Clone)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+}
+
+pub struct ImageTexture {
+    image: Arc<Image>,
+    wrap_u: WrapMode,
+    wrap_v: WrapMode,
+    filter: Filter,
+    /// Reads the alpha channel as a grey value instead of the color.
+    alpha: bool,
+}
+
+impl ImageTexture {
+    pub fn new(image: Arc<Image>) -> Self {
+        Self {
+            image,
+            wrap_u: WrapMode::Repeat,
+            wrap_v: WrapMode::ClampToEdge,
+            filter: Filter::Bilinear,
+            alpha: false,
+        }
+    }
+
+    /// The same image, wrapping and filtering, but returning alpha (which is
+    /// stored linearly) as grey. Images without alpha read as opaque.
+    pub fn alpha(&self) -> Self {
+        Self {
+            image: self.image.clone(),
+            wrap_u: self.wrap_u,
+            wrap_v: self.wrap_v,
+            filter: self.filter,
+            alpha: true,
+        }
+    }
+
+    pub fn wrap(mut self, wrap_u: WrapMode, wrap_v: WrapMode) -> Self {
+        self.wrap_u = wrap_u;
+        self.wrap_v = wrap_v;
+        self
+    }
+
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Color {
+        let x = self.wrap_u.apply(x, self.image.width());
+        let y = self.wrap_v.apply(y, self.image.height());
+
+        if self.alpha {
+            let alpha = self.image.alpha(x, y);
+            return Color::new(alpha, alpha, alpha);
+        }
+        self.image.pixel(x, y)
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _point: &Point3, _normal: &Vector3) -> Color {
+        if self.image.width() == 0 || self.image.height() == 0 {
+            return Color::new(0.0, 1.0, 1.0);
+        }
+
+        // Image rows run top to bottom while v runs bottom to top.
+        let x = u * self.image.width() as f64;
+        let y = (1.0 - v) * self.image.height() as f64;
+
+        match self.filter {
+            Filter::Nearest => self.texel(x.floor() as i64, y.floor() as i64),
+            Filter::Bilinear => {
+                // Texel centers sit at half-integer coordinates.
+                let x = x - 0.5;
+                let y = y - 0.5;
+                let x0 = x.floor();
+                let y0 = y.floor();
+                let tx = x - x0;
+                let ty = y - y0;
+                let x0 = x0 as i64;
+                let y0 = y0 as i64;
+
+                let top = self.texel(x0, y0).lerp(&self.texel(x0 + 1, y0), tx);
+                let bottom = self.texel(x0, y0 + 1).lerp(&self.texel(x0 + 1, y0 + 1), tx);
+
+                top.lerp(&bottom, ty)
+            }
+        }
+    }
+}
+
+/// Loads each image file once and hands out the same texture to every
+/// material that refers to it.
+pub struct TextureCache {
+    textures: HashMap<(PathBuf, ColorSpace), Arc<ImageTexture>>,
+}
+
+impl TextureCache {
+    pub fn new() -> Self {
+        Self {
+            textures: HashMap::new(),
+        }
+    }
+
+    /// Loads a color texture, decoding 8- and 16-bit files from sRGB.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<Arc<ImageTexture>, ImageError> {
+        self.load_as(path.as_ref(), ColorSpace::Srgb)
+    }
+
+    /// Loads a roughness, height or normal map, whose samples are read as
+    /// stored so they filter as the values they encode.
+    pub fn load_data(&mut self, path: impl AsRef<Path>) -> Result<Arc<ImageTexture>, ImageError> {
+        self.load_as(path.as_ref(), ColorSpace::Linear)
+    }
+
+    fn load_as(
+        &mut self,
+        path: &Path,
+        color_space: ColorSpace,
+    ) -> Result<Arc<ImageTexture>, ImageError> {
+        let key = (path.canonicalize()?, color_space);
+        if let Some(texture) = self.textures.get(&key) {
+            return Ok(texture.clone());
+        }
+
+        let image = load_image(path, color_space)?;
+        let texture = Arc::new(ImageTexture::new(Arc::new(image)));
+        self.textures.insert(key, texture.clone());
+
+        Ok(texture)
+    }
+}
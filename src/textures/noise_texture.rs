@@ -0,0 +1,127 @@
+use crate::math::color::Color;
+use crate::math::noise::{fbm, turbulence, Noise, Perlin, Simplex, Worley};
+use crate::math::point3::Point3;
+use crate::math::vector3::Vector3;
+use crate::textures::texture::Texture;
+use rand::Rng;
+
+/// Plain noise mapped from [-1, 1] to a gray level.
+pub struct NoiseTexture {
+    noise: Box<dyn Noise>,
+    scale: f64,
+}
+
+impl NoiseTexture {
+    pub fn perlin(scale: f64, rng: &mut impl Rng) -> Self {
+        Self {
+            noise: Box::new(Perlin::new(rng)),
+            scale,
+        }
+    }
+
+    pub fn simplex(scale: f64, rng: &mut impl Rng) -> Self {
+        Self {
+            noise: Box::new(Simplex::new(rng)),
+            scale,
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, point: &Point3, _normal: &Vector3) -> Color {
+        let n = self.noise.noise(&(self.scale * *point));
+
+        0.5 * (1.0 + n) * Color::white()
+    }
+}
+
+/// Veins across the X axis, bent by turbulence.
+pub struct MarbleTexture {
+    noise: Perlin,
+    scale: f64,
+    base: Color,
+    vein: Color,
+}
+
+impl MarbleTexture {
+    pub fn new(scale: f64, base: Color, vein: Color, rng: &mut impl Rng) -> Self {
+        Self {
+            noise: Perlin::new(rng),
+            scale,
+            base,
+            vein,
+        }
+    }
+}
+
+impl Texture for MarbleTexture {
+    fn value(&self, _u: f64, _v: f64, point: &Point3, _normal: &Vector3) -> Color {
+        let turb = turbulence(&self.noise, point, 7);
+        let t = 0.5 * (1.0 + (self.scale * point.x() + 5.0 * turb).sin());
+
+        self.vein.lerp(&self.base, t)
+    }
+}
+
+/// Concentric growth rings around the Y axis, perturbed by fBm so they wobble.
+pub struct WoodTexture {
+    noise: Perlin,
+    rings_per_unit: f64,
+    light: Color,
+    dark: Color,
+}
+
+impl WoodTexture {
+    pub fn new(rings_per_unit: f64, light: Color, dark: Color, rng: &mut impl Rng) -> Self {
+        Self {
+            noise: Perlin::new(rng),
+            rings_per_unit,
+            light,
+            dark,
+        }
+    }
+}
+
+impl Texture for WoodTexture {
+    fn value(&self, _u: f64, _v: f64, point: &Point3, _normal: &Vector3) -> Color {
+        let radius = (point.x() * point.x() + point.z() * point.z()).sqrt();
+        let wobble = fbm(&self.noise, &(2.0 * *point), 4, 2.0, 0.5);
+        let rings = self.rings_per_unit * radius + 0.6 * wobble;
+        let t = (rings - rings.floor()).powf(3.0);
+
+        self.light.lerp(&self.dark, t)
+    }
+}
+
+/// Speckled crystals from cellular noise over a mottled fBm base.
+pub struct GraniteTexture {
+    cells: Worley,
+    noise: Simplex,
+    scale: f64,
+    base: Color,
+    speckle: Color,
+}
+
+impl GraniteTexture {
+    pub fn new(scale: f64, base: Color, speckle: Color, rng: &mut impl Rng) -> Self {
+        Self {
+            cells: Worley::new(rng),
+            noise: Simplex::new(rng),
+            scale,
+            base,
+            speckle,
+        }
+    }
+}
+
+impl Texture for GraniteTexture {
+    fn value(&self, _u: f64, _v: f64, point: &Point3, _normal: &Vector3) -> Color {
+        let p = self.scale * *point;
+        let (f1, f2) = self.cells.distances(&p);
+        let edge = ((f2 - f1) * 4.0).clamp(0.0, 1.0);
+        let mottle = 0.5 * (1.0 + fbm(&self.noise, &(4.0 * p), 5, 2.0, 0.5));
+
+        let crystal = self.speckle.lerp(&self.base, edge);
+        (0.75 + 0.25 * mottle) * crystal
+    }
+}
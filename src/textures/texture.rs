@@ -0,0 +1,143 @@
+use crate::math::color::Color;
+use crate::math::point3::Point3;
+use crate::math::vector3::{dot, Vector3};
+use std::sync::Arc;
+
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f64, v: f64, point: &Point3, normal: &Vector3) -> Color;
+}
+
+pub struct SolidColor {
+    albedo: Color,
+}
+
+impl SolidColor {
+    pub fn new(albedo: Color) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _point: &Point3, _normal: &Vector3) -> Color {
+        self.albedo
+    }
+}
+
+/// Alternates two textures in a 3D grid of cubes with side `scale`.
+pub struct CheckerTexture {
+    inv_scale: f64,
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+}
+
+impl CheckerTexture {
+    pub fn new(scale: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        Self {
+            inv_scale: 1.0 / scale,
+            even,
+            odd,
+        }
+    }
+
+    pub fn from_colors(scale: f64, even: Color, odd: Color) -> Self {
+        Self::new(
+            scale,
+            Arc::new(SolidColor::new(even)),
+            Arc::new(SolidColor::new(odd)),
+        )
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, point: &Point3, normal: &Vector3) -> Color {
+        let x = (self.inv_scale * point.x()).floor() as i64;
+        let y = (self.inv_scale * point.y()).floor() as i64;
+        let z = (self.inv_scale * point.z()).floor() as i64;
+
+        if (x + y + z) % 2 == 0 {
+            self.even.value(u, v, point, normal)
+        } else {
+            self.odd.value(u, v, point, normal)
+        }
+    }
+}
+
+/// Alternates two textures in a grid over the surface parametrization, so
+/// the pattern follows the object instead of world space.
+pub struct UvCheckerTexture {
+    u_count: f64,
+    v_count: f64,
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+}
+
+impl UvCheckerTexture {
+    pub fn new(u_count: u32, v_count: u32, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        Self {
+            u_count: u_count as f64,
+            v_count: v_count as f64,
+            even,
+            odd,
+        }
+    }
+
+    pub fn from_colors(u_count: u32, v_count: u32, even: Color, odd: Color) -> Self {
+        Self::new(
+            u_count,
+            v_count,
+            Arc::new(SolidColor::new(even)),
+            Arc::new(SolidColor::new(odd)),
+        )
+    }
+}
+
+impl Texture for UvCheckerTexture {
+    fn value(&self, u: f64, v: f64, point: &Point3, normal: &Vector3) -> Color {
+        let i = (u * self.u_count).floor() as i64;
+        let j = (v * self.v_count).floor() as i64;
+
+        if (i + j) % 2 == 0 {
+            self.even.value(u, v, point, normal)
+        } else {
+            self.odd.value(u, v, point, normal)
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub enum GradientAxis {
+    V,
+    /// Distance along `direction` from `origin`, reaching `end` after `length`.
+    Linear {
+        origin: Point3,
+        direction: Vector3,
+        length: f64,
+    },
+}
+
+pub struct GradientTexture {
+    start: Color,
+    end: Color,
+    axis: GradientAxis,
+}
+
+impl GradientTexture {
+    pub fn new(start: Color, end: Color, axis: GradientAxis) -> Self {
+        Self { start, end, axis }
+    }
+}
+
+impl Texture for GradientTexture {
+    fn value(&self, _u: f64, v: f64, point: &Point3, _normal: &Vector3) -> Color {
+        let t = match self.axis {
+            GradientAxis::V => v,
+            GradientAxis::Linear {
+                origin,
+                direction,
+                length,
+            } => dot(&(*point - origin), &direction) / (direction.length() * length),
+        };
+
+        self.start.lerp(&self.end, t.clamp(0.0, 1.0))
+    }
+}